@@ -0,0 +1,44 @@
+//! Analyses of a log with many nested subgraphs (one per recursive call).
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use rayon_logs::{join, subgraph, RunLog, ThreadPoolBuilder};
+
+fn fibonacci(n: u64) -> u64 {
+    subgraph("fibonacci", n as usize, || {
+        if n < 2 {
+            n
+        } else {
+            let (a, b) = join(|| fibonacci(n - 1), || fibonacci(n - 2));
+            a + b
+        }
+    })
+}
+
+/// A log of a deep recursion, never analysed so its clones start with nothing cached.
+fn deep_recursion_log() -> RunLog {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .expect("building pool failed");
+    pool.logging_install(|| fibonacci(20)).1
+}
+
+fn analyses(c: &mut Criterion) {
+    let log = deep_recursion_log();
+    let stats_log = log.clone();
+    c.bench_function("stats", move |b| {
+        b.iter_with_setup(|| stats_log.clone(), |log| log.stats())
+    });
+    c.bench_function("scene", move |b| {
+        b.iter_with_setup(|| log.clone(), |log| log.scene().rectangles.len())
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = analyses
+}
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+//! This example compares logging speed for different sizes of logs blocks
+//! (see `ThreadPoolBuilder::log_block_size`).
+extern crate rayon_logs;
+use rayon_logs::{join, ThreadPoolBuilder};
+
+fn fibo(n: u32) -> u32 {
+    if n <= 1 {
+        n
+    } else {
+        let (a, b) = join(|| fibo(n - 1), || fibo(n - 2));
+        a + b
+    }
+}
+
+const REPETITIONS: usize = 20;
+const N: u32 = 22;
+
+fn main() {
+    for &block_size in &[1_000, 10_000, 100_000] {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(4)
+            .log_block_size(block_size)
+            .build()
+            .expect("building pool failed");
+        let time = (0..REPETITIONS)
+            .map(|_| {
+                pool.logging_install(|| {
+                    let start = std::time::Instant::now();
+                    assert!(fibo(N) > 0);
+                    start.elapsed().as_nanos() as u64
+                })
+                .0
+            })
+            .sum::<u64>()
+            / REPETITIONS as u64;
+        let events: usize = pool.logged_events().iter().sum();
+        println!(
+            "blocks of {} events: {} ns for {} events ({:.1} ns per event), {} bytes of logs",
+            block_size,
+            time,
+            events,
+            time as f64 / events as f64,
+            pool.logs_memory_footprint()
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! This example compares `par_sort_by_key` and `par_sort_by_cached_key` with an expensive key.
+//! The first one computes keys twice per comparison, the second one once per element.
+use rayon_logs::prelude::*;
+use rayon_logs::ThreadPoolBuilder;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+const SIZE: u64 = 100_000;
+
+// hash the decimal representation of x
+fn string_hash(x: &u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    x.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn main() {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .expect("building pool failed");
+    let input: Vec<u64> = (0..SIZE).map(|i| (i * 7919) % SIZE).collect();
+
+    let mut v = input.clone();
+    let start = Instant::now();
+    let (_, log) = pool.logging_install(|| v.par_sort_by_key(string_hash));
+    println!("par_sort_by_key: {:?}", start.elapsed());
+    log.save_svg("sort_by_key.svg")
+        .expect("saving svg file failed");
+
+    let mut w = input;
+    let start = Instant::now();
+    let (_, log) = pool.logging_install(|| w.par_sort_by_cached_key(string_hash));
+    println!("par_sort_by_cached_key: {:?}", start.elapsed());
+    log.save_svg("sort_by_cached_key.svg")
+        .expect("saving svg file failed");
+
+    assert_eq!(v, w);
+}
@@ -88,6 +88,7 @@ fn main() {
                 v
             },
         )
+        .baseline("seq")
         .generate_logs("compare.html")
         .expect("failed saving logs");
     println!("generated compare.html");
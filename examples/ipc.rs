@@ -0,0 +1,52 @@
+//! Example computing the instructions per cycle of recursive max leaves
+//! (both hardware events are counted during the same run).
+#[cfg(feature = "perf")]
+fn main() {
+    use rayon_logs::HardwareEventType;
+    use rayon_logs::{join, subgraph_hardware_events, ThreadPoolBuilder};
+
+    fn manual_max(slice: &[u32]) -> u32 {
+        if slice.len() < 200_000 {
+            subgraph_hardware_events(
+                "max",
+                &[
+                    HardwareEventType::Instructions,
+                    HardwareEventType::CPUCycles,
+                ],
+                || slice.iter().max().cloned().unwrap(),
+            )
+        } else {
+            let middle = slice.len() / 2;
+            let (left, right) = slice.split_at(middle);
+            let (mleft, mright) = join(|| manual_max(left), || manual_max(right));
+            std::cmp::max(mleft, mright)
+        }
+    }
+    let v: Vec<u32> = (0..2_000_000).collect();
+
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .expect("building pool failed");
+    let (max, log) = pool.logging_install(|| manual_max(&v));
+    assert_eq!(max, v.last().cloned().unwrap());
+
+    let stats = log.stats();
+    let instructions = stats["max/Instructions"].0;
+    let cycles = stats["max/CPUCycles"].0;
+    if cycles == 0 {
+        println!("no cycles counted, are performance counters available ?");
+    } else {
+        println!(
+            "{} instructions in {} cycles: {:.2} instructions per cycle",
+            instructions,
+            cycles,
+            instructions as f64 / cycles as f64
+        );
+    }
+}
+
+#[cfg(not(feature = "perf"))]
+fn main() {
+    eprintln!("please compile me with 'perf' feature enabled!")
+}
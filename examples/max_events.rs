@@ -2,15 +2,15 @@
 #[cfg(feature = "perf")]
 fn main() {
     use rayon_logs::HardwareEventType;
-    use rayon_logs::{join, subgraph_hardware_event, ThreadPoolBuilder};
+    use rayon_logs::{join, subgraph_hardware_events, ThreadPoolBuilder};
 
     fn manual_max(slice: &[u32]) -> u32 {
         if slice.len() < 200_000 {
-            subgraph_hardware_event("cache_misses", HardwareEventType::CacheMisses, || {
-                subgraph_hardware_event("cpu_cycles", HardwareEventType::CPUCycles, || {
-                    slice.iter().max().cloned().unwrap()
-                })
-            })
+            subgraph_hardware_events(
+                "max",
+                &[HardwareEventType::CacheMisses, HardwareEventType::CPUCycles],
+                || slice.iter().max().cloned().unwrap(),
+            )
         } else {
             let middle = slice.len() / 2;
             let (left, right) = slice.split_at(middle);
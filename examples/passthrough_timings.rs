@@ -0,0 +1,60 @@
+//! This example compares rayon_logs' join against rayon's join.
+//! Run it with `--features disabled` to check that logging calls then cost nothing.
+extern crate rayon_logs;
+use rayon_logs::ThreadPoolBuilder;
+
+fn rayon_fibo(n: u32) -> u32 {
+    if n <= 1 {
+        n
+    } else {
+        let (a, b) = rayon::join(|| rayon_fibo(n - 1), || rayon_fibo(n - 2));
+        a + b
+    }
+}
+
+fn logged_fibo(n: u32) -> u32 {
+    if n <= 1 {
+        n
+    } else {
+        let (a, b) = rayon_logs::join(|| logged_fibo(n - 1), || logged_fibo(n - 2));
+        a + b
+    }
+}
+
+const REPETITIONS: usize = 100;
+const N: u32 = 20;
+
+fn main() {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .expect("building pool failed");
+
+    let time = |fibo: fn(u32) -> u32| -> u64 {
+        (0..REPETITIONS)
+            .map(|_| {
+                pool.logging_install(|| {
+                    let start = std::time::Instant::now();
+                    assert!(fibo(N) > 0);
+                    start.elapsed().as_nanos() as u64
+                })
+                .0
+            })
+            .sum::<u64>()
+            / REPETITIONS as u64
+    };
+
+    let rayon_time = time(rayon_fibo);
+    let logged_time = time(logged_fibo);
+    println!(
+        "logging {}: rayon takes {} ns, rayon_logs takes {} ns (overhead: {:.1}%)",
+        if cfg!(feature = "disabled") {
+            "disabled"
+        } else {
+            "enabled"
+        },
+        rayon_time,
+        logged_time,
+        (logged_time as f64 / rayon_time as f64 - 1.0) * 100.0
+    );
+}
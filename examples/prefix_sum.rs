@@ -0,0 +1,45 @@
+//! Compare prefix sums with several chunk sizes and generate an html comparison page.
+use rayon_logs::{par_prefix_sum, par_prefix_sum_with_chunk_size, subgraph, ThreadPoolBuilder};
+
+const SIZE: usize = 20_000_000;
+
+fn seq_prefix_sum(t: &mut [u64]) {
+    subgraph("local_scan", t.len(), || {
+        t.iter_mut().fold(0, |s, e| {
+            *e += s;
+            *e
+        });
+    })
+}
+
+fn main() {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(3)
+        .build()
+        .expect("building pool failed");
+    let input = || vec![1u64; SIZE];
+    pool.compare()
+        .runs_number(3)
+        .attach_algorithm_nodisplay_with_setup("seq", input, |mut v| {
+            seq_prefix_sum(&mut v);
+            v
+        })
+        .attach_algorithm_nodisplay_with_setup("par", input, |mut v| {
+            par_prefix_sum(&mut v, |a, b| a + b);
+            assert_eq!(v[SIZE - 1], SIZE as u64);
+            v
+        })
+        .attach_algorithm_nodisplay_with_setup("par (sqrt chunks)", input, |mut v| {
+            let chunk_size = (SIZE as f64).sqrt() as usize;
+            par_prefix_sum_with_chunk_size(&mut v, chunk_size, |a, b| a + b);
+            v
+        })
+        .attach_algorithm_nodisplay_with_setup("par (halves)", input, |mut v| {
+            par_prefix_sum_with_chunk_size(&mut v, SIZE / 2, |a, b| a + b);
+            v
+        })
+        .baseline("seq")
+        .generate_logs("prefix_sum.html")
+        .expect("failed saving logs");
+    println!("generated prefix_sum.html");
+}
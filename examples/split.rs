@@ -0,0 +1,28 @@
+//! Logged version of rayon's `split` example: recursively split ranges of indices.
+use rayon_logs::prelude::*;
+use rayon_logs::{split, ThreadPoolBuilder};
+use std::ops::Range;
+
+// Splitting a range in two stops when there is only one point inside of it.
+fn split_range(r: Range<usize>) -> (Range<usize>, Option<Range<usize>>) {
+    if r.end - r.start <= 1 {
+        return (r, None);
+    }
+    let midpoint = r.start + (r.end - r.start) / 2;
+    (r.start..midpoint, Some(midpoint..r.end))
+}
+
+fn main() {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .expect("failed creating pool");
+    let (_, log) = pool.logging_install(|| {
+        split(0..4096, split_range).for_each(|sub_range| {
+            // As our initial range had a power-of-two size, the final sub-ranges
+            // should have power-of-two sizes too
+            assert!((sub_range.end - sub_range.start).is_power_of_two());
+        })
+    });
+    log.save_svg("split.svg").expect("failed saving svg");
+}
@@ -0,0 +1,20 @@
+//! Display the same algorithm run with 1, 2, 4 and 8 threads in one svg file.
+use rayon_logs::prelude::*;
+use rayon_logs::{RunLog, ThreadPoolBuilder};
+
+fn main() {
+    let v: Vec<u32> = (0..2_000_000).collect();
+    let logs: Vec<RunLog> = [1, 2, 4, 8]
+        .iter()
+        .map(|&threads| {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("building pool failed");
+            let (max, log) = pool.logging_install(|| v.par_iter().max());
+            assert_eq!(max, v.last());
+            log
+        })
+        .collect();
+    RunLog::save_svg_many(&logs, "threads_scaling.svg").expect("failed saving svg");
+}
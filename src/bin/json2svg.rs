@@ -1,15 +1,15 @@
+//! Convert log files (saved with `RunLog::save` or `RawLogs::save`) to svg.
+//!
+//! usage: json2svg log_file svg_file
+//!    or: json2svg -o svg_file log_file... (several logs are stacked on the same time scale)
 extern crate rayon_logs;
 
-use rayon_logs::RunLog;
 use std::env::args;
+use std::process::exit;
 
 fn main() {
-    let json_file = args()
-        .nth(1)
-        .expect("missing json file name as first argument");
-    let svg_file = args()
-        .nth(2)
-        .expect("missing svg file name as second argument");
-    let logs = RunLog::load(&json_file).expect("failed to load json file");
-    logs.save_svg(&svg_file).expect("failed to save svg file");
+    if let Err(message) = rayon_logs::json2svg(args().skip(1)) {
+        eprintln!("{}", message);
+        exit(1)
+    }
 }
@@ -0,0 +1,395 @@
+//! Interactive animated viewer for log files (saved with `RunLog::save` or `RawLogs::save`).
+//!
+//! usage: log_viewer log_file
+//!
+//! keys: arrows to move, +/- to zoom, space to pause, r to restart the animation,
+//! t to switch the displayed tag and escape to quit.
+//! Type '/' then a task id and enter to jump to a task. Then n and p jump to its first
+//! child and first father.
+extern crate rayon_logs;
+
+#[cfg(feature = "viewer")]
+mod viewer {
+    use minifb::{Key, KeyRepeat, Window, WindowOptions};
+    use rayon_logs::visualisation::{Rectangle, Scene};
+    use rayon_logs::{time_string, RawLogs, RunLog};
+    use std::collections::HashMap;
+    use std::process::exit;
+    use std::time::Instant;
+
+    const WIDTH: usize = 1280;
+    const HEIGHT: usize = 720;
+    /// how long (in seconds) the animation of a full run lasts
+    const ANIMATION_DURATION: f64 = 10.0;
+    /// how many pixels we move for each frame an arrow is pressed
+    const PAN_SPEED: f64 = 10.0;
+    const BACKGROUND: u32 = 0xFF_FF_FF;
+    /// how long (in ms) a selected task blinks
+    const FLASH_DURATION: u128 = 2000;
+
+    /// Display given error and exit.
+    fn fail(message: &str) -> ! {
+        eprintln!("{}", message);
+        exit(1)
+    }
+
+    /// Load a log file, either post-processed or raw.
+    fn load(path: &str) -> RunLog {
+        RunLog::load(path)
+            .or_else(|e| {
+                RawLogs::load(path)
+                    .map_err(|_| e.to_string())
+                    .and_then(|raw_logs| raw_logs.run_log().map_err(|e| e.to_string()))
+            })
+            .unwrap_or_else(|e| fail(&format!("failed loading {}: {}", path, e)))
+    }
+
+    /// Convert a color with components between 0 and 1 to a pixel.
+    fn pixel(color: [f32; 3], opacity: f64) -> u32 {
+        let component = |c: f32| ((c as f64 * opacity).clamp(0.0, 1.0) * 255.0) as u32;
+        (component(color[0]) << 16) | (component(color[1]) << 8) | component(color[2])
+    }
+
+    /// Which part of the scene is displayed.
+    struct View {
+        /// scene coordinates of the top left corner
+        origin: (f64, f64),
+        /// pixels per nanosecond and per row
+        scale: (f64, f64),
+    }
+
+    impl View {
+        /// Display the whole scene.
+        fn fit(scene: &Scene) -> Self {
+            let xmin = scene
+                .rectangles
+                .iter()
+                .map(|r| r.x)
+                .fold(f64::INFINITY, f64::min);
+            let xmax = scene
+                .rectangles
+                .iter()
+                .map(|r| r.x + r.width)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let ymin = scene
+                .rectangles
+                .iter()
+                .map(|r| r.y)
+                .fold(f64::INFINITY, f64::min);
+            let ymax = scene
+                .rectangles
+                .iter()
+                .map(|r| r.y + r.height)
+                .fold(f64::NEG_INFINITY, f64::max);
+            View {
+                origin: (xmin, ymin),
+                scale: (
+                    WIDTH as f64 / (xmax - xmin).max(1.0),
+                    HEIGHT as f64 / (ymax - ymin).max(1.0),
+                ),
+            }
+        }
+
+        /// Zoom by given factor around the window's center.
+        fn zoom(&mut self, factor: f64) {
+            let center = self.scene_point((WIDTH as f64 / 2.0, HEIGHT as f64 / 2.0));
+            self.scale = (self.scale.0 * factor, self.scale.1 * factor);
+            self.origin = (
+                center.0 - WIDTH as f64 / 2.0 / self.scale.0,
+                center.1 - HEIGHT as f64 / 2.0 / self.scale.1,
+            );
+        }
+
+        /// Move so that given rectangle is at the window's center.
+        fn center_on(&mut self, rectangle: &Rectangle) {
+            self.origin = (
+                rectangle.x + rectangle.width / 2.0 - WIDTH as f64 / 2.0 / self.scale.0,
+                rectangle.y + rectangle.height / 2.0 - HEIGHT as f64 / 2.0 / self.scale.1,
+            );
+        }
+
+        /// Move by given number of pixels.
+        fn pan(&mut self, dx: f64, dy: f64) {
+            self.origin = (
+                self.origin.0 + dx / self.scale.0,
+                self.origin.1 + dy / self.scale.1,
+            );
+        }
+
+        fn scene_point(&self, point: (f64, f64)) -> (f64, f64) {
+            (
+                self.origin.0 + point.0 / self.scale.0,
+                self.origin.1 + point.1 / self.scale.1,
+            )
+        }
+
+        fn window_point(&self, point: (f64, f64)) -> (f64, f64) {
+            (
+                (point.0 - self.origin.0) * self.scale.0,
+                (point.1 - self.origin.1) * self.scale.1,
+            )
+        }
+    }
+
+    /// Fill given window area (clipped to the window).
+    fn fill(buffer: &mut [u32], top_left: (f64, f64), bottom_right: (f64, f64), color: u32) {
+        let clip = |v: f64, max: usize| v.max(0.0).min(max as f64) as usize;
+        let (x0, x1) = (clip(top_left.0, WIDTH), clip(bottom_right.0, WIDTH));
+        let (y0, y1) = (clip(top_left.1, HEIGHT), clip(bottom_right.1, HEIGHT));
+        for row in buffer.chunks_mut(WIDTH).take(y1).skip(y0) {
+            row[x0..x1].iter_mut().for_each(|p| *p = color);
+        }
+    }
+
+    /// Draw a one pixel wide segment (clipped to the window).
+    fn line(buffer: &mut [u32], start: (f64, f64), end: (f64, f64), color: u32) {
+        let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil() as usize;
+        // do not spend time on segments far outside of the window
+        let steps = steps.min(4 * (WIDTH + HEIGHT));
+        for step in 0..=steps {
+            let t = step as f64 / steps.max(1) as f64;
+            let (x, y) = (
+                start.0 + (end.0 - start.0) * t,
+                start.1 + (end.1 - start.1) * t,
+            );
+            if x >= 0.0 && y >= 0.0 && (x as usize) < WIDTH && (y as usize) < HEIGHT {
+                buffer[y as usize * WIDTH + x as usize] = color;
+            }
+        }
+    }
+
+    /// Draw a task: black background, then its part already executed at given time
+    /// colored with its opacity for given tag.
+    fn draw_rectangle(
+        buffer: &mut [u32],
+        view: &View,
+        rectangle: &Rectangle,
+        tag: &str,
+        time: u64,
+    ) {
+        let top_left = view.window_point((rectangle.x, rectangle.y));
+        let bottom_right = view.window_point((
+            rectangle.x + rectangle.width,
+            rectangle.y + rectangle.height,
+        ));
+        if rectangle.panicked {
+            fill(
+                buffer,
+                (top_left.0 - 2.0, top_left.1 - 2.0),
+                (bottom_right.0 + 2.0, bottom_right.1 + 2.0),
+                0xFF_00_00,
+            );
+        }
+        fill(buffer, top_left, bottom_right, 0);
+        let (start, end) = rectangle.animation;
+        let opacity = rectangle.information.get(tag).map(|(_, opacity)| *opacity);
+        if let (Some(opacity), true) = (opacity, time > start) {
+            let progress = (time - start) as f64 / end.saturating_sub(start).max(1) as f64;
+            let drawn_end = top_left.0 + (bottom_right.0 - top_left.0) * progress.min(1.0);
+            fill(
+                buffer,
+                top_left,
+                (drawn_end, bottom_right.1),
+                pixel(rectangle.color, opacity),
+            );
+        }
+    }
+
+    /// Draw the outline of given window area.
+    fn outline(buffer: &mut [u32], top_left: (f64, f64), bottom_right: (f64, f64), color: u32) {
+        let (left, top) = (top_left.0 - 3.0, top_left.1 - 3.0);
+        let (right, bottom) = (bottom_right.0 + 3.0, bottom_right.1 + 3.0);
+        fill(buffer, (left, top), (right, top + 3.0), color);
+        fill(buffer, (left, bottom - 3.0), (right, bottom), color);
+        fill(buffer, (left, top), (left + 3.0, bottom), color);
+        fill(buffer, (right - 3.0, top), (right, bottom), color);
+    }
+
+    /// Digit typed with given key.
+    fn digit(key: Key) -> Option<char> {
+        let digits = [
+            (Key::Key0, Key::NumPad0),
+            (Key::Key1, Key::NumPad1),
+            (Key::Key2, Key::NumPad2),
+            (Key::Key3, Key::NumPad3),
+            (Key::Key4, Key::NumPad4),
+            (Key::Key5, Key::NumPad5),
+            (Key::Key6, Key::NumPad6),
+            (Key::Key7, Key::NumPad7),
+            (Key::Key8, Key::NumPad8),
+            (Key::Key9, Key::NumPad9),
+        ];
+        digits
+            .iter()
+            .position(|&(k, n)| key == k || key == n)
+            .and_then(|d| std::char::from_digit(d as u32, 10))
+    }
+
+    pub(crate) fn main() {
+        let path = std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| fail("usage: log_viewer log_file"));
+        let log = load(&path);
+        let scene = log.scene();
+        if scene.rectangles.is_empty() {
+            fail("nothing to display");
+        }
+        let start_time = scene
+            .rectangles
+            .iter()
+            .map(|r| r.animation.0)
+            .min()
+            .unwrap_or(0);
+        let end_time = scene
+            .rectangles
+            .iter()
+            .map(|r| r.animation.1)
+            .max()
+            .unwrap_or(0);
+        let total_time = end_time.saturating_sub(start_time).max(1) as f64;
+        // rectangle displaying each task
+        let tasks_rectangles: HashMap<usize, usize> = scene
+            .rectangles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rectangle)| rectangle.task.map(|task| (task, index)))
+            .collect();
+        let mut fathers = vec![Vec::new(); log.tasks().len()];
+        for (task_id, task) in log.tasks().iter().enumerate() {
+            for &child in &task.children {
+                fathers[child].push(task_id);
+            }
+        }
+
+        let mut window = Window::new(&path, WIDTH, HEIGHT, WindowOptions::default())
+            .unwrap_or_else(|e| fail(&format!("failed opening window: {}", e)));
+        window.set_target_fps(60);
+        let mut buffer = vec![BACKGROUND; WIDTH * HEIGHT];
+        let mut view = View::fit(&scene);
+        let mut tag_index = 0;
+        let mut paused = false;
+        // elapsed run time displayed
+        let mut time = 0.0;
+        let mut last_frame = Instant::now();
+        // task id being typed (after '/')
+        let mut search: Option<String> = None;
+        // selected task and when it was selected
+        let mut selected: Option<(usize, Instant)> = None;
+        let mut status = String::new();
+        while window.is_open() {
+            if !paused {
+                time = (time
+                    + last_frame.elapsed().as_secs_f64() * total_time / ANIMATION_DURATION)
+                    .min(total_time);
+            }
+            last_frame = Instant::now();
+            let mut jump = None;
+            let selected_task = selected.map(|(task, _)| task);
+            for key in window.get_keys_pressed(KeyRepeat::No) {
+                match (search.as_mut(), key) {
+                    (Some(_), Key::Escape) => search = None,
+                    (Some(text), Key::Backspace) => {
+                        text.pop();
+                    }
+                    (Some(text), Key::Enter) | (Some(text), Key::NumPadEnter) => {
+                        jump = Some(text.parse::<usize>().map_err(|_| text.clone()));
+                        search = None;
+                    }
+                    (Some(text), key) => text.extend(digit(key)),
+                    (None, Key::Escape) => return,
+                    (None, Key::Slash) => search = Some(String::new()),
+                    (None, Key::N) => {
+                        jump = selected_task
+                            .and_then(|task| log.tasks()[task].children.first())
+                            .map(|&child| Ok(child))
+                    }
+                    (None, Key::P) => {
+                        jump = selected_task
+                            .and_then(|task| fathers[task].first())
+                            .map(|&father| Ok(father))
+                    }
+                    (None, Key::Space) => paused = !paused,
+                    (None, Key::R) => time = 0.0,
+                    (None, Key::T) => tag_index = (tag_index + 1) % scene.tags.len(),
+                    (None, Key::Equal) | (None, Key::NumPadPlus) => view.zoom(1.25),
+                    (None, Key::Minus) | (None, Key::NumPadMinus) => view.zoom(0.8),
+                    _ => (),
+                }
+            }
+            match jump {
+                Some(Ok(task)) if tasks_rectangles.contains_key(&task) => {
+                    view.center_on(&scene.rectangles[tasks_rectangles[&task]]);
+                    selected = Some((task, Instant::now()));
+                    status = format!(" - task {}", task);
+                }
+                Some(Ok(task)) => status = format!(" - no task {}", task),
+                Some(Err(text)) => status = format!(" - invalid task id '{}'", text),
+                None => (),
+            }
+            for (key, dx, dy) in &[
+                (Key::Left, -PAN_SPEED, 0.0),
+                (Key::Right, PAN_SPEED, 0.0),
+                (Key::Up, 0.0, -PAN_SPEED),
+                (Key::Down, 0.0, PAN_SPEED),
+            ] {
+                if window.is_key_down(*key) {
+                    view.pan(*dx, *dy);
+                }
+            }
+
+            buffer.iter_mut().for_each(|p| *p = BACKGROUND);
+            let tag = &scene.tags[tag_index];
+            for rectangle in &scene.rectangles {
+                draw_rectangle(&mut buffer, &view, rectangle, tag, start_time + time as u64);
+            }
+            for (start, end) in &scene.segments {
+                line(
+                    &mut buffer,
+                    view.window_point(*start),
+                    view.window_point(*end),
+                    0,
+                );
+            }
+            if let Some((task, selection_time)) = selected {
+                // blink for a while after selecting, then stay highlighted
+                let elapsed = selection_time.elapsed().as_millis();
+                if elapsed > FLASH_DURATION || (elapsed / 250) % 2 == 0 {
+                    let rectangle = &scene.rectangles[tasks_rectangles[&task]];
+                    outline(
+                        &mut buffer,
+                        view.window_point((rectangle.x, rectangle.y)),
+                        view.window_point((
+                            rectangle.x + rectangle.width,
+                            rectangle.y + rectangle.height,
+                        )),
+                        0xFF_00_FF,
+                    );
+                }
+            }
+            window.set_title(&format!(
+                "{} - tag: {} - {}{}{}",
+                path,
+                tag,
+                time_string(time as u64),
+                if paused { " (paused)" } else { "" },
+                match &search {
+                    Some(text) => format!(" - go to task: {}_", text),
+                    None => status.clone(),
+                }
+            ));
+            window
+                .update_with_buffer(&buffer, WIDTH, HEIGHT)
+                .unwrap_or_else(|e| fail(&format!("failed displaying: {}", e)));
+        }
+    }
+}
+
+#[cfg(feature = "viewer")]
+fn main() {
+    viewer::main()
+}
+
+#[cfg(not(feature = "viewer"))]
+fn main() {
+    println!("please compile me with 'viewer' feature enabled!");
+}
@@ -0,0 +1,21 @@
+//! Convert a log file to another format, chosen by the output file's extension:
+//! svg, dot (graphviz), json (chrome trace) or csv (tasks table).
+//!
+//! usage: rlogconvert [--collapse-subgraphs] [--filter-tag tag] log_file output_file
+extern crate rayon_logs;
+
+use rayon_logs::RLOGCONVERT_USAGE;
+use std::env::args;
+use std::process::exit;
+
+fn main() {
+    let arguments: Vec<String> = args().skip(1).collect();
+    if arguments.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", RLOGCONVERT_USAGE);
+        return;
+    }
+    if let Err(message) = rayon_logs::rlogconvert(arguments) {
+        eprintln!("{}", message);
+        exit(1)
+    }
+}
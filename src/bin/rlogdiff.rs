@@ -0,0 +1,69 @@
+//! Compare two log files of the same algorithm.
+//!
+//! usage: rlogdiff [--threshold 5%] old_log_file new_log_file
+//!
+//! With a threshold only tags whose duration increased by more than the threshold are
+//! displayed and we exit with code 1 if there are any (2 on errors).
+extern crate rayon_logs;
+
+use rayon_logs::RunLog;
+use std::env::args;
+use std::process::exit;
+
+const USAGE: &str = "usage: rlogdiff [--threshold 5%] old_log_file new_log_file";
+
+/// Display given error and exit.
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(2)
+}
+
+/// Parse a percentage like "5%" or "5".
+fn parse_threshold(threshold: &str) -> f64 {
+    threshold
+        .trim_end_matches('%')
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid threshold {}\n{}", threshold, USAGE)))
+}
+
+fn main() {
+    let mut threshold = None;
+    let mut paths = Vec::new();
+    let mut arguments = args().skip(1);
+    while let Some(argument) = arguments.next() {
+        if argument == "--threshold" {
+            let value = arguments.next().unwrap_or_else(|| fail(USAGE));
+            threshold = Some(parse_threshold(&value));
+        } else if let Some(value) = argument.strip_prefix("--threshold=") {
+            threshold = Some(parse_threshold(value));
+        } else if argument.starts_with("--") {
+            fail(&format!("unknown option {}\n{}", argument, USAGE));
+        } else {
+            paths.push(argument);
+        }
+    }
+    if paths.len() != 2 {
+        fail(USAGE);
+    }
+    let load = |path: &String| {
+        RunLog::load(path).unwrap_or_else(|e| fail(&format!("failed loading {}: {}", path, e)))
+    };
+    let mut diff = load(&paths[0]).diff(&load(&paths[1]));
+    match threshold {
+        None => print!("{}", diff),
+        Some(threshold) => {
+            let regressed: Vec<String> =
+                diff.regressions(threshold).map(|t| t.tag.clone()).collect();
+            diff.tags.retain(|t| regressed.contains(&t.tag));
+            print!("{}", diff);
+            if !regressed.is_empty() {
+                eprintln!(
+                    "{} tag(s) regressed by more than {}%",
+                    regressed.len(),
+                    threshold
+                );
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Summarize log files in the terminal.
+//!
+//! usage: rlogstats [--json] log_file...
+//!    or: rlogstats --check log_file...
+//!
+//! With `--check` we only look for structural problems in the logs (raw logs saved with
+//! `RawLogs::save` are checked event by event) and exit with code 1 if any is found.
+extern crate rayon_logs;
+
+use rayon_logs::{time_string, RawLogs, RunLog, TASK_OVERHEAD};
+use serde_json::{json, Value};
+use std::env::args;
+use std::process::exit;
+
+/// How many tags and tasks we display.
+const TOP_NUMBER: usize = 5;
+
+/// Compute all statistics of given log as json.
+fn summary(path: &str, log: &RunLog) -> Value {
+    let work = log.work();
+    let span = log.span();
+    let stats = log.stats();
+    let granularity = log.granularity_report(TASK_OVERHEAD);
+    let mut tags: Vec<_> = stats.iter().collect();
+    tags.sort_by(|a, b| (b.1).1.cmp(&(a.1).1).then(a.0.cmp(b.0)));
+    json!({
+        "file": path,
+        "duration": log.duration(),
+        "threads": log.threads_number(),
+        "tasks": log.tasks().len(),
+        "work": work,
+        "span": span,
+        "parallelism": work as f64 / span.max(1) as f64,
+        "idle_times": log.threads_idle_times(),
+        "idle_causes": log
+            .threads_idle_causes()
+            .into_iter()
+            .map(|(starvation, no_parallelism)| {
+                json!({"starvation": starvation, "no_parallelism": no_parallelism})
+            })
+            .collect::<Vec<_>>(),
+        "tags": tags
+            .iter()
+            .take(TOP_NUMBER)
+            .map(|(tag, (work, duration, speed))| {
+                json!({"tag": tag, "work": work, "duration": duration, "speed": speed})
+            })
+            .collect::<Vec<_>>(),
+        "granularity": {
+            "leaves": granularity.buckets,
+            "small_work_fraction": granularity.small_work_fraction(),
+            "suggestion": granularity.suggestion(),
+        },
+        "longest_tasks": log
+            .longest_tasks(TOP_NUMBER)
+            .into_iter()
+            .map(|(task_id, task, tag)| {
+                json!({
+                    "task": task_id,
+                    "thread": task.thread_id,
+                    "duration": task.duration(),
+                    "tag": tag,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Display a summary computed by `summary` for humans.
+fn display(summary: &Value) {
+    let time = |value: &Value| time_string(value.as_u64().unwrap_or(0));
+    println!("{}", summary["file"].as_str().unwrap_or(""));
+    println!("  duration: {}", time(&summary["duration"]));
+    println!("  threads: {}", summary["threads"]);
+    println!("  tasks: {}", summary["tasks"]);
+    println!(
+        "  work: {}, span: {}, parallelism: {:.2}",
+        time(&summary["work"]),
+        time(&summary["span"]),
+        summary["parallelism"].as_f64().unwrap_or(0.0)
+    );
+    println!("  idle time per thread:");
+    for (thread, (idle_time, causes)) in summary["idle_times"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .zip(summary["idle_causes"].as_array().into_iter().flatten())
+        .enumerate()
+    {
+        println!(
+            "    {}: {} (starvation {}, no parallelism {})",
+            thread,
+            time(idle_time),
+            time(&causes["starvation"]),
+            time(&causes["no_parallelism"])
+        );
+    }
+    println!("  top tags by duration:");
+    for tag in summary["tags"].as_array().into_iter().flatten() {
+        println!(
+            "    {}: {} (work {}, speed {:.2})",
+            tag["tag"].as_str().unwrap_or(""),
+            time(&tag["duration"]),
+            tag["work"],
+            tag["speed"].as_f64().unwrap_or(0.0)
+        );
+    }
+    println!("  longest tasks:");
+    for task in summary["longest_tasks"].as_array().into_iter().flatten() {
+        println!(
+            "    task {} on thread {}: {}{}",
+            task["task"],
+            task["thread"],
+            time(&task["duration"]),
+            task["tag"]
+                .as_str()
+                .map(|tag| format!(" ({})", tag))
+                .unwrap_or_default()
+        );
+    }
+    println!("  leaves durations:");
+    for leaves in summary["granularity"]["leaves"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        println!(
+            "    from {}: {} tasks, {}",
+            time(&leaves[0]),
+            leaves[1],
+            time(&leaves[2])
+        );
+    }
+    println!(
+        "  {}",
+        summary["granularity"]["suggestion"].as_str().unwrap_or("")
+    );
+}
+
+/// Load a log file, either post-processed or raw.
+fn load(path: &str) -> RunLog {
+    RunLog::load(path)
+        .or_else(|e| {
+            RawLogs::load(path)
+                .map_err(|_| e.to_string())
+                .and_then(|raw_logs| raw_logs.run_log().map_err(|e| e.to_string()))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("failed loading {}: {}", path, e);
+            exit(1)
+        })
+}
+
+/// Display all problems found in given log file and return if there are any.
+fn check(path: &str) -> bool {
+    let findings = match RawLogs::load(path) {
+        Ok(raw_logs) => raw_logs.validate().iter().map(|f| f.to_string()).collect(),
+        Err(_) => rayon_logs::validate_log(path),
+    };
+    if findings.is_empty() {
+        println!("{}: ok", path);
+    } else {
+        println!("{}:", path);
+        for finding in &findings {
+            println!("  {}", finding);
+        }
+    }
+    !findings.is_empty()
+}
+
+fn main() {
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args().skip(1).partition(|a| a.starts_with("--"));
+    let as_json = flags.iter().any(|f| f == "--json");
+    let checking = flags.iter().any(|f| f == "--check");
+    if let Some(flag) = flags.iter().find(|f| *f != "--json" && *f != "--check") {
+        eprintln!("unknown option {}", flag);
+        exit(1);
+    }
+    if paths.is_empty() {
+        eprintln!("usage: rlogstats [--json] log_file...\n   or: rlogstats --check log_file...");
+        exit(1);
+    }
+    if checking {
+        // check all files before failing
+        let failed = paths.iter().filter(|path| check(path)).count();
+        exit(if failed == 0 { 0 } else { 1 });
+    }
+    let summaries: Vec<Value> = paths
+        .iter()
+        .map(|path| summary(path, &load(path)))
+        .collect();
+    if as_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summaries).expect("failed converting to json")
+        );
+    } else {
+        summaries.iter().for_each(display);
+    }
+}
@@ -1,15 +1,57 @@
-use crate::pool::LOGS;
-use crate::storage::Storage;
+use crate::clock::{Clock, CLOCK};
+use crate::log::ThreadInfo;
+use crate::pool::{IdsGenerators, IDS, LOGS};
+use crate::storage::{Storage, DEFAULT_BLOCK_SIZE};
 use crate::ThreadPool;
 use rayon::{self, ThreadPoolBuildError};
+use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, Mutex};
 type Builder = rayon::ThreadPoolBuilder;
 
 /// We rewrite ThreadPoolBuilders since we need to overload the start handler
 /// in order to give each thread a place to write its logs.
-#[derive(Default)]
+///
+/// Other rayon options are forwarded to the underlying `rayon::ThreadPoolBuilder`.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// fn depth(n: u64) -> u64 {
+///     let frame = std::hint::black_box([n; 512]); // 4KB per call
+///     if n == 0 {
+///         0
+///     } else {
+///         1 + depth(frame[n as usize % 512] - 1)
+///     }
+/// }
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .thread_name(|index| format!("worker {}", index))
+///     .stack_size(8 * 1024 * 1024)
+///     .build()
+///     .expect("building pool failed");
+/// let ((name, depth), _) = pool.logging_install(|| {
+///     let name = std::thread::current().name().map(|n| n.to_string());
+///     (name, depth(1_000)) // needs about 4MB of stack
+/// });
+/// assert!(name.expect("no name").starts_with("worker"));
+/// assert_eq!(depth, 1_000);
+/// ```
 pub struct ThreadPoolBuilder {
     builder: Builder,
+    start_handler: Option<Box<dyn Fn(usize) + Send + Sync>>,
+    save_logs: Option<String>,
+    log_block_size: usize,
+    clock: Clock,
+}
+
+impl Default for ThreadPoolBuilder {
+    fn default() -> Self {
+        ThreadPoolBuilder::new()
+    }
 }
 
 impl ThreadPoolBuilder {
@@ -17,6 +59,61 @@ impl ThreadPoolBuilder {
     pub fn new() -> Self {
         ThreadPoolBuilder {
             builder: Builder::new(),
+            start_handler: None,
+            save_logs: Some("log_{}.json".to_string()),
+            log_block_size: DEFAULT_BLOCK_SIZE,
+            clock: Clock::Instant,
+        }
+    }
+
+    /// Choose which clock timestamps events (default is `Clock::Instant`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::{Clock, ThreadPoolBuilder};
+    /// use std::time::Duration;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .clock(Clock::Tsc)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| {
+    ///     rayon_logs::join(|| std::thread::sleep(Duration::from_millis(10)), || ())
+    /// });
+    /// assert_eq!(log.clock(), Clock::Tsc);
+    /// // times are still in nanoseconds
+    /// assert!(log.duration() >= 9_000_000 && log.duration() < 1_000_000_000);
+    /// ```
+    pub fn clock(self, clock: Clock) -> Self {
+        ThreadPoolBuilder { clock, ..self }
+    }
+
+    /// Set the number of events each thread allocates space for at once (default is 10_000).
+    /// Bigger blocks mean less allocations, smaller ones less memory wasted
+    /// (see `ThreadPool::logs_memory_footprint`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `events` is zero.
+    pub fn log_block_size(self, events: usize) -> Self {
+        assert!(events > 0, "log blocks cannot be empty");
+        ThreadPoolBuilder {
+            log_block_size: events,
+            ..self
+        }
+    }
+
+    /// Choose where `ThreadPool::install` saves its logs.
+    /// `None` disables saving. `Some` either takes a file name pattern where `{}` is replaced
+    /// by an incremental counter or a directory where files are named with the default pattern.
+    /// Default is `Some("log_{}.json")` (in the current directory).
+    pub fn save_logs(self, destination: Option<&str>) -> Self {
+        ThreadPoolBuilder {
+            save_logs: destination.map(|d| d.to_string()),
+            ..self
         }
     }
 
@@ -24,24 +121,136 @@ impl ThreadPoolBuilder {
     pub fn num_threads(self, threads_number: usize) -> Self {
         ThreadPoolBuilder {
             builder: self.builder.num_threads(threads_number),
+            ..self
+        }
+    }
+
+    /// Set a closure which takes a thread index and returns the thread's name.
+    pub fn thread_name<F>(self, closure: F) -> Self
+    where
+        F: FnMut(usize) -> String + 'static,
+    {
+        ThreadPoolBuilder {
+            builder: self.builder.thread_name(closure),
+            ..self
+        }
+    }
+
+    /// Set the stack size (in bytes) of worker threads.
+    pub fn stack_size(self, stack_size: usize) -> Self {
+        ThreadPoolBuilder {
+            builder: self.builder.stack_size(stack_size),
+            ..self
+        }
+    }
+
+    /// Set a callback to be invoked on each thread's start (with the thread's index).
+    /// It is called after the thread is registered for logging.
+    pub fn start_handler<H>(self, start_handler: H) -> Self
+    where
+        H: Fn(usize) + Send + Sync + 'static,
+    {
+        ThreadPoolBuilder {
+            start_handler: Some(Box::new(start_handler)),
+            ..self
+        }
+    }
+
+    /// Set a callback to be invoked on each thread's exit (with the thread's index).
+    pub fn exit_handler<H>(self, exit_handler: H) -> Self
+    where
+        H: Fn(usize) + Send + Sync + 'static,
+    {
+        ThreadPoolBuilder {
+            builder: self.builder.exit_handler(exit_handler),
+            ..self
         }
     }
 
     /// Build the `ThreadPool`.
+    ///
+    /// Each worker registers its logs storage with the pool when starting.
+    /// Threads outside of the pool (including the one calling `logging_install`)
+    /// never log into the pool, even if they used logged functions before.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// // some logging calls before any pool exists
+    /// std::thread::spawn(|| rayon_logs::join(|| 1, || 2))
+    ///     .join()
+    ///     .expect("thread failed");
+    /// rayon_logs::join(|| 1, || 2);
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+    /// assert_eq!(log.threads_number(), 2);
+    /// assert_eq!(log.tasks().len(), 4);
+    /// // the initial task runs in the pool and starts the graph
+    /// assert_eq!(log.tasks()[0].children.len(), 2);
+    /// ```
     pub fn build(self) -> Result<ThreadPool, ThreadPoolBuildError> {
         let logs = Arc::new(Mutex::new(Vec::new()));
         let shared_logs = logs.clone();
+        let threads_info = Arc::new(Mutex::new(Vec::new()));
+        let shared_threads_info = threads_info.clone();
+        let ids = Arc::new(IdsGenerators::default());
+        let shared_ids = ids.clone();
+        let user_start_handler = self.start_handler;
+        let save_logs = self.save_logs;
+        let log_block_size = self.log_block_size;
+        let clock = self.clock;
+        clock.initialize();
         let pool = self
             .builder
-            .start_handler(move |_| {
+            .start_handler(move |index| {
                 LOGS.with(|l| {
-                    let thread_storage = Arc::new(Storage::new());
-                    shared_logs.lock().unwrap().push(thread_storage.clone());
+                    let thread_storage = Arc::new(Storage::with_block_size(log_block_size));
+                    let mut logs = shared_logs.lock().unwrap();
+                    logs.push(thread_storage.clone());
+                    // registered in the same order as storages
+                    shared_threads_info.lock().unwrap().push(ThreadInfo {
+                        index,
+                        os_id: os_thread_id(),
+                        name: std::thread::current().name().map(String::from),
+                    });
                     *l.borrow_mut() = thread_storage;
                 });
+                IDS.with(|i| *i.borrow_mut() = shared_ids.clone());
+                CLOCK.with(|c| c.set(clock));
+                if let Some(handler) = &user_start_handler {
+                    handler(index)
+                }
             })
             .build();
 
-        pool.map(|p| ThreadPool { pool: p, logs })
+        pool.map(|p| ThreadPool {
+            pool: p,
+            logs,
+            threads_info,
+            ids,
+            start: AtomicU64::new(0),
+            clock,
+            save_logs,
+            installing: Mutex::new(()),
+        })
+    }
+}
+
+/// Operating system id of the calling thread (if we know how to get it).
+fn os_thread_id() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(unsafe { libc::syscall(libc::SYS_gettid) } as u64)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
     }
 }
@@ -0,0 +1,84 @@
+//! Clocks used for timestamping events (see `ThreadPoolBuilder::clock`).
+//! All clocks count nanoseconds from the same origin (`START_TIME`).
+use crate::raw_events::TimeStamp;
+use lazy_static::lazy_static;
+use serde_derive::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref START_TIME: Instant = Instant::now();
+    /// tsc value at a known time and nanoseconds per tick
+    static ref TSC_CALIBRATION: (u64, TimeStamp, f64) = calibrate_tsc();
+}
+
+/// Which clock timestamps events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Clock {
+    /// `std::time::Instant` (default).
+    #[default]
+    Instant,
+    /// The processor's time stamp counter, converted to nanoseconds with a calibration
+    /// done when building the pool.
+    /// Cheaper to read but requires an invariant tsc (synchronized across cores).
+    /// Falls back to `Instant` on processors other than x86_64.
+    Tsc,
+}
+
+// clock of the pool this thread belongs to (set by the pool's start handler)
+thread_local!(pub(crate) static CLOCK: Cell<Clock> = const { Cell::new(Clock::Instant) });
+
+impl Clock {
+    /// Initialize the clock (time origin and calibration).
+    /// This is done when building pools so that no logging thread pays for it.
+    pub(crate) fn initialize(self) {
+        lazy_static::initialize(&START_TIME);
+        if self == Clock::Tsc {
+            lazy_static::initialize(&TSC_CALIBRATION);
+        }
+    }
+
+    /// Return number of nano seconds since start.
+    #[inline]
+    pub(crate) fn now(self) -> TimeStamp {
+        match self {
+            Clock::Instant => instant_now(),
+            Clock::Tsc => {
+                let (origin_tsc, origin_time, ns_per_tick) = *TSC_CALIBRATION;
+                origin_time + (tsc().saturating_sub(origin_tsc) as f64 * ns_per_tick) as TimeStamp
+            }
+        }
+    }
+}
+
+/// Return number of nano seconds since start (using the clock of the current thread).
+#[inline]
+pub(crate) fn now() -> TimeStamp {
+    CLOCK.with(|c| c.get()).now()
+}
+
+fn instant_now() -> TimeStamp {
+    START_TIME.elapsed().as_nanos() as TimeStamp
+}
+
+#[cfg(target_arch = "x86_64")]
+fn tsc() -> u64 {
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn tsc() -> u64 {
+    instant_now()
+}
+
+/// Measure how many nanoseconds a tsc tick takes (by waiting a bit).
+fn calibrate_tsc() -> (u64, TimeStamp, f64) {
+    let start_time = instant_now();
+    let start_tsc = tsc();
+    let calibration_start = Instant::now();
+    while calibration_start.elapsed() < Duration::from_millis(20) {}
+    let end_tsc = tsc();
+    let end_time = instant_now();
+    let ns_per_tick = (end_time - start_time) as f64 / (end_tsc - start_tsc).max(1) as f64;
+    (start_tsc, start_time, ns_per_tick)
+}
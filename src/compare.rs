@@ -1,30 +1,79 @@
 //! `Comparator` Structure for easy comparisons of different algorithms.
-use crate::stats::Stats;
-use crate::ThreadPool;
+use crate::results::ComparisonResults;
+use crate::stats::box_plot;
 use crate::{
     log::RunLog,
-    svg::{histogram, HISTOGRAM_COLORS},
+    svg::{box_plots, histogram, html_escape, speedup_chart, HISTOGRAM_COLORS},
 };
 use crate::{svg::fill_svg_file, visualisation};
+use crate::{ThreadPool, ThreadPoolBuilder};
 use itertools::{izip, Itertools};
+use rayon::ThreadPoolBuildError;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::Error;
-use std::iter::repeat_with;
-use std::path::Path;
+use std::io::{BufWriter, Error};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Minimal number of runs of each algorithm when using a time budget.
+const MIN_BUDGET_RUNS: usize = 5;
+
+/// Number of tasks displayed in the longest tasks table of each algorithm.
+const HOT_TASKS_NUMBER: usize = 5;
 
 /// The comparator structure enables you to easily compare performances of different algorithms.
 ///
 /// It runs each algorithm several times before displaying some simple statistics and for each
 /// algorithm the median and best execution trace.
 /// See for example the `filter_collect` example.
+///
+/// It can also run all algorithms on pools of different sizes (see `sweep_threads`) in order to
+/// display scalability curves.
 pub struct Comparator<'a> {
     labels: Vec<String>,
+    /// logs of all runs for each algorithm (on the largest pool when sweeping).
     logs: Vec<Vec<RunLog>>,
-    pool: &'a ThreadPool,
+    pool: Option<&'a ThreadPool>,
+    /// pools we built ourselves for sweeping over different numbers of threads.
+    sweep_pools: Vec<(usize, ThreadPool)>,
+    /// for each algorithm the median duration obtained on each sweep pool
+    /// (none for attached logs or when not sweeping).
+    sweep_durations: Vec<Option<Vec<u64>>>,
     runs_number: usize,
+    /// when set, runs are launched until the budget is exhausted (instead of `runs_number`).
+    time_budget: Option<Duration>,
+    warmup_runs: usize,
     display_preferences: Vec<bool>,
+    /// index of the algorithm all others are compared to.
+    baseline: Option<usize>,
+    /// where to save the logs of all measured runs (if anywhere).
+    raw_logs_directory: Option<PathBuf>,
+    /// first error encountered while saving raw logs (we stop saving them afterwards).
+    raw_logs_error: RefCell<Option<Error>>,
+    /// percentiles of the runs to display (median and best by default).
+    visualized_percentiles: Option<Vec<f64>>,
+    /// called after each measured run.
+    progress: RefCell<Option<ProgressCallback<'a>>>,
+}
+
+/// Function called with progress information.
+type ProgressCallback<'a> = Box<dyn FnMut(ProgressEvent) + 'a>;
+
+/// Information given to the progress callback after each measured run.
+/// See `Comparator::on_progress`.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressEvent<'l> {
+    /// label of the running algorithm.
+    pub label: &'l str,
+    /// index of the run which just completed (starting at 0).
+    pub run_index: usize,
+    /// total number of runs for this algorithm
+    /// (an estimation when using a time budget, exact on the last run).
+    pub total_runs: usize,
+    /// duration (in nanoseconds) of the run which just completed.
+    pub last_duration: u64,
 }
 
 impl<'a> Comparator<'a> {
@@ -32,11 +81,42 @@ impl<'a> Comparator<'a> {
         Comparator {
             labels: Vec::new(),
             logs: Vec::new(),
-            pool,
+            pool: Some(pool),
+            sweep_pools: Vec::new(),
+            sweep_durations: Vec::new(),
+            runs_number: 100,
+            time_budget: None,
+            warmup_runs: 0,
+            display_preferences: Vec::new(),
+            baseline: None,
+            raw_logs_directory: None,
+            raw_logs_error: RefCell::new(None),
+            visualized_percentiles: None,
+            progress: RefCell::new(None),
+        }
+    }
+
+    /// Create a comparator owning its own pools.
+    /// You need to call `sweep_threads` before attaching algorithms.
+    pub fn standalone() -> Comparator<'static> {
+        Comparator {
+            labels: Vec::new(),
+            logs: Vec::new(),
+            pool: None,
+            sweep_pools: Vec::new(),
+            sweep_durations: Vec::new(),
             runs_number: 100,
+            time_budget: None,
+            warmup_runs: 0,
             display_preferences: Vec::new(),
+            baseline: None,
+            raw_logs_directory: None,
+            raw_logs_error: RefCell::new(None),
+            visualized_percentiles: None,
+            progress: RefCell::new(None),
         }
     }
+
     /// Renumber all tags accross all logs such that tags number match.
     /// Return vector of all tags.
     fn fuse_tags(&mut self) -> Vec<String> {
@@ -59,19 +139,380 @@ impl<'a> Comparator<'a> {
     }
     /// Sets the number of runs for each algorithm.
     /// PRECONDITION: call that BEFORE attaching algorithms
+    ///
+    /// Panics if no runs are wanted.
+    /// Even a single run is enough to get a (not so meaningful) report.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let v: Vec<u64> = (0..10_000).collect();
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// for runs in 1..=2 {
+    ///     let path = std::env::temp_dir().join(format!("rayon_logs_{}_runs.html", runs));
+    ///     pool.compare()
+    ///         .runs_number(runs)
+    ///         .attach_algorithm("sum", || assert!(v.par_iter().sum::<u64>() > 0))
+    ///         .attach_algorithm("max", || assert!(v.par_iter().max().is_some()))
+    ///         .generate_logs(&path)
+    ///         .expect("failed saving logs");
+    ///     let html = std::fs::read_to_string(&path).expect("reading html failed");
+    ///     assert!(html.contains(&format!("over {} runs", runs)));
+    /// }
+    /// ```
+    ///
+    /// ```should_panic
+    /// let pool = rayon_logs::ThreadPoolBuilder::new().build().unwrap();
+    /// pool.compare().runs_number(0);
+    /// ```
     pub fn runs_number(self, runs_wanted: usize) -> Self {
+        assert!(runs_wanted >= 1, "comparator needs at least one run");
         Comparator {
-            labels: self.labels,
-            logs: self.logs,
-            pool: self.pool,
             runs_number: runs_wanted,
-            display_preferences: self.display_preferences,
+            ..self
+        }
+    }
+
+    /// Save the log of each measured run (before any sorting or statistics) in given directory,
+    /// as `<label>_<run_index>.json` (`<label>_<threads>threads_<run_index>.json` when sweeping).
+    /// This allows inspecting outliers, files can be loaded back with `RunLog::load`.
+    /// PRECONDITION: call that BEFORE attaching algorithms
+    ///
+    /// Failing to save a raw log does not interrupt the benchmarks: no more raw logs are saved
+    /// and the error is returned by `generate_logs`, `write_logs` or `results`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// // raw logs cannot be saved below a file
+    /// let file = std::env::temp_dir().join(format!("rayon_logs_raw_{}", std::process::id()));
+    /// std::fs::write(&file, "").expect("creating file failed");
+    /// let mut html = Vec::new();
+    /// let saving = pool
+    ///     .compare()
+    ///     .runs_number(2)
+    ///     .keep_raw_logs(file.join("raw"))
+    ///     .attach_algorithm("sum", || assert!((0..100u64).into_par_iter().sum::<u64>() > 0))
+    ///     .write_logs(&mut html);
+    /// std::fs::remove_file(&file).expect("removing file failed");
+    /// assert!(saving.is_err());
+    /// // the page is still written
+    /// assert!(html.starts_with(b"<!DOCTYPE html>"));
+    /// ```
+    pub fn keep_raw_logs<P: AsRef<Path>>(self, directory: P) -> Self {
+        if let Err(e) = std::fs::create_dir_all(&directory) {
+            self.raw_logs_error.replace(Some(e));
+        }
+        Comparator {
+            raw_logs_directory: Some(directory.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
+    /// Save given log if we are keeping all of them (and no saving failed so far).
+    fn keep_raw_log(&self, label: &str, run_index: usize, log: &RunLog) {
+        if self.raw_logs_error.borrow().is_some() {
+            return;
+        }
+        if let Some(directory) = &self.raw_logs_directory {
+            let label: String = label
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect();
+            let filename = if self.sweep_pools.is_empty() {
+                format!("{}_{}.json", label, run_index)
+            } else {
+                format!("{}_{}threads_{}.json", label, log.threads_number, run_index)
+            };
+            if let Err(e) = log.save(directory.join(filename)) {
+                self.raw_logs_error.replace(Some(e));
+            }
+        }
+    }
+
+    /// Instead of a fixed number of runs, keep running each algorithm until given duration
+    /// is spent on it (with a minimum of 5 runs).
+    /// When sharing inputs, algorithms are run round-robin until the budget of the whole group
+    /// is exhausted.
+    /// Algorithms can then end up with different numbers of runs, which are displayed in the
+    /// html header.
+    /// PRECONDITION: call that BEFORE attaching algorithms
+    pub fn time_budget(self, budget: Duration) -> Self {
+        Comparator {
+            time_budget: Some(budget),
+            ..self
+        }
+    }
+
+    /// Should we launch more runs after `runs_done` runs started at `start` ?
+    fn keep_running(&self, start: Instant, runs_done: usize) -> bool {
+        match self.time_budget {
+            None => runs_done < self.runs_number,
+            Some(budget) => runs_done < MIN_BUDGET_RUNS || start.elapsed() < budget,
+        }
+    }
+
+    /// Total number of runs we expect after `runs_done` runs started at `start`.
+    fn expected_runs(&self, start: Instant, runs_done: usize) -> usize {
+        if !self.keep_running(start, runs_done) {
+            return runs_done;
+        }
+        match self.time_budget {
+            None => self.runs_number,
+            Some(budget) => {
+                let run_duration = start.elapsed().as_nanos() / runs_done.max(1) as u128;
+                let estimation = budget.as_nanos() / run_duration.max(1);
+                (estimation as usize)
+                    .max(MIN_BUDGET_RUNS)
+                    .max(runs_done + 1)
+            }
+        }
+    }
+
+    /// Mark the algorithm with given label as the baseline.
+    /// Mean and median tables will display the relative difference of each algorithm's
+    /// total time with the baseline's one.
+    /// PRECONDITION: call that AFTER attaching the baseline algorithm
+    pub fn baseline(self, label: &str) -> Self {
+        let baseline = self
+            .labels
+            .iter()
+            .position(|l| l == label)
+            .unwrap_or_else(|| panic!("no algorithm labeled {} attached", label));
+        Comparator {
+            baseline: Some(baseline),
+            ..self
+        }
+    }
+
+    /// Log an algorithm's performances, generate svg traces and use it as the baseline.
+    /// See `baseline`.
+    pub fn attach_algorithm_baseline<A, STR>(self, label: STR, algorithm: A) -> Self
+    where
+        A: Fn() + Send + Sync,
+        STR: Into<String>,
+    {
+        let label = label.into();
+        self.attach_algorithm(label.clone(), algorithm)
+            .baseline(&label)
+    }
+
+    /// Choose which runs get displayed as svg traces in the html page, by percentile of their
+    /// durations (from 0 for the fastest run to 100 for the slowest one).
+    /// By default we display the median and best runs (`&[50.0, 0.0]`).
+    /// When small numbers of runs make several percentiles select the same run, it is only
+    /// displayed once.
+    pub fn visualize_percentiles(self, percentiles: &[f64]) -> Self {
+        assert!(
+            percentiles.iter().all(|p| (0.0..=100.0).contains(p)),
+            "percentiles must be between 0 and 100"
+        );
+        Comparator {
+            visualized_percentiles: Some(percentiles.to_vec()),
+            ..self
         }
     }
 
-    fn record_experiments<F: FnMut() -> RunLog>(&self, run_function: F) -> Vec<RunLog> {
-        let mut experiments_logs: Vec<_> =
-            repeat_with(run_function).take(self.runs_number).collect();
+    /// Html cell with the relative difference between the time of given algorithm
+    /// and the baseline's one (empty without baseline).
+    fn baseline_cell(&self, times: &[u64], algorithm: usize) -> String {
+        match self.baseline {
+            None => String::new(),
+            Some(baseline) if baseline == algorithm => "<td>baseline</td>".to_string(),
+            Some(baseline) => {
+                let baseline_time = times[baseline].max(1) as f64;
+                let difference = (times[algorithm] as f64 - baseline_time) * 100.0 / baseline_time;
+                format!(
+                    "<td style='color:{}'>{:+.1}%</td>",
+                    if difference > 0.0 { "red" } else { "green" },
+                    difference
+                )
+            }
+        }
+    }
+
+    /// Sets the number of warmup runs executed (and discarded) before the measured runs
+    /// of each algorithm.
+    /// PRECONDITION: call that BEFORE attaching algorithms
+    pub fn warmup_runs(self, warmups_wanted: usize) -> Self {
+        Comparator {
+            warmup_runs: warmups_wanted,
+            ..self
+        }
+    }
+
+    /// Call given function after each measured run (warmup runs are not reported).
+    /// The callback is called outside of the measured region.
+    pub fn on_progress<C: FnMut(ProgressEvent) + 'a>(self, callback: C) -> Self {
+        Comparator {
+            progress: RefCell::new(Some(Box::new(callback))),
+            ..self
+        }
+    }
+
+    /// Display progress on stderr (one line per algorithm).
+    pub fn verbose(self, verbose: bool) -> Self {
+        if verbose {
+            self.on_progress(|event| {
+                eprint!(
+                    "\r{}: run {}/{} ({})\x1b[K",
+                    event.label,
+                    event.run_index + 1,
+                    event.total_runs,
+                    time_string(event.last_duration)
+                );
+                if event.run_index + 1 == event.total_runs {
+                    eprintln!();
+                }
+            })
+        } else {
+            Comparator {
+                progress: RefCell::new(None),
+                ..self
+            }
+        }
+    }
+
+    /// Report progress to the callback if any.
+    fn report_progress(
+        &self,
+        label: &str,
+        run_index: usize,
+        total_runs: usize,
+        last_duration: u64,
+    ) {
+        if let Some(callback) = self.progress.borrow_mut().as_mut() {
+            callback(ProgressEvent {
+                label,
+                run_index,
+                total_runs,
+                last_duration,
+            })
+        }
+    }
+
+    /// Run all algorithms on pools of given numbers of threads.
+    /// The html report will then display speedups and efficiencies for each number of threads
+    /// while all other statistics concern the last pool.
+    /// PRECONDITION: call that BEFORE attaching algorithms
+    ///
+    /// Fails if one of the pools cannot be built.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::Comparator;
+    ///
+    /// let v: Vec<u64> = (0..10_000).collect();
+    /// let mut html = Vec::new();
+    /// Comparator::standalone()
+    ///     .sweep_threads(&[1, 2])
+    ///     .expect("building pools failed")
+    ///     .runs_number(3)
+    ///     .attach_algorithm("sum", || assert!(v.par_iter().sum::<u64>() > 0))
+    ///     .write_logs(&mut html)
+    ///     .expect("writing html failed");
+    /// let html = String::from_utf8(html).expect("invalid utf8");
+    /// assert!(html.contains("Speedups relative to 1 threads"));
+    /// assert!(html.contains("<th>2 threads</th>"));
+    /// ```
+    pub fn sweep_threads(self, threads_numbers: &[usize]) -> Result<Self, ThreadPoolBuildError> {
+        let sweep_pools = threads_numbers
+            .iter()
+            .map(|&threads| {
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map(|pool| (threads, pool))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Comparator {
+            sweep_pools,
+            ..self
+        })
+    }
+
+    /// All pools on which we run experiments.
+    fn pools(&self) -> Vec<&ThreadPool> {
+        if self.sweep_pools.is_empty() {
+            vec![self
+                .pool
+                .expect("no thread pool: call sweep_threads before attaching algorithms")]
+        } else {
+            self.sweep_pools.iter().map(|(_, pool)| pool).collect()
+        }
+    }
+
+    /// Record experiments for a new algorithm on all our pools.
+    fn record_algorithm<F: FnMut(&ThreadPool) -> RunLog>(
+        &mut self,
+        label: String,
+        display: bool,
+        mut run_function: F,
+    ) {
+        let logs_per_pool = self
+            .pools()
+            .into_iter()
+            .map(|pool| self.record_experiments(&label, || run_function(pool)))
+            .collect();
+        self.store_algorithm(label, display, logs_per_pool);
+    }
+
+    /// Store the (sorted) logs obtained by a new algorithm on each of our pools.
+    fn store_algorithm(
+        &mut self,
+        label: String,
+        display: bool,
+        mut logs_per_pool: Vec<Vec<RunLog>>,
+    ) {
+        self.sweep_durations.push(if self.sweep_pools.is_empty() {
+            None
+        } else {
+            Some(
+                logs_per_pool
+                    .iter()
+                    .map(|logs| logs[logs.len() / 2].duration)
+                    .collect(),
+            )
+        });
+        self.logs.push(logs_per_pool.pop().expect("no pool"));
+        self.labels.push(label);
+        self.display_preferences.push(display);
+    }
+
+    fn record_experiments<F: FnMut() -> RunLog>(
+        &self,
+        label: &str,
+        mut run_function: F,
+    ) -> Vec<RunLog> {
+        // warmup runs are executed with their setup but their logs are discarded
+        for _ in 0..self.warmup_runs {
+            run_function();
+        }
+        let start = Instant::now();
+        let mut experiments_logs = Vec::new();
+        while self.keep_running(start, experiments_logs.len()) {
+            let log = run_function();
+            let run_index = experiments_logs.len();
+            let total_runs = self.expected_runs(start, run_index + 1);
+            self.keep_raw_log(label, run_index, &log);
+            self.report_progress(label, run_index, total_runs, log.duration);
+            experiments_logs.push(log);
+        }
         experiments_logs.sort_unstable_by_key(|run| run.duration);
         experiments_logs
     }
@@ -82,10 +523,9 @@ impl<'a> Comparator<'a> {
         A: Fn() + Send + Sync,
         STR: Into<String>,
     {
-        let logs = self.record_experiments(|| self.pool.logging_install(&algorithm).1);
-        self.logs.push(logs);
-        self.labels.push(label.into());
-        self.display_preferences.push(false);
+        self.record_algorithm(label.into(), false, |pool| {
+            pool.logging_install(&algorithm).1
+        });
         self
     }
     /// Log an algorithm's performances and generate svg traces.
@@ -94,10 +534,9 @@ impl<'a> Comparator<'a> {
         A: Fn() + Send + Sync,
         STR: Into<String>,
     {
-        let logs = self.record_experiments(|| self.pool.logging_install(&algorithm).1);
-        self.logs.push(logs);
-        self.labels.push(label.into());
-        self.display_preferences.push(true);
+        self.record_algorithm(label.into(), true, |pool| {
+            pool.logging_install(&algorithm).1
+        });
         self
     }
 
@@ -116,13 +555,10 @@ impl<'a> Comparator<'a> {
         T: Send + Sync,
         STR: Into<String>,
     {
-        let logs = self.record_experiments(|| {
+        self.record_algorithm(label.into(), false, |pool| {
             let input = setup_function();
-            self.pool.logging_install(|| algorithm(input)).1
+            pool.logging_install(|| algorithm(input)).1
         });
-        self.logs.push(logs);
-        self.labels.push(label.into());
-        self.display_preferences.push(false);
         self
     }
 
@@ -141,20 +577,303 @@ impl<'a> Comparator<'a> {
         T: Send + Sync,
         STR: Into<String>,
     {
-        let logs = self.record_experiments(|| {
+        self.record_algorithm(label.into(), true, |pool| {
             let input = setup_function();
-            self.pool.logging_install(|| algorithm(input)).1
+            pool.logging_install(|| algorithm(input)).1
         });
+        self
+    }
+
+    /// Attach already recorded logs of an algorithm instead of running it.
+    /// Svg traces are generated.
+    /// Algorithms can have different numbers of logs.
+    pub fn attach_logs<STR: Into<String>>(mut self, label: STR, mut logs: Vec<RunLog>) -> Self {
+        assert!(!logs.is_empty(), "no logs to attach");
+        logs.sort_unstable_by_key(|run| run.duration);
         self.logs.push(logs);
+        self.sweep_durations.push(None);
         self.labels.push(label.into());
         self.display_preferences.push(true);
         self
     }
 
-    /// This method should be called in the end to write the logs to a desired html file.
-    pub fn generate_logs<P: AsRef<Path>>(mut self, filename: P) -> Result<(), Error> {
+    /// Load logs of an algorithm from given log files and attach them.
+    /// See `attach_logs`.
+    pub fn attach_log_files<STR: Into<String>, P: AsRef<Path>>(
+        self,
+        label: STR,
+        paths: &[P],
+    ) -> Result<Self, Error> {
+        let logs = paths
+            .iter()
+            .map(RunLog::load)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.attach_logs(label, logs))
+    }
+
+    /// Start attaching algorithms sharing the same inputs.
+    ///
+    /// For each round one input is generated (un-timed) with the given setup function and a
+    /// clone of it is given to every algorithm attached with `attach_algorithm_on_shared`.
+    /// Runs are interleaved round-robin between algorithms and only happen when calling
+    /// `end_shared_setup`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let comparator = pool
+    ///     .compare()
+    ///     .runs_number(3)
+    ///     .attach_shared_setup(|| (0..10_000u32).rev().collect::<Vec<u32>>())
+    ///     .attach_algorithm_on_shared("sort", |mut v| v.par_sort())
+    ///     .attach_algorithm_on_shared_nodisplay("sort by", |mut v| v.par_sort_by(|a, b| a.cmp(b)))
+    ///     .end_shared_setup();
+    /// ```
+    pub fn attach_shared_setup<'b, I, S>(
+        self,
+        setup_function: S,
+    ) -> SharedInputComparator<'a, 'b, I, S>
+    where
+        S: FnMut() -> I,
+        I: Clone + Send,
+    {
+        SharedInputComparator {
+            comparator: self,
+            setup_function,
+            algorithms: Vec::new(),
+        }
+    }
+
+    /// Save durations of all runs of all algorithms in a csv file.
+    ///
+    /// Columns are `algorithm,run_index,duration_ns,idle_ns` followed by one column
+    /// per tag containing the total duration of the tagged subgraphs.
+    /// Runs of each algorithm are sorted by increasing duration.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let v: Vec<u64> = (0..10_000).collect();
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let mut comparator = pool
+    ///     .compare()
+    ///     .runs_number(3)
+    ///     .attach_algorithm_nodisplay("sum", || assert!(v.par_iter().sum::<u64>() > 0))
+    ///     .attach_algorithm_nodisplay("max", || assert!(v.par_iter().max().is_some()));
+    /// let path = std::env::temp_dir().join("rayon_logs_raw_results.csv");
+    /// comparator.save_raw_results(&path).expect("saving csv failed");
+    /// let csv = std::fs::read_to_string(&path).expect("reading csv failed");
+    /// assert_eq!(csv.lines().count(), 1 + 2 * 3);
+    /// ```
+    pub fn save_raw_results<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let tags = self.fuse_tags();
+        let mut csv_file = BufWriter::new(File::create(path)?);
+        writeln!(
+            csv_file,
+            "algorithm,run_index,duration_ns,idle_ns{}",
+            tags.iter()
+                .map(|t| format!(",{}", csv_field(t)))
+                .collect::<String>()
+        )?;
+        for (label, algorithm_logs) in self.labels.iter().zip(self.logs.iter()) {
+            for (run_index, run) in algorithm_logs.iter().enumerate() {
+                let activity: u64 = run.tasks_logs.iter().map(|t| t.duration()).sum();
+                let idle_time = (run.duration * run.threads_number as u64).saturating_sub(activity);
+                let stats = run.stats();
+                writeln!(
+                    csv_file,
+                    "{},{},{},{}{}",
+                    csv_field(label),
+                    run_index,
+                    run.duration,
+                    idle_time,
+                    tags.iter()
+                        .map(|t| format!(",{}", stats.get(t).map(|s| s.1).unwrap_or(0)))
+                        .collect::<String>()
+                )?;
+            }
+        }
+        csv_file.flush()
+    }
+
+    /// Write speedups chart and efficiencies table for all swept numbers of threads.
+    /// Speedups are relative to the first pool's median duration.
+    fn write_scalability<W: Write>(&self, html_file: &mut W) -> Result<(), Error> {
+        let threads_numbers: Vec<usize> = self.sweep_pools.iter().map(|(t, _)| *t).collect();
+        let speedups: Vec<Option<Vec<f64>>> = self
+            .sweep_durations
+            .iter()
+            .map(|durations| {
+                durations.as_ref().map(|durations| {
+                    durations
+                        .iter()
+                        .map(|&d| durations[0] as f64 / d as f64)
+                        .collect()
+                })
+            })
+            .collect();
+        writeln!(
+            html_file,
+            "<H2> Speedups relative to {} threads</H2>",
+            threads_numbers[0]
+        )?;
+        speedup_chart(html_file, &threads_numbers, &speedups)?;
+        writeln!(html_file, "<H2> Efficiencies (and median times)</H2>")?;
+        writeln!(
+            html_file,
+            "<table><tr><th></th><th>algorithm</th>{}</tr>",
+            threads_numbers
+                .iter()
+                .map(|t| format!("<th>{} threads</th>", t))
+                .collect::<String>()
+        )?;
+        for (name, algorithm_speedups, durations, algo_color) in izip!(
+            self.labels.iter(),
+            speedups.iter(),
+            self.sweep_durations.iter(),
+            HISTOGRAM_COLORS.iter().cycle()
+        ) {
+            let (algorithm_speedups, durations) = match (algorithm_speedups, durations) {
+                (Some(speedups), Some(durations)) => (speedups, durations),
+                _ => continue, // attached logs
+            };
+            writeln!(
+                html_file,
+                "<tr><td><span style='color:{}'>&#9632;</span></td><td>{}</td>{}</tr>",
+                algo_color,
+                html_escape(name),
+                izip!(algorithm_speedups, durations, threads_numbers.iter())
+                    .map(|(speedup, duration, threads)| format!(
+                        "<td>{:.2} ({})</td>",
+                        speedup * threads_numbers[0] as f64 / *threads as f64,
+                        time_string(*duration)
+                    ))
+                    .collect::<String>()
+            )?;
+        }
+        writeln!(html_file, "</table>",)?;
+        Ok(())
+    }
+
+    /// Compute all statistics.
+    fn compute_results(&mut self) -> ComparisonResults {
         let tags = self.fuse_tags(); // have a consistent tags numbering accross all logs
-        let mut html_file = File::create(filename)?;
+        ComparisonResults::new(&self.labels, &self.logs, tags)
+    }
+
+    /// Return all statistics instead of generating an html page.
+    /// This allows checking performances programmatically (see `ComparisonResults`).
+    /// Fails if saving raw logs failed (see `keep_raw_logs`).
+    pub fn results(mut self) -> Result<ComparisonResults, Error> {
+        let results = self.compute_results();
+        match self.raw_logs_error.take() {
+            Some(e) => Err(e),
+            None => Ok(results),
+        }
+    }
+
+    /// This method should be called in the end to write the logs to a desired html file.
+    /// Fails if writing the file or saving raw logs failed (see `keep_raw_logs`).
+    pub fn generate_logs<P: AsRef<Path>>(self, filename: P) -> Result<(), Error> {
+        let mut html_file = BufWriter::new(File::create(filename)?);
+        let written = self.write_logs(&mut html_file);
+        html_file.flush()?;
+        written
+    }
+
+    /// Write the html comparison page to given writer.
+    /// See `generate_logs`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let v: Vec<u64> = (0..10_000).collect();
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let mut html = Vec::new();
+    /// pool.compare()
+    ///     .runs_number(3)
+    ///     .attach_algorithm("sum", || assert!(v.par_iter().sum::<u64>() > 0))
+    ///     .write_logs(&mut html)
+    ///     .expect("writing html failed");
+    /// assert!(html.starts_with(b"<!DOCTYPE html>"));
+    /// ```
+    ///
+    /// Labels and tags are escaped so they cannot break the page:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::{subgraph, ThreadPoolBuilder};
+    /// use scraper::{Html, Selector};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let tag = "it's \"</script>";
+    /// let mut html = Vec::new();
+    /// pool.compare()
+    ///     .runs_number(2)
+    ///     .attach_algorithm("a<b>c", || {
+    ///         rayon_logs::join(|| subgraph(tag, 1, || ()), || ());
+    ///     })
+    ///     .attach_algorithm("<i>d</i>", || ())
+    ///     .write_logs(&mut html)
+    ///     .expect("writing html failed");
+    /// let page = Html::parse_document(std::str::from_utf8(&html).expect("invalid utf8"));
+    /// let select = |selector: &str| {
+    ///     let selector = Selector::parse(selector).expect("invalid selector");
+    ///     page.select(&selector)
+    ///         .map(|e| e.text().collect::<String>())
+    ///         .collect::<Vec<_>>()
+    /// };
+    /// assert!(select("b").is_empty() && select("i").is_empty());
+    /// assert!(select("td").iter().any(|cell| cell == "a<b>c"));
+    /// assert!(select("td").iter().any(|cell| cell == "<i>d</i>"));
+    /// assert!(select("h2")
+    ///     .iter()
+    ///     .any(|h| h.trim() == "Significance of differences with a<b>c (Mann-Whitney U test)"));
+    /// assert!(select("th").iter().any(|header| header == tag));
+    /// // each table row has as many cells as its header
+    /// let tables = Selector::parse("table").expect("invalid selector");
+    /// let rows = Selector::parse(":scope > tbody > tr").expect("invalid selector");
+    /// let cells = Selector::parse(":scope > th, :scope > td").expect("invalid selector");
+    /// for table in page.select(&tables) {
+    ///     let widths: Vec<usize> = table.select(&rows).map(|r| r.select(&cells).count()).collect();
+    ///     assert!(!widths.is_empty() && widths.iter().all(|&w| w == widths[0]));
+    /// }
+    /// // the tags of the svg trace are still a valid javascript array
+    /// let script = select("script").concat();
+    /// let tags = script
+    ///     .split("var tags_")
+    ///     .nth(1)
+    ///     .and_then(|s| s.split(" = ").nth(1))
+    ///     .and_then(|s| s.split(";\n").next())
+    ///     .expect("no tags in svg");
+    /// let tags: Vec<String> = serde_json::from_str(tags).expect("invalid tags array");
+    /// assert_eq!(tags, vec!["_NO_TAGS_", "it&#39;s &quot;&lt;/script&gt;"]);
+    /// ```
+    pub fn write_logs<W: Write>(mut self, html_file: &mut W) -> Result<(), Error> {
+        let results = self.compute_results();
+        let tags = &results.tags;
 
         writeln!(html_file, "<!DOCTYPE html>")?;
         writeln!(
@@ -173,77 +892,195 @@ table, th, td {{
         writeln!(
             html_file,
             "<H1> Comparing {} and {}</H1>",
-            first_labels.join(", "),
-            last_label
+            first_labels.iter().map(|l| html_escape(l)).join(", "),
+            html_escape(last_label)
         )?;
 
-        writeln!(
-            html_file,
-            "<H2>Distribution of execution times over {} runs ",
-            self.runs_number
-        )?;
-        for (label, color) in self.labels.iter().zip(HISTOGRAM_COLORS.iter().cycle()) {
+        let runs_numbers: Vec<usize> = self.logs.iter().map(|logs| logs.len()).collect();
+        let uniform_runs = runs_numbers.iter().all_equal();
+        if uniform_runs {
             writeln!(
                 html_file,
-                "<text style=\"color:{0}\">{0}</text> is {1}, ",
-                color, label
+                "<H2>Distribution of execution times over {} runs ",
+                runs_numbers[0]
             )?;
+        } else {
+            writeln!(html_file, "<H2>Distribution of execution times ")?;
+        }
+        if self.warmup_runs > 0 {
+            writeln!(html_file, "({} warmup runs discarded) ", self.warmup_runs)?;
+        }
+        for (label, color, runs_number) in
+            izip!(&self.labels, HISTOGRAM_COLORS.iter().cycle(), &runs_numbers)
+        {
+            if uniform_runs {
+                writeln!(
+                    html_file,
+                    "<text style=\"color:{0}\">{0}</text> is {1}, ",
+                    color,
+                    html_escape(label)
+                )?;
+            } else {
+                writeln!(
+                    html_file,
+                    "<text style=\"color:{0}\">{0}</text> is {1} ({2} runs), ",
+                    color,
+                    html_escape(label),
+                    runs_number
+                )?;
+            }
         }
         writeln!(html_file, "</H2>")?;
-        histogram(&mut html_file, &self.logs, 30)?;
-        let number_of_threads = self.logs[0][0].threads_number;
-        let statistics = Stats::get_statistics(&self.logs, number_of_threads, self.runs_number);
+        let durations: Vec<Vec<u64>> = results
+            .algorithms
+            .iter()
+            .map(|algorithm| algorithm.durations.clone())
+            .collect();
+        histogram(html_file, &durations, 30)?;
+        writeln!(html_file, "<H2> Box plots of execution times</H2>")?;
+        let boxes: Vec<_> = results
+            .algorithms
+            .iter()
+            .map(|algorithm| box_plot(&algorithm.durations))
+            .collect();
+        box_plots(html_file, &boxes)?;
+        let baseline_header = if self.baseline.is_some() {
+            "<th>vs baseline</th>"
+        } else {
+            ""
+        };
+        let mean_times: Vec<u64> = results
+            .algorithms
+            .iter()
+            .map(|algorithm| algorithm.mean_duration)
+            .collect();
         writeln!(html_file, "<H2> The Mean statistics are</H2>")?;
         writeln!(
             html_file,
-            "<table><tr><th></th><th>algorithm</th><th>net time</th>{}<th>idle time</th></tr>",
+            "<table><tr><th></th><th>algorithm</th><th>net time</th>{}<th>std deviation</th><th>95% confidence interval</th>{}<th>idle time</th><th>tasks</th><th>steals</th><th>time above 90% utilization</th></tr>",
+            baseline_header,
             tags.iter()
-                .map(|t| format!("<th>{}</th>", t))
+                .map(|t| format!("<th>{}</th>", html_escape(t)))
                 .collect::<String>()
         )?;
-        for (name, total_time, tagged_columns, idle_time, algo_color) in izip!(
-            //for (name, total_time, sequential_times, idle_time, algo_color) in izip!(
-            self.labels.iter(),
-            statistics.total_times(),
-            statistics.average_tagged_times(&tags),
-            statistics.idle_times(),
-            HISTOGRAM_COLORS.iter().cycle()
-        ) {
+        for (index, (algorithm, algo_color)) in results
+            .algorithms
+            .iter()
+            .zip(HISTOGRAM_COLORS.iter().cycle())
+            .enumerate()
+        {
             writeln!(
                 html_file,
-                "<tr><td>{}</td><td>{}</td><td>{}</td>{}<td>{}</td></tr>",
-                format!("<span style='color:{}'>&#9632;</span>", algo_color),
-                name,
-                time_string(total_time),
-                tagged_columns,
-                time_string(idle_time)
+                "<tr><td><span style='color:{}'>&#9632;</span></td><td>{}</td><td>{}</td>{}<td>{}</td><td>[{}, {}]</td>{}<td>{}</td><td>{:.1}</td><td>{:.1}</td><td>{}</td></tr>",
+                algo_color,
+                html_escape(&algorithm.label),
+                time_string(algorithm.mean_duration),
+                self.baseline_cell(&mean_times, index),
+                time_string(algorithm.deviation as u64),
+                time_string(algorithm.confidence_interval.0),
+                time_string(algorithm.confidence_interval.1),
+                algorithm
+                    .tags
+                    .iter()
+                    .map(|tag| format!(
+                        "<td>{} &plusmn; {}</td>",
+                        time_string(tag.mean_duration),
+                        time_string(tag.confidence_half_width as u64)
+                    ))
+                    .collect::<String>(),
+                time_string(algorithm.mean_idle_time),
+                algorithm.mean_tasks_count,
+                algorithm.mean_steals,
+                time_string(algorithm.mean_high_utilization_time)
             )?;
         }
         writeln!(html_file, "</table>",)?;
+        if self.labels.len() > 1 {
+            writeln!(
+                html_file,
+                "<H2> Significance of differences with {} (Mann-Whitney U test)</H2>",
+                html_escape(&self.labels[0])
+            )?;
+            writeln!(
+                html_file,
+                "<table><tr><th></th><th>algorithm</th><th>p-value</th><th>verdict</th></tr>"
+            )?;
+            for (name, (p_value, verdict), algo_color) in izip!(
+                self.labels.iter().skip(1),
+                results.significance_tests(),
+                HISTOGRAM_COLORS.iter().cycle().skip(1)
+            ) {
+                writeln!(
+                    html_file,
+                    "<tr><td><span style='color:{}'>&#9632;</span></td><td>{}</td><td>{:.4}</td><td>{}</td></tr>",
+                    algo_color,
+                    html_escape(name),
+                    p_value,
+                    verdict
+                )?;
+            }
+            writeln!(html_file, "</table>",)?;
+        }
+        if !self.sweep_pools.is_empty() {
+            self.write_scalability(html_file)?;
+        }
         writeln!(html_file, "<H2> The Median statistics are</H2>")?;
-        writeln!(html_file, "<H4> you may see tagged statistics for your tags in the form (count, duration, speed)</H4>")?;
+        writeln!(html_file, "<H4> you may see tagged statistics for your tags in the form (count, duration, speed), hover them for the count of each thread. Idle time is split between starvation (a task was waiting to be stolen) and no available parallelism, hover it for each thread</H4>")?;
+        let median_times: Vec<u64> = results
+            .algorithms
+            .iter()
+            .map(|algorithm| algorithm.median_duration)
+            .collect();
         writeln!(
             html_file,
-            "<table><tr><th></th><th>algorithm</th><th>unrolled time</th>{}<th>idle time</th></tr>",
+            "<table><tr><th></th><th>algorithm</th><th>unrolled time</th>{}{}<th>idle time</th><th>starvation / no parallelism</th></tr>",
+            baseline_header,
             tags.iter()
-                .map(|t| format!("<th>{}</th>", t))
+                .map(|t| format!("<th>{}</th>", html_escape(t)))
                 .collect::<String>()
         )?;
-        for (name, total_time, tagged_columns, idle_time, algo_color) in izip!(
-            self.labels.iter(),
-            statistics.unrolled_times_median(),
-            statistics.median_tagged_allstats(&tags),
-            statistics.idle_times_median(),
-            HISTOGRAM_COLORS.iter().cycle()
-        ) {
+        for (index, (algorithm, algo_color)) in results
+            .algorithms
+            .iter()
+            .zip(HISTOGRAM_COLORS.iter().cycle())
+            .enumerate()
+        {
             writeln!(
                 html_file,
-                "<tr><td>{}</td><td>{}</td><td>{}</td>{}<td>{}</td></tr>",
-                format!("<span style='color:{}'>&#9632;</span>", algo_color),
-                name,
-                time_string(total_time),
-                tagged_columns,
-                time_string(idle_time)
+                "<tr><td><span style='color:{}'>&#9632;</span></td><td>{}</td><td>{}</td>{}{}<td>{}</td><td title='{}'>{} / {}</td></tr>",
+                algo_color,
+                html_escape(&algorithm.label),
+                time_string(algorithm.median_unrolled_time),
+                self.baseline_cell(&median_times, index),
+                algorithm
+                    .tags
+                    .iter()
+                    .map(|tag| format!(
+                        "<td><table title='{}'><tr><td>{}</td><td>{}</td><td>{}</td></tr></table></td>",
+                        tag.median_threads_works
+                            .iter()
+                            .enumerate()
+                            .map(|(thread, work)| format!("thread {}: {}", thread, work))
+                            .join("&#10;"),
+                        tag.median_count,
+                        time_string(tag.median_duration),
+                        tag.median_speed
+                    ))
+                    .collect::<String>(),
+                time_string(algorithm.median_idle_time),
+                algorithm
+                    .median_idle_causes
+                    .iter()
+                    .enumerate()
+                    .map(|(thread, (starvation, no_parallelism))| format!(
+                        "thread {}: {} / {}",
+                        thread,
+                        time_string(*starvation),
+                        time_string(*no_parallelism)
+                    ))
+                    .join("&#10;"),
+                time_string(algorithm.median_idle_causes.iter().map(|c| c.0).sum()),
+                time_string(algorithm.median_idle_causes.iter().map(|c| c.1).sum())
             )?;
         }
         writeln!(html_file, "</table>",)?;
@@ -253,58 +1090,260 @@ table, th, td {{
             html_file,
             "<table><tr><th></th><th>algorithm</th><th>total count</th>{}</tr>",
             tags.iter()
-                .map(|t| format!("<th>{}</th>", t))
+                .map(|t| format!("<th>{}</th>", html_escape(t)))
                 .collect::<String>()
         )?;
-        for (name, total_count, tagged_counts, algo_color) in izip!(
-            self.labels.iter(),
-            statistics.get_median_task_counts(),
-            statistics.tasks_split_median(&tags),
-            HISTOGRAM_COLORS.iter().cycle()
-        ) {
+        for (algorithm, algo_color) in results
+            .algorithms
+            .iter()
+            .zip(HISTOGRAM_COLORS.iter().cycle())
+        {
             writeln!(
                 html_file,
-                "<tr><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
-                format!("<span style='color:{}'>&#9632;</span>", algo_color),
-                name,
-                total_count,
-                tagged_counts,
+                "<tr><td><span style='color:{}'>&#9632;</span></td><td>{}</td><td>{}</td>{}</tr>",
+                algo_color,
+                html_escape(&algorithm.label),
+                algorithm.median_tasks_count,
+                algorithm
+                    .tags
+                    .iter()
+                    .map(|tag| format!("<td>{}</td>", tag.median_tasks_count))
+                    .collect::<String>(),
             )?;
         }
         writeln!(html_file, "</table>",)?;
+
+        writeln!(
+            html_file,
+            "<H2> The longest tasks of the median runs are</H2>"
+        )?;
+        writeln!(
+            html_file,
+            "<table><tr><th></th><th>algorithm</th><th>task</th><th>thread</th><th>duration</th><th>tag</th></tr>"
+        )?;
+        for (runs, name, algo_color) in
+            izip!(&self.logs, &self.labels, HISTOGRAM_COLORS.iter().cycle())
+        {
+            let median_run = match runs.get(runs.len() / 2) {
+                Some(run) => run,
+                None => continue,
+            };
+            for (task_id, task, tag) in median_run.longest_tasks(HOT_TASKS_NUMBER) {
+                writeln!(
+                    html_file,
+                    "<tr><td><span style='color:{}'>&#9632;</span></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    algo_color,
+                    html_escape(name),
+                    task_id,
+                    task.thread_id,
+                    time_string(task.duration()),
+                    html_escape(tag.unwrap_or(""))
+                )?;
+            }
+        }
+        writeln!(html_file, "</table>",)?;
         if self.display_preferences.iter().any(|b| *b) {
-            writeln!(html_file, "<H2>Comparing median runs</H2>")?;
-            let median_index = (self.runs_number) / 2;
-            for (pos, (log, name)) in self.logs.iter().zip(self.labels.iter()).enumerate() {
-                if self.display_preferences[pos] {
-                    let scene = visualisation(&log[median_index]);
-                    writeln!(html_file, "<H3 align=\"left\"><u>{}</u> :</H3>", name)?;
-                    fill_svg_file(&scene, &mut html_file)?;
+            let percentiles = self
+                .visualized_percentiles
+                .clone()
+                .unwrap_or_else(|| vec![50.0, 0.0]);
+            // for each algorithm, indices of the runs we already displayed
+            let mut displayed: Vec<Vec<usize>> = self.logs.iter().map(|_| Vec::new()).collect();
+            for percentile in percentiles {
+                writeln!(
+                    html_file,
+                    "<H2>Comparing {} runs</H2>",
+                    percentile_name(percentile)
+                )?;
+                for (pos, (log, name)) in self.logs.iter().zip(self.labels.iter()).enumerate() {
+                    if !self.display_preferences[pos] {
+                        continue;
+                    }
+                    let index = percentile_index(percentile, log.len());
+                    if displayed[pos].contains(&index) {
+                        writeln!(
+                            html_file,
+                            "<H3 align=\"left\"><u>{}</u> : run {}/{} is already displayed above</H3>",
+                            html_escape(name),
+                            index + 1,
+                            log.len()
+                        )?;
+                        continue;
+                    }
+                    displayed[pos].push(index);
+                    let scene = visualisation(&log[index]);
+                    writeln!(
+                        html_file,
+                        "<H3 align=\"left\"><u>{}</u> : run {}/{} ({})</H3>",
+                        html_escape(name),
+                        index + 1,
+                        log.len(),
+                        time_string(log[index].duration)
+                    )?;
+                    fill_svg_file(&scene, html_file)?;
                     writeln!(html_file, "<p>")?;
                 }
             }
+            write!(html_file, "</body></html>")?;
+        }
+        match self.raw_logs_error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
 
-            writeln!(html_file, "<H2>Comparing best runs</H2>")?;
-            for (pos, (log, name)) in self.logs.iter().zip(self.labels.iter()).enumerate() {
-                if self.display_preferences[pos] {
-                    let scene = visualisation(&log[0]);
-                    writeln!(html_file, "<H3 align=\"left\"><u>{}</u> :</H3>", name)?;
-                    fill_svg_file(&scene, &mut html_file)?;
-                    writeln!(html_file, "<p>")?;
+/// Index of the run at given percentile among given number of sorted runs.
+fn percentile_index(percentile: f64, runs_number: usize) -> usize {
+    let last = runs_number.saturating_sub(1);
+    ((percentile / 100.0 * last as f64).round() as usize).min(last)
+}
+
+/// Name of the runs at given percentile.
+fn percentile_name(percentile: f64) -> String {
+    if percentile == 0.0 {
+        "best".to_string()
+    } else if percentile == 50.0 {
+        "median".to_string()
+    } else if percentile == 100.0 {
+        "worst".to_string()
+    } else {
+        format!("{}th percentile", percentile)
+    }
+}
+
+/// A logged run of an algorithm on a given input.
+type SharedRun<'b, I> = Box<dyn Fn(&ThreadPool, I) -> RunLog + 'b>;
+
+/// Comparator in the process of attaching algorithms working on shared inputs.
+/// Obtained with `Comparator::attach_shared_setup`.
+pub struct SharedInputComparator<'a, 'b, I, S> {
+    comparator: Comparator<'a>,
+    setup_function: S,
+    /// label, display preference and logged run of each algorithm.
+    algorithms: Vec<(String, bool, SharedRun<'b, I>)>,
+}
+
+impl<'a, 'b, I, S> SharedInputComparator<'a, 'b, I, S>
+where
+    S: FnMut() -> I,
+    I: Clone + Send,
+{
+    fn attach<A, T>(mut self, label: String, display: bool, algorithm: A) -> Self
+    where
+        A: Fn(I) -> T + Send + Sync + 'b,
+        T: Send + Sync,
+    {
+        self.algorithms.push((
+            label,
+            display,
+            Box::new(move |pool, input| pool.logging_install(|| algorithm(input)).1),
+        ));
+        self
+    }
+
+    /// Log an algorithm on the shared inputs and generate svg traces.
+    pub fn attach_algorithm_on_shared<A, T, STR>(self, label: STR, algorithm: A) -> Self
+    where
+        A: Fn(I) -> T + Send + Sync + 'b,
+        T: Send + Sync,
+        STR: Into<String>,
+    {
+        self.attach(label.into(), true, algorithm)
+    }
+
+    /// Log an algorithm on the shared inputs but do not generate svg traces.
+    pub fn attach_algorithm_on_shared_nodisplay<A, T, STR>(self, label: STR, algorithm: A) -> Self
+    where
+        A: Fn(I) -> T + Send + Sync + 'b,
+        T: Send + Sync,
+        STR: Into<String>,
+    {
+        self.attach(label.into(), false, algorithm)
+    }
+
+    /// Run all rounds for all attached algorithms and go back to the comparator.
+    pub fn end_shared_setup(mut self) -> Comparator<'a> {
+        let comparator = &self.comparator;
+        let setup_function = &mut self.setup_function;
+        let algorithms = &self.algorithms;
+        // for each algorithm, logs on each pool
+        let mut logs: Vec<Vec<Vec<RunLog>>> = algorithms.iter().map(|_| Vec::new()).collect();
+        for pool in comparator.pools() {
+            for _ in 0..comparator.warmup_runs {
+                let input = setup_function();
+                for (_, _, algorithm) in algorithms {
+                    algorithm(pool, input.clone());
                 }
             }
-            write!(html_file, "</body></html>")?;
+            let mut pool_logs: Vec<Vec<RunLog>> = algorithms.iter().map(|_| Vec::new()).collect();
+            let start = Instant::now();
+            let mut run_index = 0;
+            while comparator.keep_running(start, run_index) {
+                let input = setup_function();
+                for ((label, _, algorithm), algorithm_logs) in algorithms.iter().zip(&mut pool_logs)
+                {
+                    let log = algorithm(pool, input.clone());
+                    let total_runs = comparator.expected_runs(start, run_index + 1);
+                    comparator.keep_raw_log(label, run_index, &log);
+                    comparator.report_progress(label, run_index, total_runs, log.duration);
+                    algorithm_logs.push(log);
+                }
+                run_index += 1;
+            }
+            for (mut algorithm_logs, all_logs) in pool_logs.into_iter().zip(&mut logs) {
+                algorithm_logs.sort_unstable_by_key(|run| run.duration);
+                all_logs.push(algorithm_logs);
+            }
         }
-        Ok(())
+        let mut comparator = self.comparator;
+        for ((label, display, _), logs_per_pool) in self.algorithms.into_iter().zip(logs) {
+            comparator.store_algorithm(label, display, logs_per_pool);
+        }
+        comparator
     }
 }
 
-pub(crate) fn time_string(nano: u64) -> String {
+/// Quote given string if needed for including it in a csv file.
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Human readable version of given duration in nanoseconds.
+/// Used by all textual reports.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::time_string;
+///
+/// assert_eq!(time_string(999), "999ns");
+/// assert_eq!(time_string(1_500), "1.50us");
+/// assert_eq!(time_string(2_000_000), "2.00ms");
+/// assert_eq!(time_string(59_990_000_000), "59.99s");
+/// assert_eq!(time_string(90_000_000_000), "1m30.00s");
+/// assert_eq!(time_string(3_599_000_000_000), "59m59.00s");
+/// assert_eq!(time_string(3_725_500_000_000), "1h2m5.50s");
+/// ```
+pub fn time_string(nano: u64) -> String {
+    const MINUTE: u64 = 60_000_000_000;
+    const HOUR: u64 = 60 * MINUTE;
+    let seconds = |n: u64| n as f64 / 1_000_000_000.0;
     match nano {
         n if n < 1_000 => format!("{}ns", n),
         n if n < 1_000_000 => format!("{:.2}us", (n as f64 / 1_000.0)),
         n if n < 1_000_000_000 => format!("{:.2}ms", (n as f64 / 1_000_000.0)),
-        n if n < 60_000_000_000 => format!("{:.2}s", (n as f64 / 1_000_000_000.0)),
-        n => format!("{}m{}s", n / 60_000_000_000, n % 60_000_000_000),
+        n if n < MINUTE => format!("{:.2}s", seconds(n)),
+        n if n < HOUR => format!("{}m{:.2}s", n / MINUTE, seconds(n % MINUTE)),
+        n => format!(
+            "{}h{}m{:.2}s",
+            n / HOUR,
+            (n % HOUR) / MINUTE,
+            seconds(n % MINUTE)
+        ),
     }
 }
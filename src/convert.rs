@@ -0,0 +1,196 @@
+//! Conversion of log files to other formats (see the `json2svg` and `rlogconvert` binaries).
+use crate::log::RunLog;
+use crate::raw_events::RawLogs;
+use std::path::Path;
+
+/// Command line usage of `json2svg`.
+pub const JSON2SVG_USAGE: &str =
+    "usage: json2svg log_file svg_file\n   or: json2svg -o svg_file log_file...";
+
+/// Command line usage of `rlogconvert`.
+pub const RLOGCONVERT_USAGE: &str =
+    "usage: rlogconvert [--collapse-subgraphs] [--filter-tag tag] log_file output.{svg,dot,json,csv}";
+
+/// Load a log file, either post-processed or raw.
+fn load(path: &str) -> Result<RunLog, String> {
+    RunLog::load(path)
+        .or_else(|e| {
+            RawLogs::load(path)
+                .map_err(|_| e.to_string())
+                .and_then(|raw_logs| raw_logs.run_log().map_err(|e| e.to_string()))
+        })
+        .map_err(|e| format!("failed loading {}: {}", path, e))
+}
+
+/// Convert log files (saved with `RunLog::save` or `RawLogs::save`) to svg,
+/// given the command line arguments of `json2svg` (without the program name):
+///
+/// - `log_file svg_file` converts one log
+/// - `-o svg_file log_file...` stacks several logs on the same time scale
+///
+/// Return the message to display on failure (`JSON2SVG_USAGE` for invalid arguments).
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::{json2svg, JSON2SVG_USAGE};
+///
+/// let directory = std::env::temp_dir().join(format!("json2svg_{}", std::process::id()));
+/// std::fs::create_dir_all(&directory).expect("creating directory failed");
+/// let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+/// std::fs::write(
+///     path("log.json"),
+///     r#"{"threads_number": 1, "duration": 10,
+///     "tasks_logs": [{"start_time": 0, "end_time": 10, "thread_id": 0, "children": []}],
+///     "tags": [], "subgraphs": []}"#,
+/// )
+/// .expect("writing log failed");
+///
+/// json2svg(vec![path("log.json"), path("one.svg")]).expect("conversion failed");
+/// json2svg(vec!["-o".into(), path("two.svg"), path("log.json"), path("log.json")])
+///     .expect("conversion failed");
+/// // a single log can also be given with -o, in any position
+/// json2svg(vec![path("log.json"), "-o".into(), path("three.svg")]).expect("conversion failed");
+/// for svg in &["one.svg", "two.svg", "three.svg"] {
+///     let svg = std::fs::read_to_string(path(svg)).expect("reading svg failed");
+///     assert!(svg.starts_with("<?xml"));
+/// }
+///
+/// // invalid arguments
+/// let usage = Err(JSON2SVG_USAGE.to_string());
+/// assert_eq!(json2svg(Vec::new()), usage);
+/// assert_eq!(json2svg(vec![path("log.json")]), usage);
+/// assert_eq!(json2svg(vec![path("log.json"), path("a.svg"), path("b.svg")]), usage);
+/// assert_eq!(json2svg(vec!["-o".into(), path("a.svg")]), usage);
+/// assert_eq!(json2svg(vec![path("log.json"), "-o".into()]), usage);
+/// // invalid files
+/// let error = json2svg(vec![path("missing.json"), path("a.svg")]).unwrap_err();
+/// assert!(error.starts_with("failed loading"));
+/// std::fs::remove_dir_all(&directory).expect("removing directory failed");
+/// ```
+pub fn json2svg<I: IntoIterator<Item = String>>(arguments: I) -> Result<(), String> {
+    let mut output = None;
+    let mut inputs = Vec::new();
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        if argument == "-o" {
+            output = Some(arguments.next().ok_or(JSON2SVG_USAGE)?);
+        } else {
+            inputs.push(argument);
+        }
+    }
+    let output = match output {
+        Some(output) => output,
+        None if inputs.len() == 2 => inputs.pop().unwrap(),
+        None => return Err(JSON2SVG_USAGE.to_string()),
+    };
+    if inputs.is_empty() {
+        return Err(JSON2SVG_USAGE.to_string());
+    }
+    let logs = inputs
+        .iter()
+        .map(|path| load(path))
+        .collect::<Result<Vec<RunLog>, String>>()?;
+    let saved = if logs.len() == 1 {
+        logs[0].save_svg(&output)
+    } else {
+        RunLog::save_svg_many(&logs, &output)
+    };
+    saved.map_err(|e| format!("failed saving {}: {}", output, e))
+}
+
+/// Convert a log file (saved with `RunLog::save`) to the format given by the output file's
+/// extension: svg, dot (graphviz), json (chrome trace) or csv (tasks table),
+/// given the command line arguments of `rlogconvert` (without the program name):
+///
+/// `[--collapse-subgraphs] [--filter-tag tag] log_file output_file`
+///
+/// Return the message to display on failure: the `rlogconvert` binary then exits with code 1.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::{rlogconvert, RLOGCONVERT_USAGE};
+///
+/// let directory = std::env::temp_dir().join(format!("rlogconvert_{}", std::process::id()));
+/// std::fs::create_dir_all(&directory).expect("creating directory failed");
+/// let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+/// let log = |children: &str| {
+///     format!(
+///         r#"{{"threads_number": 1, "duration": 10,
+///         "tasks_logs": [{{"start_time": 0, "end_time": 10, "thread_id": 0,
+///                         "children": {}}}],
+///         "tags": [], "subgraphs": []}}"#,
+///         children
+///     )
+/// };
+/// std::fs::write(path("log.json"), log("[]")).expect("writing log failed");
+/// rlogconvert(vec![path("log.json"), path("log.svg")]).expect("conversion failed");
+/// rlogconvert(vec!["--collapse-subgraphs".into(), path("log.json"), path("log.dot")])
+///     .expect("conversion failed");
+/// let dot = std::fs::read_to_string(path("log.dot")).expect("reading dot failed");
+/// assert!(dot.starts_with("digraph"));
+///
+/// // well-formed json but inconsistent log
+/// std::fs::write(path("bad.json"), log("[5]")).expect("writing log failed");
+/// let error = rlogconvert(vec![path("bad.json"), path("bad.svg")]).unwrap_err();
+/// assert!(error.contains("task 0 has unknown child 5"));
+/// assert!(!directory.join("bad.svg").exists());
+///
+/// // invalid arguments
+/// let usage = Err(RLOGCONVERT_USAGE.to_string());
+/// assert_eq!(rlogconvert(vec![path("log.json")]), usage);
+/// assert_eq!(rlogconvert(vec![path("log.json"), "--filter-tag".into()]), usage);
+/// assert!(rlogconvert(vec![path("log.json"), path("log.txt")]).is_err());
+/// assert!(rlogconvert(vec!["--filter-tag".into(), "t".into(), path("log.json"), path("a.svg")])
+///     .unwrap_err()
+///     .starts_with("no tag t"));
+/// std::fs::remove_dir_all(&directory).expect("removing directory failed");
+/// ```
+pub fn rlogconvert<I: IntoIterator<Item = String>>(arguments: I) -> Result<(), String> {
+    let mut collapse_subgraphs = false;
+    let mut filtered_tag = None;
+    let mut paths = Vec::new();
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--collapse-subgraphs" => collapse_subgraphs = true,
+            "--filter-tag" => filtered_tag = Some(arguments.next().ok_or(RLOGCONVERT_USAGE)?),
+            option if option.starts_with("--") => {
+                return Err(format!("unknown option {}\n{}", option, RLOGCONVERT_USAGE))
+            }
+            _ => paths.push(argument),
+        }
+    }
+    if paths.len() != 2 {
+        return Err(RLOGCONVERT_USAGE.to_string());
+    }
+    let (input, output) = (&paths[0], &paths[1]);
+    let mut log = RunLog::load(input).map_err(|e| format!("failed loading {}: {}", input, e))?;
+    if let Some(tag) = filtered_tag {
+        if !log.tags().contains(&tag) {
+            return Err(format!("no tag {} in {}", tag, input));
+        }
+        log = log.filter_by_tag(&tag);
+    }
+    let extension = Path::new(output)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    if collapse_subgraphs && extension != "dot" {
+        return Err("--collapse-subgraphs is only available for dot files".to_string());
+    }
+    let saved = match extension {
+        "svg" => log.save_svg(output),
+        "dot" => log.save_dot(output, collapse_subgraphs),
+        "json" => log.save_chrome_trace(output),
+        "csv" => log.save_csv(output),
+        _ => {
+            return Err(format!(
+                "unknown output format for {}\n{}",
+                output, RLOGCONVERT_USAGE
+            ))
+        }
+    };
+    saved.map_err(|e| format!("failed saving {}: {}", output, e))
+}
@@ -0,0 +1,158 @@
+//! Measures for tagging subgraphs which work on stable rust (no `perf` feature needed).
+//!
+//! Counters are per thread: only what the calling thread does is measured,
+//! not the work of tasks stolen by other threads.
+use crate::{custom_subgraph, custom_subgraph_multi};
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+
+// bytes allocated so far by this thread (only counted with a `CountingAllocator`)
+thread_local!(static ALLOCATED: Cell<usize> = const { Cell::new(0) });
+
+/// Bytes allocated so far by the current thread.
+fn allocated_bytes() -> usize {
+    ALLOCATED.try_with(|a| a.get()).unwrap_or(0)
+}
+
+/// Count given number of newly allocated bytes for the current thread.
+fn count_allocation(bytes: usize) {
+    // the thread local might be already destroyed when a thread exits
+    let _ = ALLOCATED.try_with(|a| a.set(a.get().wrapping_add(bytes)));
+}
+
+/// Allocator wrapper counting bytes allocated by each thread (for `subgraph_alloc`).
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{subgraph_alloc, CountingAllocator, ThreadPoolBuilder};
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+///
+/// fn main() {
+///     let pool = ThreadPoolBuilder::new()
+///         .num_threads(2)
+///         .build()
+///         .expect("building pool failed");
+///     let (v, log) = pool.logging_install(|| subgraph_alloc("fill", || vec![0u64; 1_000]));
+///     assert_eq!(v.len(), 1_000);
+///     assert!(log.stats()["fill"].0 >= 8_000);
+/// }
+/// ```
+pub struct CountingAllocator<A> {
+    allocator: A,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Count allocations done with given allocator.
+    pub const fn new(allocator: A) -> Self {
+        CountingAllocator { allocator }
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        self.allocator.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        self.allocator.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.allocator.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // only growing allocates new bytes
+        count_allocation(new_size.saturating_sub(layout.size()));
+        self.allocator.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Tag a subgraph with the number of bytes allocated by the calling thread while running it.
+///
+/// Allocations are only counted when the program uses a `CountingAllocator`
+/// as its global allocator (0 is recorded otherwise).
+pub fn subgraph_alloc<OP, R>(tag: &'static str, op: OP) -> R
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph(
+        tag,
+        allocated_bytes,
+        |before| allocated_bytes().wrapping_sub(before),
+        op,
+    )
+}
+
+/// (minor page faults, voluntary context switches) of the calling thread so far.
+#[cfg(target_os = "linux")]
+fn thread_usage() -> (usize, usize) {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) } == 0 {
+        (usage.ru_minflt as usize, usage.ru_nvcsw as usize)
+    } else {
+        (0, 0)
+    }
+}
+
+/// (minor page faults, voluntary context switches) of the calling thread so far.
+/// (only known on linux).
+#[cfg(not(target_os = "linux"))]
+fn thread_usage() -> (usize, usize) {
+    (0, 0)
+}
+
+/// Tag a subgraph with the minor page faults and voluntary context switches
+/// of the calling thread while running it (measured with `getrusage`, only on linux).
+///
+/// Page faults are the work amount used for speeds. Both values also appear in
+/// `RunLog::stats` under the pseudo-tags "tag/minor page faults" and
+/// "tag/voluntary context switches".
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{subgraph_rusage, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (v, log) = pool.logging_install(|| subgraph_rusage("fill", || vec![1u8; 1 << 24]));
+/// assert_eq!(v.len(), 1 << 24);
+/// let stats = log.stats();
+/// assert!(stats.contains_key("fill"));
+/// assert!(stats.contains_key("fill/voluntary context switches"));
+/// if cfg!(target_os = "linux") {
+///     // touching 16MB of fresh memory page faults
+///     assert!(stats["fill/minor page faults"].0 > 0);
+/// }
+/// ```
+pub fn subgraph_rusage<OP, R>(tag: &'static str, op: OP) -> R
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph_multi(
+        tag,
+        thread_usage,
+        |(faults, switches)| {
+            let (new_faults, new_switches) = thread_usage();
+            vec![
+                ("minor page faults", new_faults.saturating_sub(faults)),
+                (
+                    "voluntary context switches",
+                    new_switches.saturating_sub(switches),
+                ),
+            ]
+        },
+        op,
+    )
+}
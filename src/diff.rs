@@ -0,0 +1,233 @@
+//! Comparison of two runs of the same algorithm (see `RunLog::diff`).
+use crate::compare::time_string;
+use crate::log::RunLog;
+use crate::raw_events::TimeStamp;
+use std::fmt;
+
+/// Changes of one tag between two runs.
+#[derive(Debug, Clone)]
+pub struct TagDiff {
+    /// the tag.
+    pub tag: String,
+    /// (tasks count, total duration, speed in work units per nanosecond) of all subgraphs
+    /// with this tag in the first run (none if the tag was added).
+    pub before: Option<(usize, TimeStamp, f64)>,
+    /// (tasks count, total duration, speed in work units per nanosecond) of all subgraphs
+    /// with this tag in the second run (none if the tag was removed).
+    pub after: Option<(usize, TimeStamp, f64)>,
+}
+
+impl TagDiff {
+    /// Change in the total duration of the tagged subgraphs (missing tags count as 0).
+    pub fn duration_change(&self) -> i64 {
+        self.after.map(|a| a.1 as i64).unwrap_or(0) - self.before.map(|b| b.1 as i64).unwrap_or(0)
+    }
+
+    /// Change in the number of tasks in the tagged subgraphs (missing tags count as 0).
+    pub fn tasks_count_change(&self) -> i64 {
+        self.after.map(|a| a.0 as i64).unwrap_or(0) - self.before.map(|b| b.0 as i64).unwrap_or(0)
+    }
+
+    /// Change in the speed of the tagged subgraphs (missing tags count as 0).
+    pub fn speed_change(&self) -> f64 {
+        self.after.map(|a| a.2).unwrap_or(0.0) - self.before.map(|b| b.2).unwrap_or(0.0)
+    }
+
+    /// Relative change (in percents) of the total duration of the tagged subgraphs.
+    /// Nothing if the tag is missing in one of the runs or took no time in the first one.
+    pub fn relative_duration_change(&self) -> Option<f64> {
+        match (self.before, self.after) {
+            (Some(before), Some(after)) if before.1 > 0 => {
+                Some((after.1 as f64 - before.1 as f64) * 100.0 / before.1 as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Differences between two runs, obtained with `RunLog::diff`.
+/// Tags are matched by name.
+#[derive(Debug, Clone)]
+pub struct RunLogDiff {
+    /// durations of the first and second run.
+    pub durations: (TimeStamp, TimeStamp),
+    /// idle times of the first and second run.
+    pub idle_times: (TimeStamp, TimeStamp),
+    /// changes of each tag: tags of the first run (in order) then tags added in the second run.
+    pub tags: Vec<TagDiff>,
+}
+
+/// Idle time of given run: time threads spent outside of any task.
+fn idle_time(log: &RunLog) -> TimeStamp {
+    let activity: TimeStamp = log.tasks_logs.iter().map(|t| t.duration()).sum();
+    // logging overhead can make activity exceed the available time
+    (log.duration.saturating_mul(log.threads_number as u64)).saturating_sub(activity)
+}
+
+/// (tasks count, total duration, speed) of each tag of given run, in tags order.
+fn tags_statistics(log: &RunLog) -> Vec<(String, (usize, TimeStamp, f64))> {
+    let stats = log.stats();
+    let counts = log.count_tasks();
+    log.tags
+        .iter()
+        .map(|tag| {
+            let (work, duration) = stats.get(tag).map(|s| (s.0, s.1)).unwrap_or((0, 0));
+            let speed = if duration == 0 {
+                0.0
+            } else {
+                work as f64 / duration as f64
+            };
+            let count = counts.get(tag).cloned().unwrap_or(0);
+            (tag.clone(), (count, duration, speed))
+        })
+        .collect()
+}
+
+/// Relative change between given values, as a percentage (nothing when starting from 0).
+fn percentage(before: TimeStamp, after: TimeStamp) -> String {
+    if before == 0 {
+        String::new()
+    } else {
+        format!(
+            " ({:+.1}%)",
+            (after as f64 - before as f64) * 100.0 / before as f64
+        )
+    }
+}
+
+impl RunLogDiff {
+    /// Compare given runs.
+    pub(crate) fn new(before: &RunLog, after: &RunLog) -> Self {
+        let before_tags = tags_statistics(before);
+        let after_tags = tags_statistics(after);
+        let find = |tags: &[(String, (usize, TimeStamp, f64))], tag: &str| {
+            tags.iter().find(|(t, _)| t == tag).map(|(_, s)| *s)
+        };
+        let tags = before_tags
+            .iter()
+            .map(|(tag, stats)| TagDiff {
+                tag: tag.clone(),
+                before: Some(*stats),
+                after: find(&after_tags, tag),
+            })
+            .chain(
+                after_tags
+                    .iter()
+                    .filter(|(tag, _)| find(&before_tags, tag).is_none())
+                    .map(|(tag, stats)| TagDiff {
+                        tag: tag.clone(),
+                        before: None,
+                        after: Some(*stats),
+                    }),
+            )
+            .collect();
+        RunLogDiff {
+            durations: (before.duration, after.duration),
+            idle_times: (idle_time(before), idle_time(after)),
+            tags,
+        }
+    }
+
+    /// Change in the total duration.
+    pub fn duration_change(&self) -> i64 {
+        self.durations.1 as i64 - self.durations.0 as i64
+    }
+
+    /// Change in the idle time.
+    pub fn idle_time_change(&self) -> i64 {
+        self.idle_times.1 as i64 - self.idle_times.0 as i64
+    }
+
+    /// Tags present in both runs whose duration increased by more than given percentage.
+    pub fn regressions(&self, threshold: f64) -> impl Iterator<Item = &TagDiff> {
+        self.tags.iter().filter(move |t| {
+            t.relative_duration_change()
+                .map(|change| change > threshold)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Tags only present in the second run.
+    pub fn added_tags(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|t| t.before.is_none())
+            .map(|t| t.tag.as_str())
+    }
+
+    /// Tags only present in the first run.
+    pub fn removed_tags(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|t| t.after.is_none())
+            .map(|t| t.tag.as_str())
+    }
+}
+
+impl fmt::Display for RunLogDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "duration: {} -> {}{}",
+            time_string(self.durations.0),
+            time_string(self.durations.1),
+            percentage(self.durations.0, self.durations.1)
+        )?;
+        writeln!(
+            f,
+            "idle time: {} -> {}{}",
+            time_string(self.idle_times.0),
+            time_string(self.idle_times.1),
+            percentage(self.idle_times.0, self.idle_times.1)
+        )?;
+        if self.tags.is_empty() {
+            return Ok(());
+        }
+        let width = self.tags.iter().map(|t| t.tag.len()).max().unwrap_or(0);
+        writeln!(
+            f,
+            "{:<width$} | {:>12} | {:>30} | {:>20}",
+            "tag",
+            "tasks",
+            "duration",
+            "speed",
+            width = width
+        )?;
+        for tag in &self.tags {
+            match (tag.before, tag.after) {
+                (Some(before), Some(after)) => writeln!(
+                    f,
+                    "{:<width$} | {:>12} | {:>30} | {:>20}",
+                    tag.tag,
+                    format!("{} -> {}", before.0, after.0),
+                    format!(
+                        "{} -> {}{}",
+                        time_string(before.1),
+                        time_string(after.1),
+                        percentage(before.1, after.1)
+                    ),
+                    format!("{:.3} -> {:.3}", before.2, after.2),
+                    width = width
+                )?,
+                (Some(before), None) => writeln!(
+                    f,
+                    "{:<width$} | removed ({} tasks, {})",
+                    tag.tag,
+                    before.0,
+                    time_string(before.1),
+                    width = width
+                )?,
+                (None, Some(after)) => writeln!(
+                    f,
+                    "{:<width$} | added ({} tasks, {})",
+                    tag.tag,
+                    after.0,
+                    time_string(after.1),
+                    width = width
+                )?,
+                (None, None) => unreachable!("tag in no run"),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+//! Export logs to formats other tools understand (graphviz, chrome tracing, csv).
+use crate::compare::{csv_field, time_string};
+use crate::log::RunLog;
+use itertools::Itertools;
+use serde_json::json;
+use std::io::{Error, Write};
+
+/// Quote given string for including it in a graphviz label (newlines become line breaks).
+fn dot_string(label: &str) -> String {
+    format!(
+        "\"{}\"",
+        label
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Write the tasks graph in graphviz's dot format.
+/// Manual dependencies are dashed.
+/// When collapsing, each outermost subgraph becomes a single node.
+pub(crate) fn write_dot<W: Write>(
+    log: &RunLog,
+    writer: &mut W,
+    collapse_subgraphs: bool,
+) -> Result<(), Error> {
+    writeln!(writer, "digraph tasks {{")?;
+    writeln!(writer, "  node [shape=box];")?;
+    // subgraph each task is collapsed into
+    let collapsed: Vec<Option<usize>> = (0..log.tasks_logs.len())
+        .map(|task| log.outermost_subgraph(task).filter(|_| collapse_subgraphs))
+        .collect();
+    let nodes: Vec<String> = collapsed
+        .iter()
+        .enumerate()
+        .map(|(task, subgraph)| match subgraph {
+            Some(subgraph) => format!("s{}", subgraph),
+            None => format!("t{}", task),
+        })
+        .collect();
+    let durations = log.subgraphs_sums(|task| task.duration());
+    for &subgraph in collapsed.iter().flatten().unique() {
+        let (_, _, tag, work) = log.subgraphs[subgraph];
+        writeln!(
+            writer,
+            "  s{} [label={}];",
+            subgraph,
+            dot_string(&format!(
+                "{}\nwork: {}\n{}",
+                log.tags[tag],
+                work,
+                time_string(durations[subgraph])
+            ))
+        )?;
+    }
+    for (task_id, task) in log.tasks_logs.iter().enumerate() {
+        if collapsed[task_id].is_none() {
+            writeln!(
+                writer,
+                "  t{} [label={}];",
+                task_id,
+                dot_string(&format!(
+                    "{}{}\nthread {}\n{}{}",
+                    task_id,
+                    log.task_tag(task_id)
+                        .map(|tag| format!(" ({})", tag))
+                        .unwrap_or_default(),
+                    task.thread_id,
+                    time_string(task.duration()),
+                    task.work
+                        .map(|work| format!("\nwork: {}", work.work_share))
+                        .unwrap_or_default()
+                ))
+            )?;
+        }
+    }
+    let edges = log
+        .tasks_logs
+        .iter()
+        .enumerate()
+        .flat_map(|(task_id, task)| task.children.iter().map(move |&c| (task_id, c)))
+        .map(|(father, child)| (&nodes[father], &nodes[child]))
+        .filter(|(father, child)| father != child)
+        .unique();
+    for (father, child) in edges {
+        writeln!(writer, "  {} -> {};", father, child)?;
+    }
+    let dependencies = log
+        .dependencies
+        .iter()
+        .map(|&(producer, consumer)| (&nodes[producer], &nodes[consumer]))
+        .filter(|(producer, consumer)| producer != consumer)
+        .unique();
+    for (producer, consumer) in dependencies {
+        writeln!(writer, "  {} -> {} [style=dashed];", producer, consumer)?;
+    }
+    writeln!(writer, "}}")
+}
+
+/// Write tasks and marks as a json array of chrome trace events
+/// (for chrome://tracing or perfetto). Times are in microseconds.
+pub(crate) fn write_chrome_trace<W: Write>(log: &RunLog, writer: &mut W) -> Result<(), Error> {
+    let micro_seconds = |time: u64| time as f64 / 1000.0;
+    let tasks = log.tasks_logs.iter().enumerate().map(|(task_id, task)| {
+        json!({
+            "name": log.task_tag(task_id).unwrap_or("task"),
+            "ph": "X",
+            "pid": 0,
+            "tid": task.thread_id,
+            "ts": micro_seconds(task.start_time),
+            "dur": micro_seconds(task.duration()),
+            "args": {
+                "task": task_id,
+                "children": task.children,
+                "work": task.work.map(|work| work.work_share),
+            },
+        })
+    });
+    let marks = log.marks.iter().map(|(time, label)| {
+        json!({
+            "name": label,
+            "ph": "i",
+            "s": "g",
+            "pid": 0,
+            "tid": 0,
+            "ts": micro_seconds(*time),
+        })
+    });
+    let events: Vec<_> = tasks.chain(marks).collect();
+    serde_json::to_writer(writer, &events)?;
+    Ok(())
+}
+
+/// Write one line per task in csv format.
+pub(crate) fn write_csv<W: Write>(log: &RunLog, writer: &mut W) -> Result<(), Error> {
+    writeln!(writer, "task,thread,start,end,duration,tag,work,children")?;
+    for (task_id, task) in log.tasks_logs.iter().enumerate() {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            task_id,
+            task.thread_id,
+            task.start_time,
+            task.end_time,
+            task.duration(),
+            log.task_tag(task_id).map(csv_field).unwrap_or_default(),
+            task.work
+                .map(|work| work.work_share.to_string())
+                .unwrap_or_default(),
+            task.children.iter().join(" ")
+        )?;
+    }
+    Ok(())
+}
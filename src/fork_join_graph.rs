@@ -2,13 +2,17 @@
 use crate::raw_events::{TaskId, TimeStamp};
 use crate::svg::{Rectangle, Scene, COLORS};
 type BlockId = usize;
+use crate::compare::time_string;
 use crate::log::{RunLog, TaskLog};
 use itertools::{iproduct, Itertools};
 use std::collections::HashMap;
-use std::iter::{once, repeat};
+use std::iter::{once, repeat, successors};
 
 const VERTICAL_GAP: f64 = 0.2;
 
+/// number of time windows in the utilization chart
+const UTILIZATION_BUCKETS: usize = 100;
+
 use crate::svg::Point;
 
 trait BlockVector {
@@ -73,114 +77,99 @@ pub(crate) enum Block {
     Parallel(Vec<BlockId>),
 }
 
-/// iterate on all ancestors blocks (including initial block)
+/// iterate on all ancestors blocks (including initial block), up to the root sequence.
 fn ancestors_blocks<'a>(
     blocks_fathers: &'a HashMap<BlockId, BlockId>,
     block: BlockId,
 ) -> impl Iterator<Item = BlockId> + 'a {
-    (0..).scan(block, move |b, _| {
-        blocks_fathers
-            .get(b)
-            .map(|f| {
-                let current_b = *b;
-                *b = *f;
-                current_b
-            })
-            .or_else(|| Some(*b)) // it repeats on last but I guess it's ok
-    })
+    successors(Some(block), move |b| blocks_fathers.get(b).cloned())
 }
 
-/// find first block common ancestor of two blocks
+/// find first block common ancestor of two blocks.
+/// the root sequence is an ancestor of every block so we fall back on it
+/// if the two chains of ancestors never meet.
 fn common_ancestor_block(
     blocks_fathers: &HashMap<BlockId, BlockId>,
-    blocks: &[BlockId],
-) -> Option<BlockId> {
-    blocks
-        .iter()
-        .map(|b| ancestors_blocks(blocks_fathers, *b))
-        .kmerge_by(|b1, b2| b1 > b2) // blocks order is topological order
-        .tuples()
-        .find(|(b1, b2)| b1 == b2)
-        .map(|(b1, _)| b1)
+    b1: BlockId,
+    b2: BlockId,
+) -> BlockId {
+    ancestors_blocks(blocks_fathers, b1)
+        .merge_by(ancestors_blocks(blocks_fathers, b2), |a1, a2| a1 > a2) // blocks order is topological order
+        .tuple_windows()
+        .find(|(a1, a2)| a1 == a2)
+        .map(|(a, _)| a)
+        .unwrap_or_else(|| ancestors_blocks(blocks_fathers, b1).last().unwrap())
 }
 
 /// Create a fork join graph (stored in a vec). This is used to convert the logs into
 /// a graphical display of animated rectangles.
+/// We also need all fathers of each task.
 /// Returns the fork join graph and a vector containing all root blocks, sorted by starting times.
-pub(crate) fn create_graph(tasks: &[TaskLog]) -> (Vec<Block>, Vec<BlockId>) {
+pub(crate) fn create_graph(
+    tasks: &[TaskLog],
+    fathers: &[Vec<TaskId>],
+) -> (Vec<Block>, Vec<BlockId>) {
     // graph is composed of sequential or parallel blocks
     let mut graph = Vec::with_capacity(tasks.len());
     let mut root_nodes = Vec::new();
 
-    // we need a quick way to find all fathers for a given node
-    let mut fathers: HashMap<TaskId, Vec<TaskId>> = HashMap::new();
-    for (task_id, task) in tasks.iter().enumerate() {
-        for child in &task.children {
-            fathers.entry(*child).or_insert_with(Vec::new).push(task_id);
-        }
-    }
-
     // now, we are going to compute in which block is every node
-    let mut blocks: HashMap<TaskId, BlockId> = HashMap::new();
+    let mut blocks: Vec<BlockId> = vec![0; tasks.len()];
 
     // store all parallel blocks (one for each multi-children node)
-    let mut parallel_blocks: HashMap<TaskId, BlockId> = HashMap::new();
+    let mut parallel_blocks: Vec<BlockId> = vec![0; tasks.len()];
 
     // also store what is the father block of each block
     let mut blocks_fathers: HashMap<BlockId, BlockId> = HashMap::new();
 
-    // we sort by starting time to be sure fathers are processed before children
+    // we sort by starting time to be sure fathers are processed before children.
+    // ties are broken by ids so that the layout is always the same.
     let mut sorted_tasks: Vec<TaskId> = (0..tasks.len()).collect();
-    sorted_tasks.sort_by(|t1, t2| {
-        tasks[*t1]
-            .start_time
-            .partial_cmp(&tasks[*t2].start_time)
-            .unwrap()
-    });
+    sorted_tasks.sort_by_key(|&task_id| (tasks[task_id].start_time, task_id));
 
     for task_id in &sorted_tasks {
         let task = &tasks[*task_id];
-        let sequence_id = if !fathers.contains_key(task_id) {
+        let sequence_id = if fathers[*task_id].is_empty() {
             // we are one of the roots
             let block_id = graph.len();
             graph.push(Block::Sequence(Vec::new()));
             root_nodes.push((task_id, block_id));
             block_id
-        } else if fathers[task_id].len() == 1 {
+        } else if fathers[*task_id].len() == 1 {
             // check if we have brothers
-            let father = fathers[task_id][0];
+            let father = fathers[*task_id][0];
             if tasks[father].children.len() == 1 {
                 // one father, no brothers, we go directly after him
                 // in his block
-                blocks[&father]
+                blocks[father]
             } else {
                 // several brothers, we need to create a new sequence
                 let sequential_block = graph.add_sequence();
-                blocks_fathers.insert(sequential_block, blocks[&father]); // save where to go back
-                let parallel_block = parallel_blocks[&father];
+                blocks_fathers.insert(sequential_block, blocks[father]); // save where to go back
+                let parallel_block = parallel_blocks[father];
                 graph.parallel(parallel_block).push(sequential_block);
                 sequential_block
             }
         } else {
             // several fathers
             // we need to find the first (while going up) common ancestor block
-            let mut direct_fathers_blocks = fathers[task_id].iter().map(|f| blocks[f]);
+            let mut direct_fathers_blocks = fathers[*task_id].iter().map(|&f| blocks[f]);
             let starting_block = direct_fathers_blocks.next().unwrap();
             direct_fathers_blocks.fold(starting_block, |b1, b2| {
-                common_ancestor_block(&blocks_fathers, &[b1, b2]).expect("no common ancestor")
+                common_ancestor_block(&blocks_fathers, b1, b2)
             })
         };
         let new_block = graph.add_task(*task_id, (*task).clone());
         graph.sequence(sequence_id).push(new_block);
-        blocks.insert(*task_id, sequence_id);
+        blocks[*task_id] = sequence_id;
 
         // now create a parallel block after us when we have multiple children
         if task.children.len() > 1 {
             // several children, we create a parallel block
             let parallel_block = graph.add_parallel();
             // add it to our block
-            graph.sequence(blocks[task_id]).push(parallel_block);
-            parallel_blocks.insert(*task_id, parallel_block);
+            graph.sequence(blocks[*task_id]).push(parallel_block);
+            parallel_blocks[*task_id] = parallel_block;
         }
     }
     root_nodes.sort_by_key(|(t, _)| tasks[**t].start_time);
@@ -216,7 +205,7 @@ fn compute_blocks_dimensions(
                 },
             )
         }),
-        Block::Task(_, ref t) => ((t.end_time - t.start_time) as f64, 1.0),
+        Block::Task(_, ref t) => (t.duration() as f64, 1.0),
     };
     blocks_dimensions[index] = dimensions;
     dimensions
@@ -306,15 +295,19 @@ fn generate_visualisation(
             acc
         }),
         Block::Task(task_id, ref t) => {
-            let duration = (t.end_time - t.start_time) as f64;
+            let duration = t.duration() as f64;
             let information = tasks_information.remove(&task_id).unwrap();
-            scene.rectangles.push(Rectangle::new(
+            let mut rectangle = Rectangle::new(
                 COLORS[t.thread_id % COLORS.len()],
                 positions[index],
                 (duration, 1.0),
                 (t.start_time, t.end_time),
                 information,
-            ));
+            );
+            rectangle.panicked = t.panicked;
+            rectangle.in_progress = t.in_progress;
+            rectangle.task = Some(task_id);
+            scene.rectangles.push(rectangle);
             (
                 vec![(positions[index].0 + duration / 2.0, positions[index].1)],
                 vec![(
@@ -326,6 +319,41 @@ fn generate_visualisation(
     }
 }
 
+/// Display the utilization timeline of given log as an area chart one row high
+/// (one bar per time window).
+/// y is vertical start for the chart.
+fn compute_utilization(log: &RunLog, starting_position: &(f64, f64), scene: &mut Scene) {
+    let first_time = match log.tasks_logs.iter().map(|t| t.start_time).min() {
+        Some(first_time) => first_time,
+        None => return,
+    };
+    let bucket_width = log.duration as f64 / UTILIZATION_BUCKETS as f64;
+    for (index, utilization) in log
+        .utilization_timeline(UTILIZATION_BUCKETS)
+        .into_iter()
+        .enumerate()
+    {
+        let start = index as f64 * bucket_width;
+        scene.rectangles.push(Rectangle::new(
+            [0.5, 0.5, 0.5],
+            (
+                starting_position.0 + start,
+                starting_position.1 + 1.0 - utilization,
+            ),
+            (bucket_width, utilization),
+            (
+                first_time + start as TimeStamp,
+                first_time + (start + bucket_width) as TimeStamp,
+            ),
+            once((
+                "_NO_TAGS_".to_string(),
+                (format!("utilization: {:.1}%", utilization * 100.0), 1.0),
+            ))
+            .collect(),
+        ));
+    }
+}
+
 /// Take all taskslogs and compute idle periods animations for each thread.
 /// add all rectangles to given vector.
 /// given height (height of animated running tasks) enables us to center the display vertically.
@@ -336,6 +364,9 @@ fn compute_idle_times(
     threads_number: usize,
     scene: &mut Scene,
 ) {
+    if tasks.is_empty() {
+        return;
+    }
     // do one pass to figure out the last recorded time.
     // we need it to figure out who is idle at the end.
     let last_time = tasks.iter().map(|t| t.end_time).max().unwrap();
@@ -381,7 +412,7 @@ pub fn visualisation(log: &RunLog) -> Scene {
     let mut scene = Scene::new(log);
 
     let tasks = &log.tasks_logs;
-    let (g, root_blocks) = create_graph(tasks);
+    let (g, root_blocks) = create_graph(tasks, log.fathers());
 
     // compute recursively the width and height of each block
     let mut blocks_dimensions = Vec::with_capacity(g.len());
@@ -419,9 +450,94 @@ pub fn visualisation(log: &RunLog) -> Scene {
         );
     }
 
+    // manual dependencies go from the bottom of the producing task to the top of the consuming one
+    let tasks_positions: HashMap<TaskId, (f64, f64)> = g
+        .iter()
+        .zip(&positions)
+        .filter_map(|(block, position)| match block {
+            Block::Task(task_id, task) => Some((
+                *task_id,
+                (position.0 + task.duration() as f64 / 2.0, position.1),
+            )),
+            _ => None,
+        })
+        .collect();
+    scene
+        .segments
+        .extend(log.dependencies.iter().map(|(producer, consumer)| {
+            let start = tasks_positions[producer];
+            ((start.0, start.1 + 1.0), tasks_positions[consumer])
+        }));
+
     let starting_position = (width as f64 * 0.1, height + 1.0);
+    compute_utilization(log, &starting_position, &mut scene);
 
+    let starting_position = (
+        starting_position.0,
+        starting_position.1 + 1.0 + VERTICAL_GAP,
+    );
     compute_idle_times(tasks, &starting_position, log.threads_number, &mut scene);
 
     scene
 }
+
+/// Computes a graphical view of several logs, stacked vertically with a header for each one.
+/// All logs share the same time scale so their widths are comparable and tags are fused
+/// across all logs.
+/// The scene is empty if there are no logs.
+pub fn visualisation_many(logs: &[RunLog]) -> Scene {
+    let mut global_tags = HashMap::new();
+    for log in logs {
+        log.scan_tags(&mut global_tags);
+    }
+    let scenes: Vec<Scene> = logs
+        .iter()
+        .map(|log| {
+            let mut log = log.clone();
+            log.update_tags(&global_tags);
+            visualisation(&log)
+        })
+        .collect();
+    let mut scene = Scene {
+        rectangles: Vec::new(),
+        segments: Vec::new(),
+        tags: scenes.first().map(|s| s.tags.clone()).unwrap_or_default(),
+        texts: Vec::new(),
+        marks: Vec::new(),
+    };
+    let mut y_offset = 0.0;
+    for (index, (log, log_scene)) in logs.iter().zip(scenes).enumerate() {
+        // leave one row for the header
+        scene.texts.push((
+            (0.0, y_offset),
+            format!(
+                "log {}: {} threads, {}",
+                index,
+                log.threads_number,
+                time_string(log.duration)
+            ),
+        ));
+        let graph_offset = y_offset + 1.0 + VERTICAL_GAP;
+        let height = log_scene
+            .rectangles
+            .iter()
+            .map(|r| r.y + r.height)
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap_or(0.0);
+        scene
+            .rectangles
+            .extend(log_scene.rectangles.into_iter().map(|mut r| {
+                r.y += graph_offset;
+                r
+            }));
+        scene.segments.extend(
+            log_scene
+                .segments
+                .into_iter()
+                .map(|(s, e)| ((s.0, s.1 + graph_offset), (e.0, e.1 + graph_offset))),
+        );
+        scene.marks.extend(log_scene.marks);
+        y_offset = graph_offset + height + 1.0;
+    }
+    scene
+}
@@ -0,0 +1,115 @@
+//! Advice on tasks sizes (see `RunLog::granularity_report`).
+use crate::compare::time_string;
+use crate::log::RunLog;
+use crate::raw_events::TimeStamp;
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Estimated cost (in nanoseconds) of creating, scheduling and logging one task.
+/// Each task logs a few events and the `timings` example measures around
+/// 1 micro second per logged event.
+pub const TASK_OVERHEAD: TimeStamp = 2_000;
+
+/// Leaves shorter than this many overheads are too small.
+const SMALL_TASK_FACTOR: TimeStamp = 10;
+
+/// Above this fraction of the work in small leaves we advise coarser tasks.
+const SMALL_WORK_LIMIT: f64 = 0.1;
+
+/// Durations of the leaf tasks of a run, obtained with `RunLog::granularity_report`.
+/// Leaves are the tasks which did not fork: they have at most one child
+/// (the continuation after their join).
+#[derive(Debug, Clone, Serialize)]
+pub struct GranularityReport {
+    /// estimated overhead of each task (in nanoseconds).
+    pub overhead: TimeStamp,
+    /// leaves shorter than this duration are too small (ten times the overhead).
+    pub threshold: TimeStamp,
+    /// leaves durations summarized as (smallest duration, number of leaves, total duration)
+    /// for each power of two range of durations, by increasing durations.
+    pub buckets: Vec<(TimeStamp, usize, TimeStamp)>,
+    /// total duration of all tasks.
+    pub work: TimeStamp,
+    /// total duration of the leaves shorter than the threshold.
+    pub small_leaves_work: TimeStamp,
+}
+
+impl GranularityReport {
+    /// Analyze given run, assuming each task costs the given overhead.
+    pub(crate) fn new(log: &RunLog, overhead: TimeStamp) -> Self {
+        let threshold = overhead * SMALL_TASK_FACTOR;
+        let mut buckets: BTreeMap<TimeStamp, (usize, TimeStamp)> = BTreeMap::new();
+        let mut small_leaves_work = 0;
+        for task in log.tasks_logs.iter().filter(|t| t.children.len() <= 1) {
+            let duration = task.duration();
+            let smallest = (duration + 1).next_power_of_two() / 2;
+            let bucket = buckets.entry(smallest).or_insert((0, 0));
+            bucket.0 += 1;
+            bucket.1 += duration;
+            if duration < threshold {
+                small_leaves_work += duration;
+            }
+        }
+        GranularityReport {
+            overhead,
+            threshold,
+            buckets: buckets
+                .into_iter()
+                .map(|(smallest, (count, duration))| (smallest, count, duration))
+                .collect(),
+            work: log.work(),
+            small_leaves_work,
+        }
+    }
+
+    /// Fraction (between 0 and 1) of the work done in leaves shorter than the threshold.
+    pub fn small_work_fraction(&self) -> f64 {
+        if self.work == 0 {
+            0.0
+        } else {
+            self.small_leaves_work as f64 / self.work as f64
+        }
+    }
+
+    /// Are too many tasks too small ?
+    pub fn too_fine(&self) -> bool {
+        self.small_work_fraction() > SMALL_WORK_LIMIT
+    }
+
+    /// A one line advice like
+    /// "increase sequential cutoff: 43% of work is in tasks < 20.00us".
+    pub fn suggestion(&self) -> String {
+        format!(
+            "{}: {:.0}% of work is in tasks < {}",
+            if self.too_fine() {
+                "increase sequential cutoff"
+            } else {
+                "granularity looks fine"
+            },
+            self.small_work_fraction() * 100.0,
+            time_string(self.threshold)
+        )
+    }
+}
+
+impl fmt::Display for GranularityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12} | {:>10} | {:>12} | {:>6}",
+            "leaves from", "count", "duration", "work"
+        )?;
+        for &(smallest, count, duration) in &self.buckets {
+            writeln!(
+                f,
+                "{:>12} | {:>10} | {:>12} | {:>5.1}%",
+                time_string(smallest),
+                count,
+                time_string(duration),
+                duration as f64 * 100.0 / self.work.max(1) as f64
+            )?;
+        }
+        writeln!(f, "{}", self.suggestion())
+    }
+}
@@ -1,13 +1,105 @@
 //! Provides logging for parallel iterators.
-use crate::pool::{log, next_iterator_id, next_task_id};
+use crate::pool::{
+    in_sampled_out_region, log, next_iterator_id, next_task_id, sampled_out, subgraph,
+    SampledOutRegion,
+};
 use crate::raw_events::{now, IteratorId, RayonEvent, TaskId};
 use rayon::iter::plumbing::*;
 use rayon::iter::*;
+use std::cell::Cell;
 
 /// `Logged` is an iterator that logs all tasks created.
+/// Each leaf task records how many items it folded (see `TaskLog::iterator_work`).
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::prelude::*;
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (sum, log) = pool.logging_install(|| (0..1_000_000u64).into_par_iter().sum::<u64>());
+/// assert_eq!(sum, 999_999 * 1_000_000 / 2);
+/// let items: usize = log
+///     .tasks()
+///     .iter()
+///     .filter_map(|t| t.iterator_work)
+///     .map(|(_, items)| items)
+///     .sum();
+/// assert_eq!(items, 1_000_000);
+/// ```
+///
+/// Iterator tasks are timestamped with the same clock as all other events,
+/// so they can be mixed with subgraphs in the same run.
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::prelude::*;
+/// use rayon_logs::{subgraph, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (sum, log) = pool.logging_install(|| {
+///     subgraph("sum", 10_000, || (0..10_000u64).into_par_iter().sum::<u64>())
+/// });
+/// assert_eq!(sum, 9_999 * 10_000 / 2);
+/// assert!(log.tasks().len() > 3);
+/// let first_start = log.tasks().iter().map(|t| t.start_time).min().unwrap();
+/// assert!(log
+///     .tasks()
+///     .iter()
+///     .all(|t| t.start_time <= t.end_time && t.end_time - first_start <= log.duration()));
+/// ```
+///
+/// Unindexed iterators (like bridges) split into a valid fork-join graph:
+/// all tasks but the first one have a parent.
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon::iter::ParallelBridge;
+/// use rayon_logs::prelude::*;
+/// use rayon_logs::{Logged, RunLog, ThreadPoolBuilder};
+///
+/// fn roots(log: &RunLog) -> usize {
+///     let mut has_parent = vec![false; log.tasks().len()];
+///     for child in log.tasks().iter().flat_map(|t| t.children.iter()) {
+///         has_parent[*child] = true;
+///     }
+///     has_parent.iter().filter(|p| !**p).count()
+/// }
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(4)
+///     .build()
+///     .expect("building pool failed");
+/// let (sum, log) = pool.logging_install(|| Logged::new((0..1_000u64).par_bridge()).sum::<u64>());
+/// assert_eq!(sum, 999 * 1_000 / 2);
+/// assert_eq!(roots(&log), 1);
+/// let (sum, log) = pool.logging_install(|| {
+///     Logged::new((0..100u64).par_bridge().flat_map(|x| (0..x).par_bridge())).sum::<u64>()
+/// });
+/// assert_eq!(sum, (0..100u64).flat_map(|x| 0..x).sum::<u64>());
+/// assert_eq!(roots(&log), 1);
+/// let (sum, log) = pool.logging_install(|| {
+///     Logged::new((0..1_000u64).par_bridge().map(|x| Some(x).filter(|&x| x < 10)).while_some())
+///         .count()
+/// });
+/// assert!(sum <= 1_000);
+/// assert_eq!(roots(&log), 1);
+/// ```
 #[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
 pub struct Logged<I: ParallelIterator> {
     base: I,
+    /// tag of the subgraph containing all tasks of the iterator
+    label: Option<&'static str>,
+    /// do we log the tasks ourselves or only tag the ones logged by inner iterators
+    log_tasks: bool,
 }
 
 impl<I: ParallelIterator> Logged<I> {
@@ -16,7 +108,115 @@ impl<I: ParallelIterator> Logged<I> {
     where
         I: ParallelIterator,
     {
-        Logged { base }
+        Logged {
+            base,
+            label: None,
+            log_tasks: true,
+        }
+    }
+
+    /// Create a new `Logged` iterator whose tasks are all tagged with given label.
+    /// The label appears in the svg's tags and in `RunLog::stats` with the iterator's length
+    /// (if known) as work amount.
+    /// Several iterators with the same label share the same tag.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::{Logged, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (sum, log) = pool.logging_install(|| {
+    ///     // an iterator coming directly from rayon
+    ///     let numbers = rayon::iter::IntoParallelIterator::into_par_iter(0..1_000u64);
+    ///     let squares: Vec<u64> = Logged::named(numbers, "squares").map(|x| x * x).collect();
+    ///     // `log` tags iterators of the prelude (which already log their tasks)
+    ///     squares.par_iter().log("sum").sum::<u64>()
+    /// });
+    /// assert_eq!(sum, (0..1_000u64).map(|x| x * x).sum::<u64>());
+    /// assert!(log.tags().contains(&"squares".to_string()));
+    /// assert!(log.tags().contains(&"sum".to_string()));
+    /// let stats = log.stats();
+    /// assert_eq!(stats["squares"].0, 1_000);
+    /// assert_eq!(stats["sum"].0, 1_000);
+    /// ```
+    pub fn named(base: I, label: &'static str) -> Logged<I> {
+        Logged {
+            base,
+            label: Some(label),
+            log_tasks: true,
+        }
+    }
+
+    /// Tag the tasks logged by the `Logged` iterators inside base (see `prelude::LogParallelIterator`).
+    pub(crate) fn tagging(base: I, label: &'static str) -> Logged<I> {
+        Logged {
+            base,
+            label: Some(label),
+            log_tasks: false,
+        }
+    }
+}
+
+/// Logged version of rayon's `split`: a parallel iterator on the parts obtained by
+/// recursively splitting data with splitter.
+/// Each split is a task forking into two parts and each part ends up folded in a leaf task.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::prelude::*;
+/// use rayon_logs::{split, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// // sum all numbers from 0 to 1023 by splitting the range in halves
+/// let (sum, log) = pool.logging_install(|| {
+///     split(0..1024u32, |range| {
+///         let middle = range.start + (range.end - range.start) / 2;
+///         if range.end - range.start <= 64 {
+///             (range, None)
+///         } else {
+///             (range.start..middle, Some(middle..range.end))
+///         }
+///     })
+///     .map(|range| range.sum::<u32>())
+///     .sum::<u32>()
+/// });
+/// assert_eq!(sum, 1023 * 1024 / 2);
+/// let mut svg = Vec::new();
+/// log.write_svg(&mut svg).expect("writing svg failed");
+/// assert!(svg.starts_with(b"<?xml"));
+/// // rayon stops splitting when there are enough parts for all threads
+/// let parts: usize = log
+///     .tasks()
+///     .iter()
+///     .filter_map(|t| t.iterator_work)
+///     .map(|(_, parts)| parts)
+///     .sum();
+/// assert!(parts >= 1 && parts <= 1024 / 64);
+/// ```
+pub fn split<D, S>(data: D, splitter: S) -> Logged<rayon::iter::Split<D, S>>
+where
+    D: Send,
+    S: Fn(D) -> (D, Option<D>) + Sync + Send,
+{
+    Logged::new(rayon::iter::split(data, splitter))
+}
+
+/// Run op (driving the iterator) in a subgraph if we have a label.
+fn in_subgraph<R>(label: Option<&'static str>, len: Option<usize>, op: impl FnOnce() -> R) -> R {
+    match label {
+        Some(label) => subgraph(label, len.unwrap_or(0), op),
+        None => op(),
     }
 }
 
@@ -31,21 +231,15 @@ where
     where
         C: UnindexedConsumer<Self::Item>,
     {
-        let continuing_task_id = next_task_id();
-        let consumer_id = next_task_id();
-        let iterator_id = next_iterator_id();
-        let consumer1 = LoggedConsumer {
-            base: consumer,
-            part: self.base.opt_len().map(|l| (0, l)),
-            iterator_id,
-            consumer_id,
-            continuing_task_id,
-        };
-        //log(RayonEvent::IteratorStart(consumer1.iterator_id));
-        logs!(RayonEvent::Child(consumer_id), RayonEvent::TaskEnd(now()));
-        let r = self.base.drive_unindexed(consumer1);
-        log(RayonEvent::TaskStart(continuing_task_id, now()));
-        r
+        let len = self.base.opt_len();
+        let (base, log_tasks) = (self.base, self.log_tasks);
+        in_subgraph(self.label, len, || {
+            if log_tasks {
+                logged_drive_unindexed(base, consumer)
+            } else {
+                base.drive_unindexed(consumer)
+            }
+        })
     }
 
     fn opt_len(&self) -> Option<usize> {
@@ -53,6 +247,29 @@ where
     }
 }
 
+fn logged_drive_unindexed<I, C>(base: I, consumer: C) -> C::Result
+where
+    I: ParallelIterator,
+    C: UnindexedConsumer<I::Item>,
+{
+    let continuing_task_id = next_task_id();
+    let consumer_id = next_task_id();
+    let iterator_id = next_iterator_id();
+    let consumer1 = LoggedConsumer::new(
+        consumer,
+        base.opt_len().map(|l| (0, l)),
+        iterator_id,
+        consumer_id,
+        continuing_task_id,
+        in_sampled_out_region(),
+    );
+    //log(RayonEvent::IteratorStart(consumer1.iterator_id));
+    logs!(RayonEvent::Child(consumer_id), RayonEvent::TaskEnd(now()));
+    let r = base.drive_unindexed(consumer1);
+    log(RayonEvent::TaskStart(continuing_task_id, now()));
+    r
+}
+
 impl<T, I> IndexedParallelIterator for Logged<I>
 where
     I: IndexedParallelIterator<Item = T>,
@@ -62,22 +279,15 @@ where
     where
         C: Consumer<Self::Item>,
     {
-        let part = Some((0, self.base.len()));
-        let continuing_task_id = next_task_id();
-        let consumer_id = next_task_id();
-        let iterator_id = next_iterator_id();
-        let consumer1 = LoggedConsumer {
-            base: consumer,
-            part,
-            iterator_id,
-            consumer_id,
-            continuing_task_id,
-        };
-        //log(RayonEvent::IteratorStart(consumer1.iterator_id));
-        logs!(RayonEvent::Child(consumer_id), RayonEvent::TaskEnd(now()));
-        let r = self.base.drive(consumer1);
-        log(RayonEvent::TaskStart(continuing_task_id, now()));
-        r
+        let len = self.base.len();
+        let (base, log_tasks) = (self.base, self.log_tasks);
+        in_subgraph(self.label, Some(len), || {
+            if log_tasks {
+                logged_drive(base, consumer)
+            } else {
+                base.drive(consumer)
+            }
+        })
     }
 
     fn len(&self) -> usize {
@@ -112,6 +322,29 @@ where
     }
 }
 
+fn logged_drive<I, C>(base: I, consumer: C) -> C::Result
+where
+    I: IndexedParallelIterator,
+    C: Consumer<I::Item>,
+{
+    let continuing_task_id = next_task_id();
+    let consumer_id = next_task_id();
+    let iterator_id = next_iterator_id();
+    let consumer1 = LoggedConsumer::new(
+        consumer,
+        Some((0, base.len())),
+        iterator_id,
+        consumer_id,
+        continuing_task_id,
+        in_sampled_out_region(),
+    );
+    //log(RayonEvent::IteratorStart(consumer1.iterator_id));
+    logs!(RayonEvent::Child(consumer_id), RayonEvent::TaskEnd(now()));
+    let r = base.drive(consumer1);
+    log(RayonEvent::TaskStart(continuing_task_id, now()));
+    r
+}
+
 /// ////////////////////////////////////////////////////////////////////////
 
 struct LoggedProducer<P> {
@@ -157,12 +390,42 @@ where
 /// ////////////////////////////////////////////////////////////////////////
 /// Consumer implementation
 
+/// Unindexed splits (`split_off_left`) only borrow the consumer which then goes on
+/// as the right part. Task ids are therefore updated in place.
 struct LoggedConsumer<C> {
     base: C,
     part: Option<(usize, usize)>,
     iterator_id: IteratorId,
-    consumer_id: TaskId,
-    continuing_task_id: TaskId,
+    consumer_id: Cell<TaskId>,
+    continuing_task_id: Cell<TaskId>,
+    /// reducer obtained with `to_reducer` and waiting for the next `split_off_left`
+    pending_reducer: Cell<Option<TaskId>>,
+    /// parts are split off while folding (like in `flat_map`) and not by a bridge
+    folding: Cell<bool>,
+    /// created in a sampled-out region: record nothing, on any thread
+    unsampled: bool,
+}
+
+impl<C> LoggedConsumer<C> {
+    fn new(
+        base: C,
+        part: Option<(usize, usize)>,
+        iterator_id: IteratorId,
+        consumer_id: TaskId,
+        continuing_task_id: TaskId,
+        unsampled: bool,
+    ) -> Self {
+        LoggedConsumer {
+            base,
+            part,
+            iterator_id,
+            consumer_id: Cell::new(consumer_id),
+            continuing_task_id: Cell::new(continuing_task_id),
+            pending_reducer: Cell::new(None),
+            folding: Cell::new(false),
+            unsampled,
+        }
+    }
 }
 
 impl<T, C> Consumer<T> for LoggedConsumer<C>
@@ -178,33 +441,48 @@ where
         let consumer_id_1 = next_task_id();
         let consumer_id_2 = next_task_id();
         let continuing_reducer_id = next_task_id();
+        let _region = if self.unsampled {
+            Some(SampledOutRegion::enter())
+        } else {
+            None
+        };
         logs!(
-            RayonEvent::TaskStart(self.consumer_id, now()),
+            RayonEvent::TaskStart(self.consumer_id.get(), now()),
             RayonEvent::Child(consumer_id_1),
             RayonEvent::Child(consumer_id_2)
         );
+        if let Some((start, end)) = self.part {
+            log(RayonEvent::IteratorSplit(
+                start + index,
+                index,
+                end - start - index,
+            ));
+        }
         let (left, right, reducer) = self.base.split_at(index);
         let left_part = self.part.map(|(s, _)| (s, s + index));
         let right_part = self.part.map(|(s, e)| (s + index, e));
         let r = (
-            LoggedConsumer {
-                base: left,
-                part: left_part,
-                iterator_id: self.iterator_id,
-                consumer_id: consumer_id_1,
-                continuing_task_id: continuing_reducer_id,
-            },
-            LoggedConsumer {
-                base: right,
-                part: right_part,
-                iterator_id: self.iterator_id,
-                consumer_id: consumer_id_2,
-                continuing_task_id: continuing_reducer_id,
-            },
+            LoggedConsumer::new(
+                left,
+                left_part,
+                self.iterator_id,
+                consumer_id_1,
+                continuing_reducer_id,
+                self.unsampled,
+            ),
+            LoggedConsumer::new(
+                right,
+                right_part,
+                self.iterator_id,
+                consumer_id_2,
+                continuing_reducer_id,
+                self.unsampled,
+            ),
             LoggedReducer {
                 rayon_reducer: reducer,
                 id: continuing_reducer_id,
-                continuing_task_id: self.continuing_task_id,
+                continuing_task_id: self.continuing_task_id.get(),
+                unsampled: self.unsampled,
             },
         );
         log(RayonEvent::TaskEnd(now()));
@@ -212,7 +490,12 @@ where
     }
 
     fn into_folder(self) -> LoggedFolder<C::Folder> {
-        log(RayonEvent::TaskStart(self.consumer_id, now()));
+        let region = if self.unsampled {
+            Some(SampledOutRegion::enter())
+        } else {
+            None
+        };
+        log(RayonEvent::TaskStart(self.consumer_id.get(), now()));
         //log(RayonEvent::IteratorTask(
         //    self.consumer_id,
         //    self.iterator_id,
@@ -222,7 +505,10 @@ where
 
         LoggedFolder {
             base: self.base.into_folder(),
-            continuing_task_id: self.continuing_task_id,
+            iterator_id: self.iterator_id,
+            items: 0,
+            continuing_task_id: self.continuing_task_id.get(),
+            _region: region,
         }
     }
 
@@ -236,27 +522,53 @@ where
     C: UnindexedConsumer<T>,
     T: Send,
 {
+    /// Like `split_at`: the current part's task forks into the left part
+    /// and the right part (which is `self` from now on).
+    /// With a bridge `to_reducer` comes first and both parts continue with the reducer.
+    /// While folding (`flat_map` for example) reductions happen right away so
+    /// parts continue directly with the current continuation.
     fn split_off_left(&self) -> Self {
-        let split_task_id = next_task_id();
-        let continuing_task_id = next_task_id();
-        log(RayonEvent::TaskStart(split_task_id, now()));
-        let consumer_id = next_task_id();
-        let r = LoggedConsumer {
-            base: self.base.split_off_left(),
-            part: None,
-            iterator_id: self.iterator_id,
-            consumer_id,
-            continuing_task_id,
+        let left_id = next_task_id();
+        let right_id = next_task_id();
+        let _region = if self.unsampled {
+            Some(SampledOutRegion::enter())
+        } else {
+            None
         };
+        match self.pending_reducer.take() {
+            Some(reducer_id) => self.continuing_task_id.set(reducer_id),
+            None => self.folding.set(true),
+        }
+        logs!(
+            RayonEvent::TaskStart(self.consumer_id.get(), now()),
+            RayonEvent::Child(left_id),
+            RayonEvent::Child(right_id)
+        );
+        let left = LoggedConsumer::new(
+            self.base.split_off_left(),
+            None,
+            self.iterator_id,
+            left_id,
+            self.continuing_task_id.get(),
+            self.unsampled,
+        );
+        self.consumer_id.set(right_id);
         log(RayonEvent::TaskEnd(now()));
-        r
+        left
     }
+
     fn to_reducer(&self) -> LoggedReducer<C::Reducer> {
         let reducer_id = next_task_id();
+        let folding = self.folding.get();
+        if !folding {
+            self.pending_reducer.set(Some(reducer_id));
+        }
         LoggedReducer {
             rayon_reducer: self.base.to_reducer(),
             id: reducer_id,
-            continuing_task_id: self.continuing_task_id,
+            continuing_task_id: self.continuing_task_id.get(),
+            // reductions while folding are not tasks of their own
+            unsampled: self.unsampled || folding,
         }
     }
 }
@@ -266,7 +578,12 @@ where
 
 struct LoggedFolder<F> {
     base: F,
+    iterator_id: IteratorId,
+    /// how many items we folded so far
+    items: usize,
     continuing_task_id: TaskId,
+    /// folding happens in a sampled-out region as long as this is alive
+    _region: Option<SampledOutRegion>,
 }
 
 impl<T, F> Folder<T> for LoggedFolder<F>
@@ -279,14 +596,19 @@ where
     fn consume(self, item: T) -> Self {
         LoggedFolder {
             base: self.base.consume(item),
+            iterator_id: self.iterator_id,
+            items: self.items + 1,
             continuing_task_id: self.continuing_task_id,
+            _region: self._region,
         }
     }
 
     fn complete(self) -> F::Result {
         let continuing_task_id = self.continuing_task_id;
+        let (iterator_id, items) = (self.iterator_id, self.items);
         let result = self.base.complete();
         logs!(
+            RayonEvent::IteratorWork(iterator_id, items),
             RayonEvent::Child(continuing_task_id),
             RayonEvent::TaskEnd(now())
         );
@@ -304,6 +626,7 @@ struct LoggedReducer<R> {
     rayon_reducer: R,
     id: TaskId,
     continuing_task_id: TaskId,
+    unsampled: bool,
 }
 
 impl<T, R> Reducer<T> for LoggedReducer<R>
@@ -312,6 +635,10 @@ where
     T: Send,
 {
     fn reduce(self, left: T, right: T) -> T {
+        if self.unsampled {
+            let rayon_reducer = self.rayon_reducer;
+            return sampled_out(|| rayon_reducer.reduce(left, right));
+        }
         log(RayonEvent::TaskStart(self.id, now()));
         let r = self.rayon_reducer.reduce(left, right);
         let continuing_task_id = self.continuing_task_id;
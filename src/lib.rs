@@ -10,6 +10,7 @@
 //! currently log all parts of rayon.
 //!
 //! - the global `ThreadPool` is not logged so it is *required* to use a `ThreadPoolBuilder`.
+//!   `logging_install` runs on a logged pool of the default size instead.
 //! - not all of rayon's traits are implemented. In particular no `IndexedParallelIterator` (no zip),
 //! no `FromParallelIterator`  (no  collect)...
 //! - `par_sort` is logged but it is not directly rayon's `par_sort` but a copy-pasted version of
@@ -19,6 +20,8 @@
 //! using `json2svg`.
 //! - each log generates an overhead of around 1 micro seconds. This is due to thread_local being
 //! very slow.
+//! - enabling the `disabled` feature turns all logging functions into direct calls to rayon,
+//!   so you can keep them in your code at no cost when not profiling.
 //!
 //! With this being said, here is a small example:
 //!
@@ -37,7 +40,12 @@
 //! Running this code will create a `log_0.json` file.
 //! You can then use `cargo run --bin json2svg -- log_0.json example_sum.svg` to view the log.
 //! The resulting file should be viewed in a web browser since it is animated.
-//! The bars below the graph represent idle times.
+//! Several logs can be stacked on the same time scale with
+//! `cargo run --bin json2svg -- -o all.svg log_0.json log_1.json`.
+//! Logs can also be explored interactively with
+//! `cargo run --features viewer --bin log_viewer -- log_0.json`.
+//! Below the graph, the grey area chart shows the fraction of threads working over time
+//! and the bars represent idle times.
 //!
 //! <div>
 //! <img src="http://www-id.imag.fr/Laboratoire/Membres/Wagner_Frederic/images/downgraded_iter_sum.svg"/>
@@ -45,32 +53,74 @@
 #![type_length_limit = "2097152"] // it seems we have types with long names
 #![deny(missing_docs)]
 #![warn(clippy::all)]
+// with the `disabled` feature logging code is only used by `ThreadPool` (and produces nothing)
+#![cfg_attr(feature = "disabled", allow(dead_code, unused_imports))]
 
 mod pool; // this comes first because it exports the logs macro
 
+mod clock;
+pub use crate::clock::Clock;
+
 mod iterator;
 mod storage;
-pub use crate::iterator::Logged;
+#[cfg(not(feature = "disabled"))]
+pub use crate::iterator::{split, Logged};
+pub use crate::pool::ThreadPool;
+#[cfg(not(feature = "disabled"))]
 pub use crate::pool::{
-    custom_subgraph, end_subgraph, join, join_context, start_subgraph, subgraph, ThreadPool,
+    broadcast, current_subgraph_stack, current_task_id, custom_subgraph, custom_subgraph_measured,
+    custom_subgraph_multi, dependency_handle, end_subgraph, join, join3, join4, join_context,
+    logging_enabled, mark, sample_rate, spawn, start_subgraph, subgraph, subgraph_owned, unlogged,
+    DependencyHandle,
+};
+#[cfg(feature = "disabled")]
+mod passthrough;
+#[cfg(feature = "disabled")]
+pub use crate::passthrough::{
+    broadcast, current_subgraph_stack, current_task_id, custom_subgraph, custom_subgraph_measured,
+    custom_subgraph_multi, dependency_handle, end_subgraph, join, join3, join4, join_context,
+    logging_enabled, mark, sample_rate, scope, scope_fifo, spawn, split, start_subgraph, subgraph,
+    subgraph_owned, unlogged, DependencyHandle, Logged, Scope, ScopeFifo,
 };
+pub use crate::pool::{logging_install, perf_available};
 #[cfg(feature = "perf")]
-pub use crate::pool::{subgraph_cache_event, subgraph_hardware_event, subgraph_software_event};
+pub use crate::pool::{
+    subgraph_cache_event, subgraph_cache_event_measured, subgraph_hardware_event,
+    subgraph_hardware_event_measured, subgraph_hardware_events, subgraph_perf,
+    subgraph_software_event, subgraph_software_event_measured,
+};
+mod counters;
+pub use crate::counters::{subgraph_alloc, subgraph_rusage, CountingAllocator};
 mod builder;
 pub mod prelude;
 pub use crate::builder::ThreadPoolBuilder;
 mod scope;
+#[cfg(not(feature = "disabled"))]
 pub use crate::scope::{scope, scope_fifo, Scope, ScopeFifo};
 mod fork_join_graph;
-mod stats;
-pub use crate::fork_join_graph::visualisation;
+mod results;
+pub mod stats;
+pub use crate::fork_join_graph::{visualisation, visualisation_many};
+pub use crate::results::{AlgorithmResults, ComparisonResults, TagResults};
 pub(crate) mod compare;
+mod convert;
+pub use crate::convert::{json2svg, rlogconvert, JSON2SVG_USAGE, RLOGCONVERT_USAGE};
+mod diff;
+mod export;
+pub use crate::diff::{RunLogDiff, TagDiff};
+mod granularity;
+pub use crate::granularity::{GranularityReport, TASK_OVERHEAD};
 mod log;
-pub use crate::log::RunLog;
+pub mod visualisation;
+pub use crate::log::{validate_log, LogError, RunLog, TaskLog, ThreadInfo, WorkInformation};
 mod rayon_algorithms;
+pub use crate::rayon_algorithms::merge::par_merge;
+pub use crate::rayon_algorithms::scan::{par_prefix_sum, par_prefix_sum_with_chunk_size};
 pub(crate) mod svg;
-pub use crate::compare::Comparator;
-pub(crate) mod raw_events;
+pub use crate::svg::{duration_histogram, duration_histogram_counts};
+pub use crate::compare::{time_string, Comparator, ProgressEvent, SharedInputComparator};
+mod raw_events;
+pub use crate::raw_events::{RawLogs, RayonEvent};
 /// We re-export rayon's `current_num_threads`.
 pub use rayon::current_num_threads;
 pub use rayon::current_thread_index;
@@ -1,22 +1,27 @@
 //! Provide structures holding all logged information for all tasks.
 //! This structure provides intermediate level information.
 //! It is a dag of tasks stored in a vector (using indices as pointers).
-use crate::fork_join_graph::visualisation;
+use crate::clock::Clock;
+use crate::diff::RunLogDiff;
+use crate::export::{write_chrome_trace, write_csv, write_dot};
+use crate::fork_join_graph::{visualisation, visualisation_many};
+use crate::granularity::GranularityReport;
 use crate::raw_events::{RayonEvent, TaskId, TimeStamp};
-use crate::storage::Storage;
-use crate::svg::write_svg_file;
+use crate::stats::{deviations, quantile};
+use crate::svg::{write_svg, write_svg_file, Scene};
 use itertools::Itertools;
 use serde_derive::{Deserialize, Serialize};
 use serde_json;
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io;
-use std::io::ErrorKind;
-use std::iter::successors;
-use std::iter::{repeat, repeat_with};
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::iter::{repeat, successors};
 use std::path::Path;
-use std::{cmp::Ordering, sync::Arc};
+use std::sync::OnceLock;
 
 /// The final information produced for log viewers.
 /// A 'task' here is not a rayon task but a subpart of one.
@@ -36,42 +41,537 @@ pub struct TaskLog {
     pub thread_id: usize,
     /// indices of children tasks (either when forking or joining)
     pub children: Vec<TaskId>,
+    /// did the task's code panic
+    #[serde(default)]
+    pub panicked: bool,
+    /// was the task stolen (only known for tasks created by `join` and `join_context`)
+    #[serde(default)]
+    pub migrated: bool,
+    /// was the task still running when logs were taken (see `ThreadPool::snapshot`).
+    /// its end time is then the last observed time.
+    #[serde(default)]
+    pub in_progress: bool,
+    /// for leaves of logged iterators: iterator id and number of items folded
+    #[serde(default)]
+    pub iterator_work: Option<(usize, usize)>,
+    /// for splits of logged indexed iterators: split position and sizes of both parts
+    /// (the parts are the first two children)
+    #[serde(default)]
+    pub iterator_split: Option<(usize, usize, usize)>,
+    /// time between the end of the last father and our start (queueing or stealing latency).
+    /// this is 0 for the root task.
+    #[serde(default)]
+    pub scheduling_delay: TimeStamp,
+    /// innermost subgraph containing the task and the task's part of its work
+    /// (none for untagged tasks).
+    #[serde(default)]
+    pub work: Option<WorkInformation>,
 }
 
 impl TaskLog {
     /// Return how much time it took to run this task.
+    /// (0 for tasks ending before starting, which only happens in broken log files).
     pub fn duration(&self) -> TimeStamp {
-        self.end_time - self.start_time
+        self.end_time.saturating_sub(self.start_time)
+    }
+}
+
+/// Subgraph information of a task, computed when building the log.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{subgraph, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| {
+///     subgraph("outer", 10, || {
+///         rayon_logs::join(|| subgraph("inner", 5, || ()), || ())
+///     })
+/// });
+/// let tag = |work: &rayon_logs::WorkInformation| {
+///     log.tags()[log.subgraphs()[work.subgraph].2].as_str()
+/// };
+/// // only the first join task is inside the inner subgraph
+/// let inner_works: Vec<_> = log
+///     .tasks()
+///     .iter()
+///     .filter_map(|t| t.work.as_ref())
+///     .filter(|w| tag(w) == "inner")
+///     .collect();
+/// // it does all the inner work
+/// assert_eq!(inner_works.len(), 1);
+/// assert_eq!(inner_works[0].work_share, 5);
+/// // the first task is not tagged
+/// assert!(log.tasks()[0].work.is_none());
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct WorkInformation {
+    /// index in `RunLog::subgraphs` of the innermost subgraph containing the task.
+    pub subgraph: usize,
+    /// part of the subgraph's work done by the task, extrapolated from its part
+    /// of the subgraph's duration.
+    pub work_share: usize,
+}
+
+/// Part of given work done by a task, assuming work is proportional to time.
+fn work_share(work: usize, task_duration: TimeStamp, subgraph_duration: TimeStamp) -> usize {
+    (work as f64 * task_duration as f64 / subgraph_duration.max(1) as f64).round() as usize
+}
+
+/// Information on a thread of the pool, recorded when it starts.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .thread_name(|index| format!("worker-{}", index))
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+/// assert_eq!(log.threads_info().len(), 2);
+/// for info in log.threads_info() {
+///     assert_eq!(info.name, Some(format!("worker-{}", info.index)));
+///     assert_eq!(info.os_id.is_some(), cfg!(target_os = "linux"));
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThreadInfo {
+    /// index of the thread in rayon's pool
+    pub index: usize,
+    /// id of the thread for the operating system (only known on linux)
+    pub os_id: Option<u64>,
+    /// name of the thread (see `ThreadPoolBuilder::thread_name`)
+    pub name: Option<String>,
+}
+
+impl fmt::Display for ThreadInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, " ({})", name)?;
+        }
+        if let Some(os_id) = self.os_id {
+            write!(f, " tid {}", os_id)?;
+        }
+        Ok(())
     }
 }
 
 /// Logged information.
 ///
 /// This stores tasks information, threads number and run duration.
-/// Obtained by `ThreadPool::install`.
-#[derive(Debug, Serialize, Deserialize)]
+/// Obtained by `ThreadPool::logging_install` or loaded from a file with `RunLog::load`.
+/// Fields are private so that new information can be added later: use the accessors.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{RunLog, ThreadPoolBuilder};
+///
+/// /// average duration of all tasks in the log, in nanoseconds.
+/// fn average_task_duration(log: &RunLog) -> f64 {
+///     let total: u64 = log.tasks().iter().map(|t| t.duration()).sum();
+///     total as f64 / log.tasks().len() as f64
+/// }
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+/// assert_eq!(log.tasks().len(), 4);
+/// let average = average_task_duration(&log);
+/// assert!(average > 0.0);
+/// assert!(average * 4.0 <= (log.duration() * log.threads_number() as u64) as f64);
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[non_exhaustive]
 pub struct RunLog {
     /// total number of threads (some might be unused).
-    pub threads_number: usize,
+    pub(crate) threads_number: usize,
     /// fork-join tasks.
-    pub tasks_logs: Vec<TaskLog>,
+    pub(crate) tasks_logs: Vec<TaskLog>,
     /// total run time in nanoseconds.
-    pub duration: TimeStamp,
+    pub(crate) duration: TimeStamp,
     /// all strings used for tagging tasks.
-    pub tags: Vec<String>,
+    pub(crate) tags: Vec<String>,
     /// subgraphs: some parts of the graph can be tagged with a tag and usize
     /// values are: start task, ending task, tag_id, recorded size
-    pub subgraphs: Vec<(TaskId, TaskId, usize, usize)>,
+    pub(crate) subgraphs: Vec<(TaskId, TaskId, usize, usize)>,
+    /// additional labelled measures of some subgraphs
+    /// values are: subgraph index, label, measured value
+    #[serde(default)]
+    pub(crate) measures: Vec<(usize, String, usize)>,
+    /// instant markers (see `mark`): time (in ns after the run start) and label
+    #[serde(default)]
+    pub(crate) marks: Vec<(TimeStamp, String)>,
+    /// manually declared dependencies (see `dependency_handle`): producing and consuming tasks.
+    /// they are kept apart from the fork-join `children` edges.
+    #[serde(default)]
+    pub(crate) dependencies: Vec<(TaskId, TaskId)>,
+    /// only one logged join or subgraph out of `sampling_rate` was recorded
+    /// (see `sample_rate`).
+    #[serde(default = "full_sampling")]
+    pub(crate) sampling_rate: usize,
+    /// information on each thread (indexed like tasks' `thread_id`, empty for old logs)
+    #[serde(default)]
+    pub(crate) threads_info: Vec<ThreadInfo>,
+    /// clock used for timestamps (see `ThreadPoolBuilder::clock`).
+    /// all times are converted to nanoseconds anyway.
+    #[serde(default)]
+    pub(crate) clock: Clock,
+    /// problems found and corrected while building the log (like clock skews).
+    #[serde(default)]
+    pub(crate) warnings: Vec<String>,
+    /// graph information computed on first use (see `RunLog::structure`).
+    /// tasks and subgraphs should not be modified afterwards.
+    #[serde(skip)]
+    structure: OnceLock<Structure>,
+}
+
+/// Graph information shared by all analyses of a log, computed once.
+#[derive(Debug, Clone)]
+struct Structure {
+    /// fathers of each task.
+    fathers: Vec<Vec<TaskId>>,
+    /// innermost subgraph containing each task.
+    innermost_subgraphs: Vec<Option<usize>>,
+    /// innermost subgraph containing each subgraph.
+    parent_subgraphs: Vec<Option<usize>>,
+    /// innermost subgraph containing each subgraph and having a different tag.
+    other_tag_ancestors: Vec<Option<usize>>,
+}
+
+/// Load given log file and return a description of all problems found
+/// (nothing for a valid log). See `RunLog::validate`.
+///
+/// Example:
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{validate_log, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+/// let path = std::env::temp_dir().join(format!("validated_log_{}.json", std::process::id()));
+/// log.save(&path).expect("saving failed");
+/// assert!(validate_log(&path).is_empty());
+///
+/// let mut json: serde_json::Value = serde_json::to_value(&log).expect("serializing failed");
+/// json["tasks_logs"][0]["children"]
+///     .as_array_mut()
+///     .unwrap()
+///     .push(100.into());
+/// std::fs::write(&path, json.to_string()).expect("writing failed");
+/// assert_eq!(validate_log(&path), vec!["task 0 has unknown child 100".to_string()]);
+///
+/// std::fs::write(&path, "{\"threads_number\": 2, \"tasks_logs\": [").expect("writing failed");
+/// let findings = validate_log(&path);
+/// assert!(findings[0].starts_with("file is truncated"));
+/// assert!(findings[0].contains("line 1 column"));
+/// std::fs::remove_file(&path).expect("removing failed");
+/// ```
+pub fn validate_log<P: AsRef<Path>>(path: P) -> Vec<String> {
+    match RunLog::read(path.as_ref()) {
+        Ok(log) => log.validate(),
+        Err(e) => vec![e.to_string()],
+    }
+}
+
+/// Logs saved before sampling was introduced recorded everything.
+fn full_sampling() -> usize {
+    1
+}
+
+/// Error encountered while post-processing raw events into a `RunLog`.
+///
+/// This happens when logged and un-logged computations are mixed
+/// (plain rayon calls inside `logging_install` or logged calls outside of any logged pool).
+#[derive(Debug, Clone)]
+pub struct LogError {
+    /// thread on which the faulty event was logged.
+    pub thread_id: usize,
+    /// index of the faulty event among all events of this thread.
+    pub event_index: usize,
+    /// what went wrong.
+    pub description: String,
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid logs on thread {} (event {}): {}. are you mixing logged and un-logged computations ?",
+            self.thread_id, self.event_index, self.description
+        )
+    }
+}
+
+impl Error for LogError {}
+
+/// Remove all tasks which are not kept and renumber the others.
+/// Edges towards removed tasks are removed but subgraphs and dependencies
+/// should only contain kept tasks.
+fn retain_tasks(
+    tasks_info: &mut Vec<TaskLog>,
+    subgraphs: &mut [(TaskId, TaskId, usize, usize)],
+    dependencies: &mut [(TaskId, TaskId)],
+    kept: &[bool],
+) {
+    let new_ids: Vec<TaskId> = kept
+        .iter()
+        .scan(0, |next_id, &k| {
+            let id = *next_id;
+            if k {
+                *next_id += 1;
+            }
+            Some(id)
+        })
+        .collect();
+    let mut index = 0;
+    tasks_info.retain(|_| {
+        index += 1;
+        kept[index - 1]
+    });
+    for task in tasks_info.iter_mut() {
+        task.children.retain(|&child| kept[child]);
+        for child in &mut task.children {
+            *child = new_ids[*child];
+        }
+    }
+    for subgraph in subgraphs.iter_mut() {
+        subgraph.0 = new_ids[subgraph.0];
+        subgraph.1 = new_ids[subgraph.1];
+    }
+    for dependency in dependencies.iter_mut() {
+        dependency.0 = new_ids[dependency.0];
+        dependency.1 = new_ids[dependency.1];
+    }
+}
+
+/// Remove all subgraphs which never ended (logs taken while computing)
+/// and renumber measures.
+fn remove_unfinished_subgraphs(
+    subgraphs: &mut Vec<(TaskId, TaskId, usize, usize)>,
+    measures: &mut [(usize, String, usize)],
+    ended: &[bool],
+) {
+    let new_indices: Vec<usize> = ended
+        .iter()
+        .scan(0, |next_index, &e| {
+            let index = *next_index;
+            if e {
+                *next_index += 1;
+            }
+            Some(index)
+        })
+        .collect();
+    let mut index = 0;
+    subgraphs.retain(|_| {
+        index += 1;
+        ended[index - 1]
+    });
+    for measure in measures.iter_mut() {
+        measure.0 = new_indices[measure.0];
+    }
+}
+
+/// Index of given tag in tags (added if not there yet).
+fn tag_index(tags: &mut Vec<String>, tag: &str) -> usize {
+    tags.iter().position(|t| t == tag).unwrap_or_else(|| {
+        tags.push(tag.to_string());
+        tags.len() - 1
+    })
+}
+
+/// Concatenate given logs one after the other (see `RunLog::from_phases`).
+/// Named logs become a subgraph tagged with their name and get a mark at their start.
+fn concatenate<'a, I: IntoIterator<Item = (Option<&'a str>, &'a RunLog)>>(logs: I) -> RunLog {
+    let mut log = RunLog {
+        threads_number: 0,
+        threads_info: Vec::new(),
+        clock: Clock::default(),
+        tasks_logs: Vec::new(),
+        duration: 0,
+        tags: Vec::new(),
+        subgraphs: Vec::new(),
+        measures: Vec::new(),
+        marks: Vec::new(),
+        dependencies: Vec::new(),
+        sampling_rate: full_sampling(),
+        warnings: Vec::new(),
+        structure: OnceLock::new(),
+    };
+    // time at which the next phase starts
+    let mut time_offset = 0;
+    let mut previous_last_task: Option<TaskId> = None;
+    for (index, (name, phase)) in logs.into_iter().enumerate() {
+        if index == 0 {
+            // phases should share these, we keep the first ones
+            log.clock = phase.clock;
+            log.sampling_rate = phase.sampling_rate;
+        }
+        if phase.tasks_logs.is_empty() {
+            continue;
+        }
+        let task_offset = log.tasks_logs.len();
+        if phase.threads_number > log.threads_number {
+            log.threads_number = phase.threads_number;
+            log.threads_info = phase.threads_info.clone();
+        }
+        let phase_start = phase.tasks_logs.iter().map(|t| t.start_time).min().unwrap();
+        let phase_end = phase.tasks_logs.iter().map(|t| t.end_time).max().unwrap();
+        let shift = |time: TimeStamp| time.saturating_sub(phase_start) + time_offset;
+        log.tasks_logs
+            .extend(phase.tasks_logs.iter().map(|task| TaskLog {
+                start_time: shift(task.start_time),
+                end_time: shift(task.end_time),
+                children: task.children.iter().map(|c| c + task_offset).collect(),
+                ..task.clone()
+            }));
+        // the phase goes from its first task to the last one ending
+        let last_task = phase
+            .tasks_logs
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.children.is_empty())
+            .max_by_key(|(_, task)| task.end_time)
+            .map(|(task_id, _)| task_id + task_offset)
+            .unwrap_or(task_offset);
+        if let Some(previous) = previous_last_task {
+            log.tasks_logs[previous].children.push(task_offset);
+        }
+        previous_last_task = Some(last_task);
+
+        if let Some(name) = name {
+            let phase_tag = tag_index(&mut log.tags, name);
+            log.subgraphs.push((task_offset, last_task, phase_tag, 1));
+            log.marks.push((time_offset, name.to_string()));
+        }
+        let subgraph_offset = log.subgraphs.len();
+        for &(start, end, tag, work) in &phase.subgraphs {
+            let tag = tag_index(&mut log.tags, &phase.tags[tag]);
+            log.subgraphs
+                .push((start + task_offset, end + task_offset, tag, work));
+        }
+        log.measures.extend(
+            phase.measures.iter().map(|(subgraph, label, value)| {
+                (subgraph + subgraph_offset, label.clone(), *value)
+            }),
+        );
+        log.marks.extend(
+            phase
+                .marks
+                .iter()
+                .map(|(time, label)| (shift(*time), label.clone())),
+        );
+        log.dependencies.extend(
+            phase
+                .dependencies
+                .iter()
+                .map(|(producer, consumer)| (producer + task_offset, consumer + task_offset)),
+        );
+        log.warnings.extend(phase.warnings.iter().cloned());
+        time_offset += phase_end - phase_start;
+    }
+    log.duration = time_offset;
+    log.fill_work_information();
+    log
+}
+
+/// Extra tooltip lines for stolen tasks and tasks still running.
+fn status_information(task: &TaskLog) -> &'static str {
+    match (task.migrated, task.in_progress) {
+        (false, false) => "",
+        (true, false) => "\nstolen",
+        (false, true) => "\nin progress",
+        (true, true) => "\nstolen\nin progress",
+    }
 }
 
 impl RunLog {
+    /// Total number of threads (some might be unused).
+    pub fn threads_number(&self) -> usize {
+        self.threads_number
+    }
+
+    /// All fork-join tasks, indexed by their ids.
+    pub fn tasks(&self) -> &[TaskLog] {
+        &self.tasks_logs
+    }
+
+    /// Total run time in nanoseconds.
+    pub fn duration(&self) -> TimeStamp {
+        self.duration
+    }
+
+    /// All strings used for tagging subgraphs.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// All subgraphs: start task, ending task, tag index and recorded work.
+    pub fn subgraphs(&self) -> &[(TaskId, TaskId, usize, usize)] {
+        &self.subgraphs
+    }
+
+    /// Additional labelled measures of some subgraphs: subgraph index, label and value.
+    pub fn measures(&self) -> &[(usize, String, usize)] {
+        &self.measures
+    }
+
+    /// Instant markers (see `mark`): time (in ns after the run start) and label.
+    pub fn marks(&self) -> &[(TimeStamp, String)] {
+        &self.marks
+    }
+
+    /// Manually declared dependencies (see `dependency_handle`): producing and consuming tasks.
+    pub fn dependencies(&self) -> &[(TaskId, TaskId)] {
+        &self.dependencies
+    }
+
+    /// Only one logged join or subgraph out of `sampling_rate` was recorded.
+    pub fn sampling_rate(&self) -> usize {
+        self.sampling_rate
+    }
+
+    /// Information on each thread (empty for old logs).
+    pub fn threads_info(&self) -> &[ThreadInfo] {
+        &self.threads_info
+    }
+
+    /// Clock used for timestamps.
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Problems found and corrected while building the log (like clock skews).
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     /// Create a real log from logged events and reset the pool.
-    pub(crate) fn new(
+    /// Each element of `tasks_logs` iterates on the events of one thread.
+    pub(crate) fn new<'a, E: Iterator<Item = &'a RayonEvent>>(
         tasks_number: usize,
         _iterators_number: usize,
-        tasks_logs: &[Arc<Storage<RayonEvent>>],
+        tasks_logs: Vec<E>,
         start: TimeStamp,
-    ) -> Self {
+        sampling_rate: usize,
+        threads_info: Vec<ThreadInfo>,
+        clock: Clock,
+    ) -> Result<Self, LogError> {
         let mut seen_tags = HashMap::new(); // associate each take to a usize index
         let mut tags = Vec::new(); // vector containing all tags strings
         let mut tasks_info: Vec<_> = (0..tasks_number)
@@ -80,47 +580,139 @@ impl RunLog {
                 end_time: 0,
                 thread_id: 0,
                 children: Vec::new(),
+                panicked: false,
+                migrated: false,
+                in_progress: false,
+                iterator_work: None,
+                iterator_split: None,
+                scheduling_delay: 0,
+                work: None,
             })
             .collect();
 
+        // tasks created while recording was disabled never start
+        let mut started = vec![false; tasks_number];
+
         let threads_number = tasks_logs.len();
         // remember the active task on each thread
         let mut all_active_tasks: Vec<Option<TaskId>> = repeat(None).take(threads_number).collect();
-        // remember the active subgraph on each thread (they for a stack)
-        let mut all_active_subgraphs: Vec<Vec<usize>> =
-            repeat_with(Vec::new).take(threads_number).collect();
+        // remember the innermost open subgraph of each task.
+        // tasks inherit it from their fathers since subgraphs can end on another thread.
+        let mut tasks_subgraphs: Vec<Option<usize>> = vec![None; tasks_number];
+        // open subgraphs form a stack: remember the enclosing subgraph of each subgraph
+        let mut enclosing_subgraphs: Vec<Option<usize>> = Vec::new();
 
         // store all subgraph related informations
         let mut subgraphs = Vec::new();
+        let mut subgraphs_ended = Vec::new();
+        let mut measures = Vec::new();
+        let mut marks = Vec::new();
+        // manual dependencies: producing task of each dependency id and all arrivals
+        let mut dependencies_starts = HashMap::new();
+        let mut dependencies_ends = Vec::new();
+        // last time we saw (tasks still running end there)
+        let mut last_time = start;
 
-        for (thread_id, event) in tasks_logs
-            .iter()
+        // events without a time (children, subgraphs...) happen at the time of the previous
+        // timed event of their thread.
+        // ties are broken by thread and position in the thread for a deterministic order.
+        for (_, thread_id, event_index, event) in tasks_logs
+            .into_iter()
             .enumerate()
-            .map(|(thread_id, thread_log)| thread_log.iter().map(move |log| (thread_id, log)))
-            .kmerge_by(|a, b| a.1.time() < b.1.time())
+            .map(|(thread_id, thread_log)| {
+                thread_log
+                    .enumerate()
+                    .scan(0, move |time, (event_index, event)| {
+                        *time = event.time().max(*time);
+                        Some((*time, thread_id, event_index, event))
+                    })
+            })
+            .kmerge_by(|a, b| (a.0, a.1, a.2) < (b.0, b.1, b.2))
         {
+            let error = |description: &str| LogError {
+                thread_id,
+                event_index,
+                description: description.to_string(),
+            };
+            // late events from a previous run might predate us
+            let elapsed = |time: TimeStamp| {
+                time.checked_sub(start)
+                    .ok_or_else(|| error("event logged before start"))
+            };
+            last_time = last_time.max(event.time());
             let active_tasks = &mut all_active_tasks[thread_id];
-            let active_subgraphs = &mut all_active_subgraphs[thread_id];
             match *event {
                 RayonEvent::Child(c) => {
-                    let father = active_tasks.expect("child with no active task as father");
+                    let father =
+                        active_tasks.ok_or_else(|| error("child with no active task as father"))?;
+                    // late events of a detached task from a previous install
+                    if c >= tasks_number {
+                        return Err(error("unknown child"));
+                    }
                     tasks_info[father].children.push(c);
+                    // several fathers (after a join): the deepest subgraph is still open
+                    let inherited = tasks_subgraphs[father];
+                    if let Some(subgraph) = tasks_subgraphs.get_mut(c) {
+                        *subgraph = (*subgraph).max(inherited);
+                    }
                 }
                 RayonEvent::TaskEnd(time) => {
-                    if let Some(task) = active_tasks.take() {
-                        tasks_info[task].end_time = time - start;
-                    } else {
-                        panic!("ending a non started task. are you mixing logged and un-logged computations ?");
-                    }
+                    let task = active_tasks
+                        .take()
+                        .ok_or_else(|| error("ending a non started task"))?;
+                    tasks_info[task].end_time = elapsed(time)?;
+                }
+                RayonEvent::Mark(ref label, time) => {
+                    marks.push((elapsed(time)?, label.to_string()))
+                }
+                RayonEvent::DependencyStart(dependency) => {
+                    let task = active_tasks
+                        .ok_or_else(|| error("dependency with no active task as producer"))?;
+                    dependencies_starts.insert(dependency, task);
+                }
+                RayonEvent::DependencyEnd(dependency) => {
+                    let task = active_tasks
+                        .ok_or_else(|| error("dependency with no active task as consumer"))?;
+                    dependencies_ends.push((dependency, task));
+                }
+                RayonEvent::TaskPanicked => {
+                    let task = active_tasks.ok_or_else(|| error("panic with no active task"))?;
+                    tasks_info[task].panicked = true;
+                }
+                RayonEvent::IteratorWork(iterator, items) => {
+                    let task =
+                        active_tasks.ok_or_else(|| error("iterator work with no active task"))?;
+                    tasks_info[task].iterator_work = Some((iterator, items));
+                }
+                RayonEvent::IteratorSplit(position, left, right) => {
+                    let task =
+                        active_tasks.ok_or_else(|| error("iterator split with no active task"))?;
+                    tasks_info[task].iterator_split = Some((position, left, right));
+                }
+                RayonEvent::SubgraphMeasure(ref label, value) => {
+                    let task = active_tasks.ok_or_else(|| error("measure with no active task"))?;
+                    let graph_index = tasks_subgraphs[task]
+                        .ok_or_else(|| error("measuring a non started subgraph"))?;
+                    measures.push((graph_index, label.to_string(), value));
+                }
+                RayonEvent::TaskMigrated => {
+                    let task =
+                        active_tasks.ok_or_else(|| error("migration with no active task"))?;
+                    tasks_info[task].migrated = true;
                 }
                 RayonEvent::TaskStart(task, time) => {
+                    if task >= tasks_number {
+                        return Err(error("starting an unknown task"));
+                    }
+                    started[task] = true;
                     tasks_info[task].thread_id = thread_id;
-                    tasks_info[task].start_time = time - start;
+                    tasks_info[task].start_time = elapsed(time)?;
                     *active_tasks = Some(task);
                 }
-                RayonEvent::SubgraphStart(work_type) | RayonEvent::SubgraphEnd(work_type, _) => {
+                RayonEvent::SubgraphStart(ref work_type)
+                | RayonEvent::SubgraphEnd(ref work_type, _) => {
                     if let Some(active_task) = active_tasks {
-                        let existing_tag = seen_tags.entry(work_type);
+                        let existing_tag = seen_tags.entry(work_type.as_ref());
                         let tag_index = match existing_tag {
                             Entry::Occupied(o) => *o.get(),
                             Entry::Vacant(v) => {
@@ -132,68 +724,610 @@ impl RunLog {
                         };
                         match *event {
                             RayonEvent::SubgraphStart(_) => {
-                                active_subgraphs.push(subgraphs.len());
+                                enclosing_subgraphs.push(tasks_subgraphs[*active_task]);
+                                tasks_subgraphs[*active_task] = Some(subgraphs.len());
                                 subgraphs.push((*active_task, 0, tag_index, 0));
+                                subgraphs_ended.push(false);
                             }
                             RayonEvent::SubgraphEnd(_, work_amount) => {
-                                let graph_index =
-                                    active_subgraphs.pop().expect("ending a non started graph");
+                                let graph_index = tasks_subgraphs[*active_task]
+                                    .ok_or_else(|| error("ending a non started subgraph"))?;
+                                tasks_subgraphs[*active_task] = enclosing_subgraphs[graph_index];
                                 subgraphs[graph_index].1 = *active_task;
                                 subgraphs[graph_index].3 = work_amount;
+                                subgraphs_ended[graph_index] = true;
                             }
                             _ => unreachable!(),
                         }
                     } else {
-                        panic!("tagging a non existing task");
+                        return Err(error("tagging a non existing task"));
                     }
                 }
             }
         }
 
-        let duration = tasks_info.iter().map(|t| t.end_time).max().unwrap()
-            - tasks_info.iter().map(|t| t.start_time).min().unwrap();
+        // logs might have been taken while computing
+        for &task in all_active_tasks.iter().flatten() {
+            tasks_info[task].end_time = last_time - start;
+            tasks_info[task].in_progress = true;
+        }
+        // clocks of different cores might be slightly skewed and a task migrating between
+        // them can end before starting. we give each task at least 1ns.
+        let mut clamped_tasks = 0;
+        for (task, _) in tasks_info
+            .iter_mut()
+            .zip(&started)
+            .filter(|(t, s)| **s && t.end_time <= t.start_time)
+        {
+            task.end_time = task.start_time + 1;
+            clamped_tasks += 1;
+        }
+        let mut warnings = Vec::new();
+        if clamped_tasks != 0 {
+            warnings.push(format!(
+                "{} tasks ending before or when starting were extended to 1ns",
+                clamped_tasks
+            ));
+        }
+        if subgraphs_ended.iter().any(|e| !e) {
+            remove_unfinished_subgraphs(&mut subgraphs, &mut measures, &subgraphs_ended);
+        }
+
+        // events are not merged in time order across threads so arrivals are matched only now.
+        // dependencies started while recording was disabled are dropped.
+        let mut dependencies: Vec<(TaskId, TaskId)> = dependencies_ends
+            .into_iter()
+            .filter_map(|(dependency, consumer)| {
+                dependencies_starts
+                    .get(&dependency)
+                    .map(|producer| (*producer, consumer))
+            })
+            .collect();
+
+        // children are ready when their last father ends
+        let mut ready_times: Vec<Option<TimeStamp>> = vec![None; tasks_number];
+        for task in tasks_info.iter().filter(|t| !t.in_progress) {
+            for &child in &task.children {
+                let ready_time = ready_times[child].get_or_insert(task.end_time);
+                *ready_time = (*ready_time).max(task.end_time);
+            }
+        }
+        for (task, ready_time) in tasks_info.iter_mut().zip(ready_times) {
+            if let Some(ready_time) = ready_time {
+                task.scheduling_delay = task.start_time.saturating_sub(ready_time);
+            }
+        }
+
+        if started.iter().any(|s| !s) {
+            // tasks created while recording was disabled never start.
+            // no events were logged for them so nothing points to them.
+            retain_tasks(&mut tasks_info, &mut subgraphs, &mut dependencies, &started);
+        }
+
+        let duration = tasks_info.iter().map(|t| t.end_time).max().unwrap_or(0)
+            - tasks_info.iter().map(|t| t.start_time).min().unwrap_or(0);
 
-        RunLog {
+        let mut log = RunLog {
             threads_number,
             tasks_logs: tasks_info,
             duration,
             tags,
             subgraphs,
+            measures,
+            marks,
+            dependencies,
+            sampling_rate,
+            threads_info,
+            clock,
+            warnings,
+            structure: OnceLock::new(),
+        };
+        log.fill_work_information();
+        Ok(log)
+    }
+
+    /// Graph information (fathers and subgraphs containing each task), computed on first use.
+    fn structure(&self) -> &Structure {
+        self.structure.get_or_init(|| self.compute_structure())
+    }
+
+    /// Record in each task its innermost subgraph and its part of the subgraph's work.
+    fn fill_work_information(&mut self) {
+        let durations = self.subgraphs_sums(TaskLog::duration);
+        let works: Vec<Option<WorkInformation>> = self
+            .tasks_logs
+            .iter()
+            .zip(&self.structure().innermost_subgraphs)
+            .map(|(task, innermost)| {
+                innermost.map(|subgraph| WorkInformation {
+                    subgraph,
+                    work_share: work_share(
+                        self.subgraphs[subgraph].3,
+                        task.duration(),
+                        durations[subgraph],
+                    ),
+                })
+            })
+            .collect();
+        for (task, work) in self.tasks_logs.iter_mut().zip(works) {
+            task.work = work;
         }
     }
 
-    /// Iterate on all tasks between (including) start and end.
-    /// pre-condition: start is ancestor of end and all descendants of start
-    /// are either ancestors of end or descendants of end.
-    fn tasks_between<'a>(
-        &'a self,
-        start: TaskId,
-        end: TaskId,
-    ) -> impl Iterator<Item = TaskId> + 'a {
-        let mut stack = Vec::new();
-        let mut seen = HashSet::new();
-        successors(Some(start), move |&n| {
-            seen.insert(n);
-            if n != end {
-                stack.extend(
-                    self.tasks_logs[n]
-                        .children
-                        .iter()
-                        .filter(|&c| !seen.contains(c))
-                        .cloned(),
-                )
+    /// Compute fathers of each task and the subgraphs containing them.
+    /// Tasks are processed in topological order: each task is inside the subgraphs its
+    /// fathers were inside when ending, and inside the subgraphs it starts.
+    /// pre-condition: subgraphs are ordered topologically (they should be).
+    fn compute_structure(&self) -> Structure {
+        let tasks_number = self.tasks_logs.len();
+        let mut fathers = vec![Vec::new(); tasks_number];
+        for (task_id, task) in self.tasks_logs.iter().enumerate() {
+            for &child in &task.children {
+                fathers[child].push(task_id);
+            }
+        }
+        let mut started_subgraphs = vec![Vec::new(); tasks_number];
+        for (index, subgraph) in self.subgraphs.iter().enumerate() {
+            started_subgraphs[subgraph.0].push(index);
+        }
+        let mut innermost_subgraphs = vec![None; tasks_number];
+        let mut parent_subgraphs = vec![None; self.subgraphs.len()];
+        // innermost subgraph still open when each task ends
+        let mut open_subgraphs: Vec<Option<usize>> = vec![None; tasks_number];
+        let mut remaining_fathers: Vec<usize> = fathers.iter().map(|f| f.len()).collect();
+        let mut ready_tasks: Vec<TaskId> = (0..tasks_number)
+            .filter(|&task| fathers[task].is_empty())
+            .collect();
+        while let Some(task) = ready_tasks.pop() {
+            // deeper subgraphs have higher indices
+            let mut innermost = fathers[task]
+                .iter()
+                .filter_map(|&father| open_subgraphs[father])
+                .max();
+            for &subgraph in &started_subgraphs[task] {
+                parent_subgraphs[subgraph] = innermost;
+                innermost = Some(subgraph);
+            }
+            innermost_subgraphs[task] = innermost;
+            let mut open = innermost;
+            while let Some(subgraph) = open.filter(|&s| self.subgraphs[s].1 == task) {
+                open = parent_subgraphs[subgraph];
+            }
+            open_subgraphs[task] = open;
+            for &child in &self.tasks_logs[task].children {
+                remaining_fathers[child] -= 1;
+                if remaining_fathers[child] == 0 {
+                    ready_tasks.push(child);
+                }
+            }
+        }
+        let mut other_tag_ancestors = vec![None; self.subgraphs.len()];
+        for index in 0..self.subgraphs.len() {
+            other_tag_ancestors[index] = parent_subgraphs[index].and_then(|parent| {
+                if self.subgraphs[parent].2 == self.subgraphs[index].2 {
+                    other_tag_ancestors[parent]
+                } else {
+                    Some(parent)
+                }
+            });
+        }
+        Structure {
+            fathers,
+            innermost_subgraphs,
+            parent_subgraphs,
+            other_tag_ancestors,
+        }
+    }
+
+    /// Fathers of each task.
+    pub(crate) fn fathers(&self) -> &[Vec<TaskId>] {
+        &self.structure().fathers
+    }
+
+    /// Tag of the innermost subgraph containing given task.
+    pub(crate) fn task_tag(&self, task: TaskId) -> Option<&str> {
+        self.tasks_logs[task]
+            .work
+            .map(|work| self.tags[self.subgraphs[work.subgraph].2].as_str())
+    }
+
+    /// Outermost subgraph containing given task.
+    pub(crate) fn outermost_subgraph(&self, task: TaskId) -> Option<usize> {
+        let structure = self.structure();
+        successors(structure.innermost_subgraphs[task], |&subgraph| {
+            structure.parent_subgraphs[subgraph]
+        })
+        .last()
+    }
+
+    /// Iterate on the innermost subgraph of each tag containing given task
+    /// (innermost first, tags are not repeated).
+    fn tagged_subgraphs<'a>(&'a self, task: TaskId) -> impl Iterator<Item = usize> + 'a {
+        let structure = self.structure();
+        let mut seen_tags = Vec::new();
+        successors(structure.innermost_subgraphs[task], move |&subgraph| {
+            structure.other_tag_ancestors[subgraph]
+        })
+        .filter(move |&subgraph| {
+            let tag = self.subgraphs[subgraph].2;
+            if seen_tags.contains(&tag) {
+                false
+            } else {
+                seen_tags.push(tag);
+                true
             }
-            stack.pop()
         })
     }
 
+    /// Sum given value over all tasks of each subgraph (including nested subgraphs).
+    /// pre-condition: subgraphs are ordered topologically (they should be).
+    pub(crate) fn subgraphs_sums<F: Fn(&TaskLog) -> u64>(&self, value: F) -> Vec<u64> {
+        let structure = self.structure();
+        let mut sums = vec![0; self.subgraphs.len()];
+        for (task, innermost) in self.tasks_logs.iter().zip(&structure.innermost_subgraphs) {
+            if let Some(subgraph) = innermost {
+                sums[*subgraph] += value(task);
+            }
+        }
+        // nested subgraphs come after the subgraphs containing them
+        for subgraph in (0..self.subgraphs.len()).rev() {
+            if let Some(parent) = structure.parent_subgraphs[subgraph] {
+                sums[parent] += sums[subgraph];
+            }
+        }
+        sums
+    }
+
+    /// Return the n longest tasks (longest first) together with the tag of the innermost
+    /// subgraph containing them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| {
+    ///     rayon_logs::subgraph("sum", 100_000, || (0..100_000u64).into_par_iter().sum::<u64>())
+    /// });
+    /// let longest = log.longest_tasks(3);
+    /// assert_eq!(longest.len(), 3.min(log.tasks().len()));
+    /// assert!(longest.windows(2).all(|w| w[0].1.duration() >= w[1].1.duration()));
+    /// let max_duration = log.tasks().iter().map(|t| t.duration()).max();
+    /// assert_eq!(Some(longest[0].1.duration()), max_duration);
+    /// assert!(longest.iter().all(|&(_, _, tag)| tag == Some("sum")));
+    /// ```
+    pub fn longest_tasks(&self, n: usize) -> Vec<(TaskId, &TaskLog, Option<&str>)> {
+        self.tasks_logs
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, task)| std::cmp::Reverse(task.duration()))
+            .take(n)
+            .map(|(task_id, task)| (task_id, task, self.task_tag(task_id)))
+            .collect()
+    }
+
+    /// Return the (mean, median, max) scheduling delay of all tasks with a father
+    /// (see `TaskLog::scheduling_delay`).
+    /// Tasks executed inline right after their father should have a delay close to 0,
+    /// so a large median points to queueing or stealing overhead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| (0..100_000u64).into_par_iter().sum::<u64>());
+    /// let (mean, median, max) = log.scheduling_delays();
+    /// assert!(mean <= max as f64 && median <= max as f64);
+    /// assert_eq!(log.tasks()[0].scheduling_delay, 0);
+    /// ```
+    pub fn scheduling_delays(&self) -> (f64, f64, TimeStamp) {
+        let delays: Vec<TimeStamp> = self
+            .tasks_logs
+            .iter()
+            .zip(self.fathers())
+            .filter(|(_, fathers)| !fathers.is_empty())
+            .map(|(task, _)| task.scheduling_delay)
+            .sorted()
+            .collect();
+        (
+            deviations(delays.iter().cloned()).0,
+            quantile(&delays, 0.5),
+            delays.last().cloned().unwrap_or(0),
+        )
+    }
+
+    /// Total time spent in all tasks (the work, in nanoseconds).
+    pub fn work(&self) -> TimeStamp {
+        self.tasks_logs.iter().map(TaskLog::duration).sum()
+    }
+
+    /// Duration of the longest chain of dependent tasks (the span, or critical path,
+    /// in nanoseconds). Manual dependencies are taken into account.
+    /// `work() / span()` is the maximal speedup the graph allows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| (0..100_000u64).into_par_iter().sum::<u64>());
+    /// assert!(log.span() <= log.work());
+    /// assert!(log.span() <= log.duration());
+    /// let longest_task = log.tasks().iter().map(|t| t.duration()).max().unwrap();
+    /// assert!(log.span() >= longest_task);
+    /// ```
+    pub fn span(&self) -> TimeStamp {
+        let tasks_number = self.tasks_logs.len();
+        let mut successors: Vec<Vec<TaskId>> = self
+            .tasks_logs
+            .iter()
+            .map(|task| task.children.clone())
+            .collect();
+        for &(producer, consumer) in &self.dependencies {
+            successors[producer].push(consumer);
+        }
+        let mut remaining_fathers = vec![0; tasks_number];
+        for &successor in successors.iter().flatten() {
+            remaining_fathers[successor] += 1;
+        }
+        // longest chain ending when each task starts
+        let mut chains = vec![0; tasks_number];
+        let mut ready_tasks: Vec<TaskId> = (0..tasks_number)
+            .filter(|&task| remaining_fathers[task] == 0)
+            .collect();
+        let mut span = 0;
+        while let Some(task) = ready_tasks.pop() {
+            let chain = chains[task] + self.tasks_logs[task].duration();
+            span = span.max(chain);
+            for &successor in &successors[task] {
+                chains[successor] = chains[successor].max(chain);
+                remaining_fathers[successor] -= 1;
+                if remaining_fathers[successor] == 0 {
+                    ready_tasks.push(successor);
+                }
+            }
+        }
+        span
+    }
+
+    /// Tasks without fathers (where the run starts).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::{join_context, ThreadPoolBuilder};
+    /// use std::{thread::sleep, time::Duration};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// // give the other thread time to steal the second task
+    /// let ((_, migrated), log) = pool.logging_install(|| {
+    ///     join_context(|_| sleep(Duration::from_millis(10)), |c| c.migrated())
+    /// });
+    /// assert_eq!(log.roots(), vec![0]);
+    /// let forked = &log.tasks()[0].children;
+    /// assert_eq!(forked.len(), 2);
+    /// let threads: Vec<usize> = forked.iter().map(|&t| log.tasks()[t].thread_id).collect();
+    /// assert_eq!(migrated, threads[0] != threads[1]);
+    /// ```
+    pub fn roots(&self) -> Vec<TaskId> {
+        self.fathers()
+            .iter()
+            .positions(|fathers| fathers.is_empty())
+            .collect()
+    }
+
+    /// Number of fathers and number of children of each task, sorted.
+    /// Tasks are numbered in an order which depends on the scheduling but
+    /// this sequence only depends on the shape of the tasks graph, which is handy for testing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::{join, scope, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// // a join forks into two tasks which are joined by a last one
+    /// let (_, log) = pool.logging_install(|| join(|| 1, || 2));
+    /// assert_eq!(log.degree_sequence(), vec![(0, 2), (1, 1), (1, 1), (2, 0)]);
+    /// let (_, log) = pool.logging_install(|| join(|| join(|| 1, || 2), || 3));
+    /// assert_eq!(
+    ///     log.degree_sequence(),
+    ///     vec![(0, 2), (1, 1), (1, 1), (1, 1), (1, 2), (2, 0), (2, 1)]
+    /// );
+    /// // each spawn forks the scope's body, all spawned tasks join at the end of the scope
+    /// let (_, log) = pool.logging_install(|| {
+    ///     scope(|s| {
+    ///         s.spawn(|_| ());
+    ///         s.spawn(|_| ());
+    ///     })
+    /// });
+    /// assert_eq!(
+    ///     log.degree_sequence(),
+    ///     vec![(0, 1), (1, 1), (1, 1), (1, 1), (1, 2), (1, 2), (3, 0)]
+    /// );
+    /// // iterators fork at each logged split
+    /// let (_, log) = pool.logging_install(|| (0..4u32).into_par_iter().sum::<u32>());
+    /// let degrees = log.degree_sequence();
+    /// let forks = degrees.iter().filter(|d| d.1 == 2).count();
+    /// assert_eq!(forks, degrees.iter().filter(|d| d.0 == 2).count());
+    /// assert_eq!(forks, log.tasks().iter().filter(|t| t.iterator_split.is_some()).count());
+    /// assert_eq!(log.tasks().len(), 3 + 3 * forks);
+    /// assert_eq!(log.roots().len(), 1);
+    /// assert_eq!(degrees.iter().filter(|d| d.1 == 0).count(), 1);
+    /// ```
+    pub fn degree_sequence(&self) -> Vec<(usize, usize)> {
+        self.fathers()
+            .iter()
+            .zip(&self.tasks_logs)
+            .map(|(fathers, task)| (fathers.len(), task.children.len()))
+            .sorted()
+            .collect()
+    }
+
+    /// Time each thread spent outside of any task (in nanoseconds).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+    /// let idle_times = log.threads_idle_times();
+    /// assert_eq!(idle_times.len(), 2);
+    /// assert!(idle_times.iter().all(|&idle| idle <= log.duration()));
+    /// ```
+    pub fn threads_idle_times(&self) -> Vec<TimeStamp> {
+        let mut activities = vec![0; self.threads_number];
+        for task in &self.tasks_logs {
+            if activities.len() <= task.thread_id {
+                activities.resize(task.thread_id + 1, 0);
+            }
+            activities[task.thread_id] += task.duration();
+        }
+        // logging overhead can make activity exceed the available time
+        activities
+            .into_iter()
+            .map(|activity| self.duration.saturating_sub(activity))
+            .collect()
+    }
+
+    /// Idle periods of each thread, found by replaying the execution
+    /// between the first task start and the last task end.
+    fn idle_periods(&self) -> Vec<Vec<(TimeStamp, TimeStamp)>> {
+        let threads_number = self
+            .tasks_logs
+            .iter()
+            .map(|task| task.thread_id + 1)
+            .fold(self.threads_number, usize::max);
+        let mut periods = vec![Vec::new(); threads_number];
+        let (first_time, last_time) = match (
+            self.tasks_logs.iter().map(|t| t.start_time).min(),
+            self.tasks_logs.iter().map(|t| t.end_time).max(),
+        ) {
+            (Some(first_time), Some(last_time)) => (first_time, last_time),
+            _ => return periods,
+        };
+        // we add fake tasks at the end for last idle periods.
+        let sorted_tasks = self
+            .tasks_logs
+            .iter()
+            .map(|t| (t.start_time, t.thread_id, t.end_time))
+            .chain((0..threads_number).map(|thread| (last_time, thread, last_time)))
+            .sorted_by_key(|&(start, _, _)| start);
+        let mut previous_ends = vec![first_time; threads_number];
+        for (start, thread, end) in sorted_tasks {
+            if start > previous_ends[thread] {
+                periods[thread].push((previous_ends[thread], start));
+            }
+            previous_ends[thread] = end;
+        }
+        periods
+    }
+
+    /// Split the idle time of each thread into (starvation, no available parallelism).
+    /// A thread is starving when it is idle while a task created by another thread
+    /// (its fathers are over) is still waiting to start: there was work, but it was not
+    /// stolen in time.
+    /// Otherwise there was simply nothing to run (the execution is span-bound there).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| (0..100_000u64).into_par_iter().sum::<u64>());
+    /// let causes = log.threads_idle_causes();
+    /// assert_eq!(causes.len(), 2);
+    /// assert!(causes
+    ///     .iter()
+    ///     .all(|&(starvation, no_parallelism)| starvation + no_parallelism <= log.duration()));
+    /// ```
+    pub fn threads_idle_causes(&self) -> Vec<(TimeStamp, TimeStamp)> {
+        // thread creating each waiting task, and when it waited
+        let waiting_tasks: Vec<(usize, TimeStamp, TimeStamp)> = self
+            .tasks_logs
+            .iter()
+            .zip(self.fathers())
+            .filter_map(|(task, fathers)| {
+                let creator = &self.tasks_logs[*fathers
+                    .iter()
+                    .max_by_key(|&&father| self.tasks_logs[father].end_time)?];
+                Some((creator.thread_id, creator.end_time, task.start_time))
+            })
+            .filter(|&(_, creation, start)| creation < start)
+            .sorted_by_key(|&(_, creation, _)| creation)
+            .collect();
+        self.idle_periods()
+            .into_iter()
+            .enumerate()
+            .map(|(thread, periods)| {
+                // merge the waiting periods of tasks created by other threads
+                let mut starving: Vec<(TimeStamp, TimeStamp)> = Vec::new();
+                for &(_, creation, start) in waiting_tasks.iter().filter(|w| w.0 != thread) {
+                    match starving.last_mut() {
+                        Some(last) if creation <= last.1 => last.1 = last.1.max(start),
+                        _ => starving.push((creation, start)),
+                    }
+                }
+                let (mut starvation, mut idle) = (0, 0);
+                for &(idle_start, idle_end) in &periods {
+                    let first = starving.partition_point(|&(_, end)| end <= idle_start);
+                    starvation += starving[first..]
+                        .iter()
+                        .take_while(|&&(start, _)| start < idle_end)
+                        .map(|&(start, end)| end.min(idle_end) - start.max(idle_start))
+                        .sum::<TimeStamp>();
+                    idle += idle_end - idle_start;
+                }
+                (starvation, idle - starvation)
+            })
+            .collect()
+    }
+
     /// This returns a HashMap that maps each tag to the number of tasks it has created in the run.
     pub(crate) fn count_tasks(&self) -> HashMap<String, usize> {
         let mut task_profile = HashMap::new();
-        for (start_task, end_task, tag_id, _) in self.subgraphs.iter() {
-            let current_count = self.tasks_between(*start_task, *end_task).count();
+        let counts = self.subgraphs_sums(|_| 1);
+        for ((_, _, tag_id, _), count) in self.subgraphs.iter().zip(counts) {
             let old_count = task_profile.entry(self.tags[*tag_id].clone()).or_insert(0);
-            *old_count += current_count;
+            *old_count += count as usize;
         }
         task_profile
     }
@@ -206,67 +1340,109 @@ impl RunLog {
     pub(crate) fn compute_tasks_information(
         &self,
     ) -> HashMap<TaskId, HashMap<String, (String, f64)>> {
-        // we start by computing speeds for each subgraph
-        // we associate to each tag a hashmap (keys are subgraph) of all raw speeds (and subgraph duration).
-        let mut tags_information = HashMap::new();
-        for (subgraph_index, (start_task, end_task, tag_id, size)) in
-            self.subgraphs.iter().enumerate()
-        {
-            let total_duration: TimeStamp = self
-                .tasks_between(*start_task, *end_task)
-                .map(|t| self.tasks_logs[t].duration())
-                .sum();
-            let speed = *size as f64 / (total_duration as f64);
-            tags_information
-                .entry(tag_id)
-                .or_insert_with(HashMap::new)
-                .insert(subgraph_index, (speed, total_duration));
-        }
-        // normalize speeds
-        for informations in tags_information.values_mut() {
-            let best_speed = informations
-                .values()
-                .map(|i| i.0)
-                .max_by(|s1, s2| s1.partial_cmp(s2).unwrap())
-                .unwrap();
-            for information in informations.values_mut() {
-                information.0 /= best_speed
+        // we start by computing speeds for each subgraph (and subgraph duration)
+        let durations = self.subgraphs_sums(TaskLog::duration);
+        let mut speeds: Vec<f64> = self
+            .subgraphs
+            .iter()
+            .zip(&durations)
+            .map(|(&(_, _, _, size), &duration)| size as f64 / duration.max(1) as f64)
+            .collect();
+        // normalize speeds for each tag
+        let mut best_speeds = vec![f64::NEG_INFINITY; self.tags.len()];
+        for (&(_, _, tag_id, _), &speed) in self.subgraphs.iter().zip(&speeds) {
+            if speed > best_speeds[tag_id] {
+                best_speeds[tag_id] = speed;
             }
         }
-        // ok, we are now ready to compute tasks information
+        for (&(_, _, tag_id, _), speed) in self.subgraphs.iter().zip(speeds.iter_mut()) {
+            // no work at all in this tag: everyone is as fast as possible
+            *speed = if best_speeds[tag_id] > 0.0 {
+                *speed / best_speeds[tag_id]
+            } else {
+                1.0
+            };
+        }
+        // additional measures are displayed for all tasks of their subgraph
+        let mut measures_information: HashMap<usize, String> = HashMap::new();
+        for (subgraph_index, label, value) in &self.measures {
+            let information = measures_information.entry(*subgraph_index).or_default();
+            information.push_str(&format!("\n{}: {}", label, value));
+        }
+        // ok, we are now ready to compute tasks information.
+        // for each tag we use the innermost subgraph (for recursive subgraphs).
         let mut tasks_information = HashMap::new();
-        for (subgraph_index, (start_task, end_task, tag_id, size)) in
-            self.subgraphs.iter().enumerate()
-        {
-            for task in self.tasks_between(*start_task, *end_task) {
-                let duration = self.tasks_logs[task].duration();
-                let (speed, total_duration) = tags_information[&tag_id][&subgraph_index];
-                let r = duration as f64 / (total_duration as f64);
-                let size_part = (*size as f64 * r).round() as usize; // the task's extrapolated part of the subgraph
+        for (task, task_log) in self.tasks_logs.iter().enumerate() {
+            for subgraph_index in self.tagged_subgraphs(task) {
+                let (_, _, tag_id, size) = self.subgraphs[subgraph_index];
+                let duration = task_log.duration();
+                let speed = speeds[subgraph_index];
+                let size_part = work_share(size, duration, durations[subgraph_index]);
                 tasks_information
                     .entry(task)
-                    .or_insert_with(HashMap::new) // insert because of subgraphs topological ordering
-                    // this way we get the innermost recursive subgraph
+                    .or_insert_with(HashMap::new)
                     .insert(
-                        self.tags[*tag_id].clone(),
+                        self.tags[tag_id].clone(),
                         (
                             format!(
-                                "task: {}\ncounted: {}/{}\nduration: {} (micro sec)\nspeed: {}\nthread: {}",
+                                "task: {}\ncounted: {}/{}\nduration: {} (micro sec)\nspeed: {}\nthread: {}\nscheduling delay: {} (ns){}{}",
                                 task,
                                 size_part,
                                 size,
                                 duration / 1000,
                                 speed,
-                                self.tasks_logs[task].thread_id
+                                self.thread_description(task_log.thread_id),
+                                task_log.scheduling_delay,
+                                status_information(task_log),
+                                measures_information
+                                    .get(&subgraph_index)
+                                    .map(String::as_str)
+                                    .unwrap_or("")
                             ),
                             0.4 + speed * 0.6,
                         ),
                     );
             }
         }
+        // leaves of logged iterators get a speed relative to the fastest leaf of their iterator
+        let leaf_speed = |task: &TaskLog| {
+            task.iterator_work
+                .map(|(iterator, items)| (iterator, items as f64 / task.duration().max(1) as f64))
+        };
+        let mut iterators_best_speeds: HashMap<usize, f64> = HashMap::new();
+        for (iterator, speed) in self.tasks_logs.iter().filter_map(leaf_speed) {
+            let best_speed = iterators_best_speeds.entry(iterator).or_insert(0.0);
+            *best_speed = best_speed.max(speed);
+        }
         // final step, add information for no tags
         for (task_id, task) in self.tasks_logs.iter().enumerate() {
             let duration = task.duration();
+            let (work_information, opacity) = match leaf_speed(task) {
+                Some((iterator, speed)) => {
+                    let best_speed = iterators_best_speeds[&iterator];
+                    let speed = if best_speed > 0.0 {
+                        speed / best_speed
+                    } else {
+                        1.0
+                    };
+                    (
+                        format!(
+                            "\nitems: {}\nspeed: {}",
+                            task.iterator_work.unwrap().1,
+                            speed
+                        ),
+                        0.4 + speed * 0.6,
+                    )
+                }
+                None => (
+                    task.iterator_split
+                        .map(|(position, left, right)| {
+                            format!("\nsplit at: {} ({} | {})", position, left, right)
+                        })
+                        .unwrap_or_default(),
+                    1.0,
+                ),
+            };
             tasks_information
                 .entry(task_id)
                 .or_insert_with(HashMap::new)
@@ -274,18 +1450,29 @@ impl RunLog {
                     "_NO_TAGS_".to_string(),
                     (
                         format!(
-                            "task: {}\nduration: {} (micro sec)\nthread: {}",
+                            "task: {}\nduration: {} (micro sec)\nthread: {}\nscheduling delay: {} (ns){}{}",
                             task_id,
                             duration / 1000,
-                            task.thread_id
+                            self.thread_description(task.thread_id),
+                            task.scheduling_delay,
+                            work_information,
+                            status_information(task)
                         ),
-                        1.0,
+                        opacity,
                     ),
                 );
         }
         tasks_information
     }
 
+    /// Thread id for tooltips, together with its name and os id when known.
+    fn thread_description(&self, thread_id: usize) -> String {
+        match self.threads_info.get(thread_id) {
+            Some(info) => format!("{}{}", thread_id, info),
+            None => thread_id.to_string(),
+        }
+    }
+
     /// Fuse our tags into given tags hash table.
     pub(crate) fn scan_tags(&self, tags: &mut HashMap<String, usize>) {
         for tag in &self.tags {
@@ -316,36 +1503,615 @@ impl RunLog {
     }
 
     /// Compute for each tag, the (total work, total duration, normalised speed).
+    /// Additional measures (see `custom_subgraph_multi`) appear as pseudo-tags
+    /// named "tag/label".
+    /// Speeds of tags are normalised by the fastest tag while speeds of pseudo-tags are
+    /// normalised by the fastest pseudo-tag with the same label (measures of the same unit).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::RunLog;
+    ///
+    /// let log = |measures: &str| -> RunLog {
+    ///     serde_json::from_str(&format!(
+    ///         r#"{{"threads_number": 1, "duration": 20,
+    ///         "tasks_logs": [
+    ///             {{"start_time": 0, "end_time": 10, "thread_id": 0, "children": [1]}},
+    ///             {{"start_time": 10, "end_time": 20, "thread_id": 0, "children": []}}],
+    ///         "tags": ["fast", "slow"],
+    ///         "subgraphs": [[0, 0, 0, 100], [1, 1, 1, 10]],
+    ///         "measures": {}}}"#,
+    ///         measures
+    ///     ))
+    ///     .expect("invalid log")
+    /// };
+    /// let stats = log("[]").stats();
+    /// assert_eq!(stats["fast"], (100, 10, 1.0));
+    /// assert_eq!(stats["slow"], (10, 10, 0.1));
+    /// // large measures do not change the speeds of tags
+    /// let stats = log(r#"[[0, "bytes", 100000], [1, "bytes", 50000]]"#).stats();
+    /// assert_eq!(stats["fast"], (100, 10, 1.0));
+    /// assert_eq!(stats["slow"], (10, 10, 0.1));
+    /// assert_eq!(stats["fast/bytes"], (100_000, 10, 1.0));
+    /// assert_eq!(stats["slow/bytes"], (50_000, 10, 0.5));
+    /// ```
     pub fn stats(&self) -> HashMap<String, (usize, u64, f64)> {
         let mut hash = HashMap::new();
+        // label of each pseudo-tag (none for real tags): speeds are only normalised
+        // among stats sharing the same label
+        let mut labels: HashMap<String, Option<&str>> = HashMap::new();
+        let durations = self.subgraphs_sums(TaskLog::duration);
+        let pseudo_tags = self.measures.iter().map(|(subgraph_index, label, value)| {
+            (
+                format!("{}/{}", self.tags[self.subgraphs[*subgraph_index].2], label),
+                Some(label.as_str()),
+                *value,
+                durations[*subgraph_index],
+            )
+        });
         self.subgraphs
             .iter()
-            .for_each(|&(start_task, end_task, tag_id, work)| {
-                let subgraph_duration = self
-                    .tasks_between(start_task, end_task)
-                    .map(|t| self.tasks_logs[t].duration())
-                    .sum::<u64>();
-                let stat = hash.entry(self.tags[tag_id].clone()).or_insert((0, 0, 0.0));
+            .zip(&durations)
+            .map(|(&(_, _, tag_id, work), &duration)| {
+                (self.tags[tag_id].clone(), None, work, duration)
+            })
+            .chain(pseudo_tags)
+            .for_each(|(tag, label, work, subgraph_duration)| {
+                labels.insert(tag.clone(), label);
+                let stat = hash.entry(tag).or_insert((0, 0, 0.0));
                 stat.0 += work;
                 stat.1 += subgraph_duration;
-                stat.2 = stat.0 as f64 / stat.1 as f64;
+                stat.2 = stat.0 as f64 / stat.1.max(1) as f64;
             });
-        let max_speed = hash
-            .values()
-            .map(|(_, _, s)| *s)
-            .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Less))
-            .unwrap_or(1.0);
+        let mut max_speeds: HashMap<Option<&str>, f64> = HashMap::new();
+        for (tag, (_, _, speed)) in &hash {
+            let max_speed = max_speeds.entry(labels[tag]).or_insert(0.0);
+            *max_speed = max_speed.max(*speed);
+        }
         //Normalise the speeds across tags
-        hash.values_mut().for_each(|(_, _, speed)| {
-            *speed = *speed / max_speed;
+        hash.iter_mut().for_each(|(tag, (_, _, speed))| {
+            let max_speed = max_speeds[&labels[tag]];
+            if max_speed > 0.0 {
+                *speed /= max_speed;
+            }
         });
         hash
     }
 
+    /// Compute for each tag (and pseudo-tag, see `stats`) the total work recorded
+    /// by each thread. Each subgraph's work is attributed to the thread
+    /// which ran its starting task.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::{subgraph, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| {
+    ///     rayon_logs::join(|| subgraph("leaf", 3, || ()), || subgraph("leaf", 4, || ()))
+    /// });
+    /// let works = &log.threads_works()["leaf"];
+    /// assert_eq!(works.len(), 2);
+    /// assert_eq!(works.iter().sum::<usize>(), log.stats()["leaf"].0);
+    /// ```
+    pub fn threads_works(&self) -> HashMap<String, Vec<usize>> {
+        let mut hash: HashMap<String, Vec<usize>> = HashMap::new();
+        let start_thread =
+            |subgraph_index: usize| self.tasks_logs[self.subgraphs[subgraph_index].0].thread_id;
+        let pseudo_tags = self.measures.iter().map(|(subgraph_index, label, value)| {
+            (
+                format!("{}/{}", self.tags[self.subgraphs[*subgraph_index].2], label),
+                *value,
+                start_thread(*subgraph_index),
+            )
+        });
+        self.subgraphs
+            .iter()
+            .enumerate()
+            .map(|(index, &(_, _, tag_id, work))| {
+                (self.tags[tag_id].clone(), work, start_thread(index))
+            })
+            .chain(pseudo_tags)
+            .for_each(|(tag, work, thread)| {
+                let works = hash
+                    .entry(tag)
+                    .or_insert_with(|| vec![0; self.threads_number]);
+                if works.len() <= thread {
+                    works.resize(thread + 1, 0);
+                }
+                works[thread] += work;
+            });
+        hash
+    }
+
+    /// Sizes of the parts logged indexed iterators were not split further into
+    /// (the sequential leaves), summarized as (smallest size, number of leaves) for each
+    /// power of two range of sizes, by increasing sizes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| (0..100_000u32).into_par_iter().for_each(|_| ()));
+    /// let histogram = log.split_sizes_histogram();
+    /// assert!(histogram.windows(2).all(|w| w[0].0 < w[1].0));
+    /// let leaves: usize = histogram.iter().map(|&(_, count)| count).sum();
+    /// let splits = log.tasks().iter().filter(|t| t.iterator_split.is_some()).count();
+    /// assert_eq!(leaves, splits + 1);
+    /// ```
+    pub fn split_sizes_histogram(&self) -> Vec<(usize, usize)> {
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        let split_parts = self.tasks_logs.iter().filter_map(|task| {
+            task.iterator_split
+                .map(|(_, left, right)| task.children.iter().zip([left, right]))
+        });
+        for (_, size) in split_parts
+            .flatten()
+            .filter(|(part, _)| self.tasks_logs[**part].iterator_split.is_none())
+        {
+            let smallest = (size + 1).next_power_of_two() / 2;
+            *counts.entry(smallest).or_insert(0) += 1;
+        }
+        counts.into_iter().collect()
+    }
+
+    /// Split the run into `buckets` windows of equal durations and return for each one the
+    /// fraction of thread-time spent inside tasks (between 0 and 1).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| (0..100_000u64).into_par_iter().sum::<u64>());
+    /// let timeline = log.utilization_timeline(10);
+    /// assert_eq!(timeline.len(), 10);
+    /// assert!(timeline.iter().all(|&u| 0.0 <= u && u <= 1.0 + 1e-9));
+    /// // the mean utilization is the fraction of time spent working
+    /// let activity: u64 = log.tasks().iter().map(|t| t.duration()).sum();
+    /// let mean = timeline.iter().sum::<f64>() / 10.0;
+    /// let expected = activity as f64 / (log.duration() * log.threads_number() as u64) as f64;
+    /// assert!((mean - expected).abs() < 1e-6);
+    /// ```
+    pub fn utilization_timeline(&self, buckets: usize) -> Vec<f64> {
+        let mut timeline = vec![0.0; buckets];
+        let first_time = self.tasks_logs.iter().map(|t| t.start_time).min();
+        let last_time = self.tasks_logs.iter().map(|t| t.end_time).max();
+        let (first_time, last_time) = match (first_time, last_time) {
+            (Some(first_time), Some(last_time)) if last_time > first_time && buckets > 0 => {
+                (first_time, last_time)
+            }
+            _ => return timeline,
+        };
+        let bucket_width = (last_time - first_time) as f64 / buckets as f64;
+        // add the activity of each task to all windows it overlaps
+        for task in &self.tasks_logs {
+            let start = (task.start_time - first_time) as f64;
+            let end = (task.end_time - first_time) as f64;
+            let first_bucket = (start / bucket_width) as usize;
+            let last_bucket = ((end / bucket_width) as usize).min(buckets - 1);
+            for (index, activity) in timeline
+                .iter_mut()
+                .enumerate()
+                .take(last_bucket + 1)
+                .skip(first_bucket)
+            {
+                let bucket_start = index as f64 * bucket_width;
+                let bucket_end = bucket_start + bucket_width;
+                *activity += (end.min(bucket_end) - start.max(bucket_start)).max(0.0);
+            }
+        }
+        let thread_time = bucket_width * self.threads_number.max(1) as f64;
+        for activity in &mut timeline {
+            *activity /= thread_time;
+        }
+        timeline
+    }
+
     /// Load a rayon_logs log file and deserializes it into a `RunLog`.
+    /// Invalid files are reported with the position of the problem.
+    /// Truncated files never load since json documents need to be closed.
+    /// Files with inconsistent contents (see `validate`) are rejected with all problems found.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::RunLog;
+    /// use std::io::ErrorKind;
+    ///
+    /// let path = std::env::temp_dir().join(format!("bad_log_{}.json", std::process::id()));
+    /// std::fs::write(
+    ///     &path,
+    ///     r#"{"threads_number": 1, "duration": 10,
+    ///     "tasks_logs": [{"start_time": 0, "end_time": 10, "thread_id": 0, "children": [5]}],
+    ///     "tags": [], "subgraphs": []}"#,
+    /// )
+    /// .expect("writing failed");
+    /// let error = RunLog::load(&path).expect_err("child 5 does not exist");
+    /// std::fs::remove_file(&path).expect("removing failed");
+    /// assert_eq!(error.kind(), ErrorKind::InvalidData);
+    /// assert!(error.to_string().contains("task 0 has unknown child 5"));
+    /// ```
     pub fn load<P: AsRef<Path>>(path: P) -> Result<RunLog, io::Error> {
-        let file = File::open(path).unwrap();
-        serde_json::from_reader(file).map_err(|_| ErrorKind::InvalidData.into())
+        let path = path.as_ref();
+        let mut log = RunLog::read(path)?;
+        let findings = log.validate();
+        if !findings.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "invalid log file {}: {}",
+                    path.display(),
+                    findings.join(", ")
+                ),
+            ));
+        }
+        // older files do not store work information
+        log.fill_work_information();
+        Ok(log)
+    }
+
+    /// Deserialize a log file without checking its contents.
+    fn read(path: &Path) -> Result<RunLog, io::Error> {
+        let file = BufReader::new(File::open(path)?);
+        serde_json::from_reader(file).map_err(|e| {
+            let problem = if e.is_eof() {
+                "file is truncated"
+            } else {
+                "invalid log file"
+            };
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} {}: {}", problem, path.display(), e),
+            )
+        })
+    }
+
+    /// Concatenate the logs of several phases (successive logged installs) into one log.
+    ///
+    /// Phases are run one after the other: each one starts when the previous one ends and its
+    /// first task follows the last task of the previous one.
+    /// Each phase becomes a subgraph tagged with its name (with a work amount of 1) and
+    /// a mark with its name is added at its start.
+    /// Phases should share the same sampling rate (we keep the first one).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::{RunLog, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (v, load) = pool.logging_install(|| (0..1000u32).into_par_iter().collect::<Vec<_>>());
+    /// let (_, sum) = pool.logging_install(|| v.par_iter().sum::<u32>());
+    /// let tasks = load.tasks().len() + sum.tasks().len();
+    ///
+    /// let log = RunLog::from_phases(&[("load", load), ("sum", sum)]);
+    /// assert_eq!(log.tasks().len(), tasks);
+    /// assert_eq!(log.marks().len(), 2);
+    /// assert!(log.tags().iter().any(|t| t == "load"));
+    /// assert!(log.validate().is_empty());
+    /// let mut svg = Vec::new();
+    /// log.write_svg(&mut svg).expect("writing svg failed");
+    /// ```
+    pub fn from_phases<S: AsRef<str>>(phases: &[(S, RunLog)]) -> RunLog {
+        concatenate(
+            phases
+                .iter()
+                .map(|(name, phase)| (Some(name.as_ref()), phase)),
+        )
+    }
+
+    /// Concatenate several logs (successive logged installs) into one log.
+    ///
+    /// Logs are run one after the other: each one starts when the previous one ends and its
+    /// first task follows the last task of the previous one, so that the graph stays connected.
+    /// Tags are fused by name and the number of threads is the largest one.
+    /// See `from_phases` for tagging each log.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::{RunLog, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (v, load) = pool.logging_install(|| {
+    ///     rayon_logs::subgraph("load", 1000, || (0..1000u32).into_par_iter().collect::<Vec<_>>())
+    /// });
+    /// let (_, sum) = pool.logging_install(|| {
+    ///     rayon_logs::subgraph("sum", 1000, || v.par_iter().sum::<u32>())
+    /// });
+    ///
+    /// let log = RunLog::concat(&[load.clone(), sum.clone()]);
+    /// assert_eq!(
+    ///     log.tasks().len(),
+    ///     load.tasks().len() + sum.tasks().len()
+    /// );
+    /// assert_eq!(log.duration(), load.duration() + sum.duration());
+    /// assert_eq!(log.tags(), vec!["load".to_string(), "sum".to_string()]);
+    /// assert_eq!(log.stats()["sum"].1, sum.stats()["sum"].1);
+    /// assert!(log.validate().is_empty());
+    /// let mut svg = Vec::new();
+    /// log.write_svg(&mut svg).expect("writing svg failed");
+    /// ```
+    pub fn concat(logs: &[RunLog]) -> RunLog {
+        concatenate(logs.iter().map(|log| (None, log)))
+    }
+
+    /// Return the sub-log of all tasks inside subgraphs tagged with given tag
+    /// (nothing if the tag is unknown).
+    /// Subgraphs, measures, dependencies and marks inside these tasks are kept.
+    /// Times are unchanged (still relative to the run's start).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let mut v: Vec<u32> = (0..100_000).map(|i| (i * 7919) % 100_000).collect();
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| v.par_sort());
+    /// let merges = log.filter_by_tag("merge");
+    /// assert!(!merges.tasks().is_empty());
+    /// assert!(merges.tasks().len() < log.tasks().len());
+    /// assert_eq!(merges.tags(), vec!["merge".to_string()]);
+    /// assert_eq!(merges.stats()["merge"], log.stats()["merge"]);
+    /// assert!(merges.duration() <= log.duration());
+    /// assert!(merges.validate().is_empty());
+    /// let mut svg = Vec::new();
+    /// merges.write_svg(&mut svg).expect("writing svg failed");
+    ///
+    /// // unknown tags give an empty log, which still renders
+    /// let nothing = log.filter_by_tag("no such tag");
+    /// assert!(nothing.tasks().is_empty());
+    /// let mut svg = Vec::new();
+    /// nothing.write_svg(&mut svg).expect("writing svg failed");
+    /// assert!(String::from_utf8(svg).expect("invalid utf8").ends_with("</svg>"));
+    /// ```
+    pub fn filter_by_tag(&self, tag: &str) -> RunLog {
+        let kept: Vec<bool> = match self.tags.iter().position(|t| t == tag) {
+            Some(tag_id) => (0..self.tasks_logs.len())
+                .map(|task| {
+                    self.tagged_subgraphs(task)
+                        .any(|subgraph| self.subgraphs[subgraph].2 == tag_id)
+                })
+                .collect(),
+            None => vec![false; self.tasks_logs.len()],
+        };
+        // subgraphs inside kept tasks (including nested ones), with their tags renumbered
+        let mut tags = Vec::new();
+        let mut subgraphs_indices = HashMap::new();
+        let mut subgraphs = Vec::new();
+        for (index, &(start, end, tag_id, work)) in self.subgraphs.iter().enumerate() {
+            if kept[start] && kept[end] {
+                subgraphs_indices.insert(index, subgraphs.len());
+                let tag_id = tag_index(&mut tags, &self.tags[tag_id]);
+                subgraphs.push((start, end, tag_id, work));
+            }
+        }
+        let measures = self
+            .measures
+            .iter()
+            .filter_map(|(subgraph, label, value)| {
+                subgraphs_indices
+                    .get(subgraph)
+                    .map(|&index| (index, label.clone(), *value))
+            })
+            .collect();
+        let mut dependencies: Vec<_> = self
+            .dependencies
+            .iter()
+            .filter(|&&(producer, consumer)| kept[producer] && kept[consumer])
+            .cloned()
+            .collect();
+        let mut tasks_logs = self.tasks_logs.clone();
+        retain_tasks(&mut tasks_logs, &mut subgraphs, &mut dependencies, &kept);
+
+        let start = tasks_logs.iter().map(|t| t.start_time).min().unwrap_or(0);
+        let end = tasks_logs.iter().map(|t| t.end_time).max().unwrap_or(0);
+        let threads_number = tasks_logs
+            .iter()
+            .map(|t| t.thread_id + 1)
+            .max()
+            .unwrap_or(0);
+        let mut log = RunLog {
+            threads_number,
+            tasks_logs,
+            duration: end - start,
+            tags,
+            subgraphs,
+            measures,
+            marks: self
+                .marks
+                .iter()
+                .filter(|(time, _)| start <= *time && *time <= end)
+                .cloned()
+                .collect(),
+            dependencies,
+            sampling_rate: self.sampling_rate,
+            threads_info: self
+                .threads_info
+                .iter()
+                .take(threads_number)
+                .cloned()
+                .collect(),
+            clock: self.clock,
+            warnings: self.warnings.clone(),
+            structure: OnceLock::new(),
+        };
+        log.fill_work_information();
+        log
+    }
+
+    /// Compare this run with another run of the same algorithm.
+    /// Tags are matched by name: for each one we report changes in tasks count, total
+    /// duration and speed. Tags present in only one run are reported as added or removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::RunLog;
+    ///
+    /// let log = |json: &str| -> RunLog { serde_json::from_str(json).expect("invalid log") };
+    /// let before = log(r#"{"threads_number": 2, "duration": 100,
+    ///     "tasks_logs": [
+    ///         {"start_time": 0, "end_time": 40, "thread_id": 0, "children": [1, 2]},
+    ///         {"start_time": 40, "end_time": 100, "thread_id": 0, "children": []},
+    ///         {"start_time": 40, "end_time": 80, "thread_id": 1, "children": []}],
+    ///     "tags": ["sort", "merge"], "subgraphs": [[0, 0, 0, 20], [1, 1, 1, 30]]}"#);
+    /// let after = log(r#"{"threads_number": 2, "duration": 150,
+    ///     "tasks_logs": [
+    ///         {"start_time": 0, "end_time": 50, "thread_id": 0, "children": [1, 2]},
+    ///         {"start_time": 50, "end_time": 150, "thread_id": 0, "children": []},
+    ///         {"start_time": 50, "end_time": 100, "thread_id": 1, "children": []}],
+    ///     "tags": ["merge", "scan"], "subgraphs": [[1, 1, 0, 30], [2, 2, 1, 10]]}"#);
+    ///
+    /// let diff = before.diff(&after);
+    /// assert_eq!(diff.duration_change(), 50);
+    /// assert_eq!(diff.idle_times, (60, 100));
+    /// assert_eq!(diff.idle_time_change(), 40);
+    /// let merge = diff.tags.iter().find(|t| t.tag == "merge").expect("no merge");
+    /// assert_eq!(merge.before, Some((1, 60, 0.5)));
+    /// assert_eq!(merge.after, Some((1, 100, 0.3)));
+    /// assert_eq!(merge.duration_change(), 40);
+    /// assert_eq!(merge.tasks_count_change(), 0);
+    /// assert!((merge.speed_change() + 0.2).abs() < 1e-9);
+    /// assert!((merge.relative_duration_change().unwrap() - 200.0 / 3.0).abs() < 1e-9);
+    /// assert_eq!(diff.regressions(50.0).count(), 1);
+    /// assert_eq!(diff.regressions(70.0).count(), 0);
+    /// assert_eq!(diff.removed_tags().collect::<Vec<_>>(), vec!["sort"]);
+    /// assert_eq!(diff.added_tags().collect::<Vec<_>>(), vec!["scan"]);
+    /// let table = diff.to_string();
+    /// assert!(table.starts_with("duration: 100ns -> 150ns (+50.0%)"));
+    /// assert!(table.contains("sort  | removed (1 tasks, 40ns)"));
+    /// assert!(table.contains("scan  | added (1 tasks, 50ns)"));
+    /// ```
+    pub fn diff(&self, other: &RunLog) -> RunLogDiff {
+        RunLogDiff::new(self, other)
+    }
+
+    /// Look at the durations of the leaf tasks (tasks which did not fork) to figure out
+    /// if tasks are too small compared to the given overhead per task (in nanoseconds,
+    /// `TASK_OVERHEAD` is a reasonable estimate).
+    /// Leaves shorter than ten times the overhead are deemed too small.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::{RunLog, TASK_OVERHEAD};
+    ///
+    /// let log: RunLog = serde_json::from_str(
+    ///     r#"{"threads_number": 2, "duration": 51000,
+    ///     "tasks_logs": [
+    ///         {"start_time": 0, "end_time": 1000, "thread_id": 0, "children": [1, 2]},
+    ///         {"start_time": 1000, "end_time": 4000, "thread_id": 0, "children": []},
+    ///         {"start_time": 1000, "end_time": 51000, "thread_id": 1, "children": []}],
+    ///     "tags": [], "subgraphs": []}"#,
+    /// )
+    /// .expect("invalid log");
+    /// let report = log.granularity_report(TASK_OVERHEAD);
+    /// assert_eq!(report.threshold, 10 * TASK_OVERHEAD);
+    /// assert_eq!(report.work, 54000);
+    /// assert_eq!(report.small_leaves_work, 3000);
+    /// assert_eq!(report.buckets, vec![(2048, 1, 3000), (32768, 1, 50000)]);
+    /// assert!(!report.too_fine());
+    /// // with larger overheads both leaves are too small
+    /// let report = log.granularity_report(10_000);
+    /// assert!(report.too_fine());
+    /// assert_eq!(
+    ///     report.suggestion(),
+    ///     "increase sequential cutoff: 98% of work is in tasks < 100.00us"
+    /// );
+    /// ```
+    pub fn granularity_report(&self, overhead: TimeStamp) -> GranularityReport {
+        GranularityReport::new(self, overhead)
+    }
+
+    /// Check that all indices (tasks, threads, tags, subgraphs) are in range and that tasks
+    /// do not end before starting.
+    /// Logs produced by `logging_install` are always valid but loaded files might not be.
+    /// Return a description of each problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let tasks_number = self.tasks_logs.len();
+        let mut findings = Vec::new();
+        for (task_id, task) in self.tasks_logs.iter().enumerate() {
+            if task.end_time < task.start_time {
+                findings.push(format!("task {} ends before starting", task_id));
+            }
+            if task.thread_id >= self.threads_number {
+                findings.push(format!(
+                    "task {} runs on unknown thread {}",
+                    task_id, task.thread_id
+                ));
+            }
+            for &child in task.children.iter().filter(|&&c| c >= tasks_number) {
+                findings.push(format!("task {} has unknown child {}", task_id, child));
+            }
+        }
+        for (index, &(start, end, tag, _)) in self.subgraphs.iter().enumerate() {
+            for &task in [start, end].iter().filter(|&&t| t >= tasks_number) {
+                findings.push(format!("subgraph {} uses unknown task {}", index, task));
+            }
+            if tag >= self.tags.len() {
+                findings.push(format!("subgraph {} has unknown tag {}", index, tag));
+            }
+        }
+        for (subgraph, label, _) in &self.measures {
+            if *subgraph >= self.subgraphs.len() {
+                findings.push(format!(
+                    "measure {} refers to unknown subgraph {}",
+                    label, subgraph
+                ));
+            }
+        }
+        for &(producer, consumer) in &self.dependencies {
+            for &task in [producer, consumer].iter().filter(|&&t| t >= tasks_number) {
+                findings.push(format!("dependency uses unknown task {}", task));
+            }
+        }
+        if self.sampling_rate == 0 {
+            findings.push("sampling rate is zero".to_string());
+        }
+        findings
+    }
+
+    /// Compute the graphical layout of this log.
+    /// This is intended for custom renderers, see the `visualisation` module.
+    pub fn scene(&self) -> Scene {
+        visualisation(self)
     }
 
     /// Save an svg file of all logged information.
@@ -354,10 +2120,202 @@ impl RunLog {
         write_svg_file(&scene, path)
     }
 
+    /// Write an svg document of all logged information to given writer.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| (0..1000u32).into_par_iter().max());
+    /// let mut svg = Vec::new();
+    /// log.write_svg(&mut svg).expect("writing svg failed");
+    /// assert!(svg.starts_with(b"<?xml"));
+    ///
+    /// // rendering is deterministic, even for a reloaded log
+    /// let path = std::env::temp_dir().join(format!("svg_log_{}.json", std::process::id()));
+    /// log.save(&path).expect("saving failed");
+    /// let loaded = rayon_logs::RunLog::load(&path).expect("loading failed");
+    /// std::fs::remove_file(&path).expect("removing failed");
+    /// let mut again = Vec::new();
+    /// loaded.write_svg(&mut again).expect("writing svg failed");
+    /// assert!(svg == again);
+    /// ```
+    pub fn write_svg<W: io::Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        let scene = visualisation(self);
+        write_svg(&scene, writer)
+    }
+
+    /// Save an svg file displaying several logs stacked vertically,
+    /// all on the same time scale.
+    pub fn save_svg_many<P: AsRef<Path>>(logs: &[RunLog], path: P) -> Result<(), io::Error> {
+        let scene = visualisation_many(logs);
+        write_svg_file(&scene, path)
+    }
+
+    /// Write an svg document displaying several logs stacked vertically to given writer.
+    /// See `save_svg_many`.
+    pub fn write_svg_many<W: io::Write>(logs: &[RunLog], writer: &mut W) -> Result<(), io::Error> {
+        let scene = visualisation_many(logs);
+        write_svg(&scene, writer)
+    }
+
+    /// Save the tasks graph as a graphviz dot file.
+    /// When collapsing subgraphs, each outermost subgraph is displayed as a single node.
+    pub fn save_dot<P: AsRef<Path>>(
+        &self,
+        path: P,
+        collapse_subgraphs: bool,
+    ) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_dot(&mut file, collapse_subgraphs)?;
+        file.flush()
+    }
+
+    /// Write the tasks graph in graphviz's dot format to given writer (see `save_dot`).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::{subgraph, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| {
+    ///     subgraph("join", 2, || rayon_logs::join(|| 1, || 2))
+    /// });
+    /// let mut dot = Vec::new();
+    /// log.write_dot(&mut dot, false).expect("writing dot failed");
+    /// let dot = String::from_utf8(dot).unwrap();
+    /// assert!(dot.starts_with("digraph"));
+    /// let edges: usize = log.tasks().iter().map(|t| t.children.len()).sum();
+    /// assert_eq!(dot.matches("->").count(), edges);
+    ///
+    /// let mut collapsed = Vec::new();
+    /// log.write_dot(&mut collapsed, true).expect("writing dot failed");
+    /// let collapsed = String::from_utf8(collapsed).unwrap();
+    /// // the whole join is now a single node
+    /// assert_eq!(collapsed.matches("join\\nwork: 2").count(), 1);
+    /// assert!(collapsed.matches("->").count() < edges);
+    /// ```
+    pub fn write_dot<W: io::Write>(
+        &self,
+        writer: &mut W,
+        collapse_subgraphs: bool,
+    ) -> Result<(), io::Error> {
+        write_dot(self, writer, collapse_subgraphs)
+    }
+
+    /// Save tasks as a chrome trace file (for chrome://tracing or perfetto).
+    pub fn save_chrome_trace<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_chrome_trace(&mut file)?;
+        file.flush()
+    }
+
+    /// Write tasks and marks as chrome trace events to given writer (see `save_chrome_trace`).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+    /// let mut trace = Vec::new();
+    /// log.write_chrome_trace(&mut trace).expect("writing trace failed");
+    /// let events: serde_json::Value = serde_json::from_slice(&trace).expect("invalid json");
+    /// assert_eq!(events.as_array().unwrap().len(), log.tasks().len());
+    /// assert_eq!(events[0]["ph"], "X");
+    /// ```
+    pub fn write_chrome_trace<W: io::Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        write_chrome_trace(self, writer)
+    }
+
+    /// Save a csv table with one line per task.
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_csv(&mut file)?;
+        file.flush()
+    }
+
+    /// Write a csv table with one line per task (id, thread, start and end times,
+    /// duration, innermost tag and children) to given writer (see `save_csv`).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+    /// let mut csv = Vec::new();
+    /// log.write_csv(&mut csv).expect("writing csv failed");
+    /// let csv = String::from_utf8(csv).unwrap();
+    /// assert_eq!(csv.lines().count(), 1 + log.tasks().len());
+    /// // the join forks two tasks
+    /// assert!(csv.lines().any(|line| line.split(',').last().unwrap().contains(' ')));
+    /// ```
+    pub fn write_csv<W: io::Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        write_csv(self, writer)
+    }
+
     /// Save log file of currently recorded tasks logs.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
-        let file = File::create(path)?;
-        serde_json::to_writer(file, &self).expect("failed serializing");
-        Ok(())
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()
+    }
+
+    /// Write currently recorded tasks logs as json to given writer (see `save`).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use rayon_logs::ThreadPoolBuilder;
+    /// use std::io::{Result, Write};
+    ///
+    /// /// A writer accepting only one byte at a time.
+    /// struct ShortWriter(Vec<u8>);
+    ///
+    /// impl Write for ShortWriter {
+    ///     fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+    ///         self.0.extend(buffer.iter().take(1));
+    ///         Ok(buffer.len().min(1))
+    ///     }
+    ///     fn flush(&mut self) -> Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+    /// let mut json = Vec::new();
+    /// log.write(&mut json).expect("writing json failed");
+    /// let mut short_writer = ShortWriter(Vec::new());
+    /// log.write(&mut short_writer).expect("writing json failed");
+    /// assert_eq!(json, short_writer.0);
+    /// ```
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        serde_json::to_writer(writer, &self).map_err(io::Error::from)
     }
 }
@@ -0,0 +1,277 @@
+//! Logging functions compiled down to direct calls into rayon (`disabled` feature).
+//!
+//! No events are generated, no task ids are allocated and no thread local is accessed,
+//! so calls to rayon_logs can stay in the code at no cost when not profiling.
+//! Logs obtained with `ThreadPool::logging_install` are then empty.
+//! They can still be saved and analyzed like any other log:
+//!
+//! ```
+//! use rayon_logs::{RunLog, ThreadPoolBuilder};
+//!
+//! let pool = ThreadPoolBuilder::new()
+//!     .num_threads(2)
+//!     .build()
+//!     .expect("building pool failed");
+//! let (result, log) = pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+//! assert_eq!(result, (1, 2));
+//! assert!(log.tasks().is_empty());
+//! let mut output = Vec::new();
+//! log.write_svg(&mut output).expect("writing svg failed");
+//! RunLog::write_svg_many(&[log.clone(), log.clone()], &mut output).expect("writing svg failed");
+//! log.write_dot(&mut output, true).expect("writing dot failed");
+//! log.write_chrome_trace(&mut output).expect("writing trace failed");
+//! log.write_csv(&mut output).expect("writing csv failed");
+//! assert_eq!((log.work(), log.span()), (0, 0));
+//! assert!(log.stats().is_empty());
+//! assert!(log.threads_idle_causes().iter().all(|&causes| causes == (0, 0)));
+//! assert_eq!(log.granularity_report(rayon_logs::TASK_OVERHEAD).small_work_fraction(), 0.0);
+//! ```
+use rayon::iter::plumbing::*;
+use rayon::iter::*;
+use std::borrow::Cow;
+
+pub use rayon::{broadcast, join, join_context, scope, scope_fifo, spawn, Scope, ScopeFifo};
+
+/// Execute the three closures potentially in parallel.
+#[inline]
+pub fn join3<A, B, C, RA, RB, RC>(oper_a: A, oper_b: B, oper_c: C) -> (RA, RB, RC)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    C: FnOnce() -> RC + Send,
+    RA: Send,
+    RB: Send,
+    RC: Send,
+{
+    let (ra, (rb, rc)) = rayon::join(oper_a, || rayon::join(oper_b, oper_c));
+    (ra, rb, rc)
+}
+
+/// Execute the four closures potentially in parallel.
+#[inline]
+pub fn join4<A, B, C, D, RA, RB, RC, RD>(
+    oper_a: A,
+    oper_b: B,
+    oper_c: C,
+    oper_d: D,
+) -> (RA, RB, RC, RD)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    C: FnOnce() -> RC + Send,
+    D: FnOnce() -> RD + Send,
+    RA: Send,
+    RB: Send,
+    RC: Send,
+    RD: Send,
+{
+    let ((ra, rb), (rc, rd)) = rayon::join(
+        || rayon::join(oper_a, oper_b),
+        || rayon::join(oper_c, oper_d),
+    );
+    (ra, rb, rc, rd)
+}
+
+/// Run op (nothing is tagged).
+#[inline]
+pub fn subgraph<OP, R>(_work_type: &'static str, _work_amount: usize, op: OP) -> R
+where
+    OP: FnOnce() -> R,
+{
+    op()
+}
+
+/// Run op (nothing is tagged).
+#[inline]
+pub fn subgraph_owned<T, OP, R>(_tag: T, _work_amount: usize, op: OP) -> R
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+{
+    op()
+}
+
+/// Run op (nothing is measured).
+#[inline]
+pub fn custom_subgraph<T, OP, R, START, END, S>(_tag: T, _start: START, _end: END, op: OP) -> R
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+    START: FnOnce() -> S,
+    END: FnOnce(S) -> usize,
+{
+    op()
+}
+
+/// Run op and return the measured value (nothing is logged).
+#[inline]
+pub fn custom_subgraph_measured<T, OP, R, START, END, S>(
+    _tag: T,
+    start: START,
+    end: END,
+    op: OP,
+) -> (R, usize)
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+    START: FnOnce() -> S,
+    END: FnOnce(S) -> usize,
+{
+    let s = start();
+    let r = op();
+    (r, end(s))
+}
+
+/// Run op (nothing is measured).
+#[inline]
+pub fn custom_subgraph_multi<T, OP, R, START, END, S>(
+    _tag: T,
+    _start: START,
+    _end: END,
+    op: OP,
+) -> R
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+    START: FnOnce() -> S,
+    END: FnOnce(S) -> Vec<(&'static str, usize)>,
+{
+    op()
+}
+
+/// Does nothing.
+#[inline]
+pub fn start_subgraph<T: Into<Cow<'static, str>>>(_tag: T) {}
+
+/// Does nothing.
+#[inline]
+pub fn end_subgraph<T: Into<Cow<'static, str>>>(_tag: T, _measured_value: usize) {}
+
+/// Does nothing.
+#[inline]
+pub fn mark(_label: &'static str) {}
+
+/// Does nothing.
+#[inline]
+pub fn logging_enabled(_enabled: bool) {}
+
+/// Does nothing.
+#[inline]
+pub fn sample_rate(_rate: usize) {}
+
+/// Run op.
+#[inline]
+pub fn unlogged<R>(op: impl FnOnce() -> R) -> R {
+    op()
+}
+
+/// Always `None`.
+#[inline]
+pub fn current_task_id() -> Option<usize> {
+    None
+}
+
+/// Always empty.
+#[inline]
+pub fn current_subgraph_stack() -> Vec<Cow<'static, str>> {
+    Vec::new()
+}
+
+/// Dependencies are not recorded.
+pub struct DependencyHandle;
+
+impl DependencyHandle {
+    /// Does nothing.
+    #[inline]
+    pub fn arrived(self) {}
+}
+
+/// Return a handle recording nothing.
+#[inline]
+pub fn dependency_handle() -> DependencyHandle {
+    DependencyHandle
+}
+
+/// Rayon's `split` (nothing is logged).
+#[inline]
+pub fn split<D, S>(data: D, splitter: S) -> Logged<rayon::iter::Split<D, S>>
+where
+    D: Send,
+    S: Fn(D) -> (D, Option<D>) + Sync + Send,
+{
+    Logged::new(rayon::iter::split(data, splitter))
+}
+
+/// `Logged` is an iterator forwarding everything to the iterator it wraps.
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct Logged<I: ParallelIterator> {
+    base: I,
+}
+
+impl<I: ParallelIterator> Logged<I> {
+    /// Create a new `Logged` iterator.
+    #[inline]
+    pub fn new(base: I) -> Logged<I> {
+        Logged { base }
+    }
+
+    /// Create a new `Logged` iterator (the label is ignored).
+    #[inline]
+    pub fn named(base: I, _label: &'static str) -> Logged<I> {
+        Logged { base }
+    }
+
+    /// Create a new `Logged` iterator (the label is ignored).
+    #[inline]
+    pub(crate) fn tagging(base: I, _label: &'static str) -> Logged<I> {
+        Logged { base }
+    }
+}
+
+impl<T, I> ParallelIterator for Logged<I>
+where
+    I: ParallelIterator<Item = T>,
+    T: Send,
+{
+    type Item = T;
+
+    #[inline]
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.base.drive_unindexed(consumer)
+    }
+
+    #[inline]
+    fn opt_len(&self) -> Option<usize> {
+        self.base.opt_len()
+    }
+}
+
+impl<T, I> IndexedParallelIterator for Logged<I>
+where
+    I: IndexedParallelIterator<Item = T>,
+    T: Send,
+{
+    #[inline]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        self.base.drive(consumer)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    #[inline]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.base.with_producer(callback)
+    }
+}
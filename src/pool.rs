@@ -8,51 +8,448 @@ use perfcnt::linux::PerfCounterBuilderLinux;
 use perfcnt::linux::{CacheId, CacheOpId, CacheOpResultId, HardwareEventType, SoftwareEventType};
 #[cfg(feature = "perf")]
 use perfcnt::{AbstractPerfCounter, PerfCounter};
+#[cfg(feature = "perf")]
+use std::sync::Once;
 
-use crate::log::RunLog;
-use crate::raw_events::{now, RayonEvent, TaskId};
+use crate::clock::Clock;
+use crate::log::{LogError, RunLog, ThreadInfo};
+use crate::raw_events::{now, RawLogs, RayonEvent, TaskId, TimeStamp};
 use crate::storage::Storage;
 use crate::Comparator;
 use crate::{scope, scope_fifo, Scope, ScopeFifo};
 use rayon;
 use rayon::FnContext;
-use std::cell::RefCell;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Number of task ids each thread reserves at once (see `next_task_id`).
+const TASK_IDS_BLOCK: usize = 1024;
+
+/// Ids generators of a logged pool.
+/// Each pool has its own so that concurrent logged computations in different pools
+/// do not share ids (and resetting one pool does not affect the others).
+#[derive(Default)]
+pub(crate) struct IdsGenerators {
+    /// We use an atomic usize to generate unique ids for tasks
+    /// (threads reserve them by blocks).
+    tasks: AtomicUsize,
+    /// We use an atomic usize to generate unique ids for iterators.
+    iterators: AtomicUsize,
+    /// We use an atomic usize to generate unique ids for manual dependencies.
+    dependencies: AtomicUsize,
+    /// Incremented at each reset, invalidating all task ids reserved by threads.
+    epoch: AtomicUsize,
+}
+
+impl IdsGenerators {
+    /// Restart all ids from 0.
+    fn reset(&self) {
+        self.tasks.store(0, Ordering::Relaxed);
+        self.iterators.store(0, Ordering::Relaxed);
+        self.dependencies.store(0, Ordering::Relaxed);
+        self.epoch.fetch_add(1, Ordering::Relaxed);
+    }
+}
 
-/// We use an atomic usize to generate unique ids for tasks.
-pub(crate) static NEXT_TASK_ID: AtomicUsize = AtomicUsize::new(0);
-/// We use an atomic usize to generate unique ids for iterators.
-pub(crate) static NEXT_ITERATOR_ID: AtomicUsize = AtomicUsize::new(0);
+// ids generators of the pool this thread belongs to (set by the pool's start handler)
+thread_local!(pub(crate) static IDS: RefCell<Arc<IdsGenerators>> = RefCell::new(Arc::new(IdsGenerators::default())));
 
-/// get an id for a new task and increment global tasks counter.
+// task ids reserved by this thread: epoch of reservation, next id and end of reserved block
+thread_local!(static RESERVED_TASK_IDS: Cell<(usize, TaskId, TaskId)> = const { Cell::new((0, 0, 0)) });
+
+/// get an id for a new task.
+/// Each thread reserves blocks of ids from the pool's tasks counter so ids are unique
+/// but not contiguous: unused ones are removed when post-processing logs.
 pub fn next_task_id() -> TaskId {
-    NEXT_TASK_ID.fetch_add(1, Ordering::SeqCst)
+    IDS.with(|ids| {
+        let ids = ids.borrow();
+        let epoch = ids.epoch.load(Ordering::Relaxed);
+        RESERVED_TASK_IDS.with(|reserved| {
+            let (reserved_epoch, next_id, end) = reserved.get();
+            if reserved_epoch == epoch && next_id < end {
+                reserved.set((epoch, next_id + 1, end));
+                next_id
+            } else {
+                let start = ids.tasks.fetch_add(TASK_IDS_BLOCK, Ordering::Relaxed);
+                reserved.set((epoch, start + 1, start + TASK_IDS_BLOCK));
+                start
+            }
+        })
+    })
 }
 
-/// get an id for a new iterator and increment global iterators counter.
+/// get an id for a new iterator and increment the pool's iterators counter.
 pub fn next_iterator_id() -> usize {
-    NEXT_ITERATOR_ID.fetch_add(1, Ordering::SeqCst)
+    IDS.with(|ids| ids.borrow().iterators.fetch_add(1, Ordering::Relaxed))
 }
 
 thread_local!(pub(crate) static LOGS: RefCell<Arc<Storage<RayonEvent>>> = RefCell::new(Arc::new(Storage::new())));
 
+/// Are we currently recording events (see `logging_enabled`).
+pub(crate) static LOGGING_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable (globally) the recording of events.
+/// Disabling it in the middle of a logged computation while other threads are working will
+/// produce incomplete tasks: prefer `unlogged` which keeps the graph consistent.
+pub fn logging_enabled(enabled: bool) {
+    LOGGING_ENABLED.store(enabled, Ordering::SeqCst)
+}
+
+/// Only one logged `join` or `subgraph` out of `SAMPLE_RATE` is recorded (see `sample_rate`).
+pub(crate) static SAMPLE_RATE: AtomicUsize = AtomicUsize::new(1);
+
+// number of logged `join`/`subgraph` calls seen by this thread (for sampling)
+thread_local!(static SAMPLING_COUNTER: Cell<usize> = const { Cell::new(0) });
+// how many sampled-out regions without any active task we are nested in on this thread
+thread_local!(static SAMPLED_OUT_DEPTH: Cell<usize> = const { Cell::new(0) });
+
+/// Only record one call out of `rate` to the logged `join` (and `join_context`, `join3`,
+/// `join4`) and `subgraph` functions (on each thread). The other ones call straight into rayon.
+///
+/// This reduces logging costs on big computations. A sampled-out join executes in the task
+/// calling it. If its second closure is stolen, everything running inside it on the thief
+/// is not recorded (and appears as idle time) since no task is there to contain it.
+/// The rate is recorded in each `RunLog` (`sampling_rate`) so that analysis can scale counts.
+/// Default rate is 1 (record everything).
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// fn sum(v: &[u32]) -> u32 {
+///     if v.len() == 1 {
+///         v[0]
+///     } else {
+///         let (left, right) = v.split_at(v.len() / 2);
+///         let (a, b) = rayon_logs::join(|| sum(left), || sum(right));
+///         a + b
+///     }
+/// }
+///
+/// let v: Vec<u32> = (0..64).collect();
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// rayon_logs::sample_rate(4);
+/// let (total, log) = pool.logging_install(|| sum(&v));
+/// rayon_logs::sample_rate(1);
+/// assert_eq!(total, 64 * 63 / 2);
+/// assert_eq!(log.sampling_rate(), 4);
+/// // 63 joins would give 189 tasks without sampling
+/// assert!(log.tasks().len() < 1 + 3 * 63);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `rate` is 0.
+pub fn sample_rate(rate: usize) {
+    assert!(rate > 0, "sampling rate should be at least 1");
+    SAMPLE_RATE.store(rate, Ordering::SeqCst)
+}
+
+/// Should the current call to a logged `join` or `subgraph` be recorded.
+fn sampled() -> bool {
+    let rate = SAMPLE_RATE.load(Ordering::Relaxed);
+    if rate == 1 {
+        return !in_sampled_out_region();
+    }
+    !in_sampled_out_region()
+        && SAMPLING_COUNTER.with(|c| {
+            let count = c.get();
+            c.set(count + 1);
+            count % rate == 0
+        })
+}
+
+/// Are we running on this thread inside a sampled-out region (without any active task).
+pub(crate) fn in_sampled_out_region() -> bool {
+    SAMPLED_OUT_DEPTH.with(|d| d.get() != 0)
+}
+
+/// Nothing is recorded on the current thread while a `SampledOutRegion` is alive.
+pub(crate) struct SampledOutRegion;
+
+impl SampledOutRegion {
+    pub(crate) fn enter() -> Self {
+        SAMPLED_OUT_DEPTH.with(|d| d.set(d.get() + 1));
+        SampledOutRegion
+    }
+}
+
+impl Drop for SampledOutRegion {
+    fn drop(&mut self) {
+        SAMPLED_OUT_DEPTH.with(|d| d.set(d.get() - 1));
+    }
+}
+
+/// Run given closure in a sampled-out region: nothing is recorded on this thread until it ends.
+pub(crate) fn sampled_out<R>(op: impl FnOnce() -> R) -> R {
+    let _region = SampledOutRegion::enter();
+    op()
+}
+
+/// Are we currently recording events on this thread.
+/// Never true with the `disabled` feature, so that events are not even built.
+#[inline]
+pub(crate) fn recording() -> bool {
+    cfg!(not(feature = "disabled"))
+        && LOGGING_ENABLED.load(Ordering::Relaxed)
+        && !in_sampled_out_region()
+}
+
+// mirror of the active task on this thread (see `current_task_id`)
+thread_local!(static CURRENT_TASK: Cell<Option<TaskId>> = const { Cell::new(None) });
+// mirror of the subgraphs started on this thread (see `current_subgraph_stack`)
+thread_local!(static CURRENT_SUBGRAPHS: RefCell<Vec<Cow<'static, str>>> = const { RefCell::new(Vec::new()) });
+
+/// Update the mirror of the active task and subgraphs with given event (about to be logged).
+pub(crate) fn track(event: &RayonEvent) {
+    match event {
+        RayonEvent::TaskStart(task, _) => CURRENT_TASK.with(|t| t.set(Some(*task))),
+        RayonEvent::TaskEnd(_) => CURRENT_TASK.with(|t| t.set(None)),
+        RayonEvent::SubgraphStart(tag) => {
+            CURRENT_SUBGRAPHS.with(|s| s.borrow_mut().push(tag.clone()))
+        }
+        RayonEvent::SubgraphEnd(..) => {
+            CURRENT_SUBGRAPHS.with(|s| s.borrow_mut().pop());
+        }
+        _ => (),
+    }
+}
+
+/// Return the id of the task currently running on this thread (as found in the
+/// `tasks_logs` of the final `RunLog`, unless some tasks were not recorded).
+/// Return `None` outside of logged computations.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// assert_eq!(rayon_logs::current_task_id(), None);
+/// let ((a, b), log) = pool.logging_install(|| {
+///     rayon_logs::join(rayon_logs::current_task_id, rayon_logs::current_task_id)
+/// });
+/// let (a, b) = (a.unwrap(), b.unwrap());
+/// assert!(log.tasks()[0].children.contains(&a));
+/// assert!(log.tasks()[0].children.contains(&b));
+/// ```
+pub fn current_task_id() -> Option<usize> {
+    CURRENT_TASK.with(|t| t.get())
+}
+
+/// Return the tags of all subgraphs started on this thread which are not finished yet
+/// (innermost last).
+/// Subgraphs started on other threads are not included, even if the current task belongs to
+/// them.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{subgraph, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (stack, _) = pool.logging_install(|| {
+///     subgraph("outer", 1, || subgraph("inner", 1, rayon_logs::current_subgraph_stack))
+/// });
+/// assert_eq!(stack, vec!["outer", "inner"]);
+/// assert!(rayon_logs::current_subgraph_stack().is_empty());
+/// ```
+pub fn current_subgraph_stack() -> Vec<Cow<'static, str>> {
+    CURRENT_SUBGRAPHS.with(|s| s.borrow().clone())
+}
+
 /// Add given event to logs of current thread.
 pub(crate) fn log(event: RayonEvent) {
-    LOGS.with(|l| l.borrow().push(event))
+    if recording() {
+        track(&event);
+        LOGS.with(|l| l.borrow().push(event))
+    }
 }
 
 /// Logs several events at once (with decreased cost).
 macro_rules! logs {
+    // read the clock once for all events (adjacent events share the same time)
+    ($time:ident => $($x:expr ), +) => {
+        if $crate::pool::recording() {
+            let $time = $crate::raw_events::now();
+            $crate::pool::LOGS.with(|l| {let thread_logs = l.borrow();
+                $(
+                    let event = $x;
+                    $crate::pool::track(&event);
+                    thread_logs.push(event);
+                    )*
+            })
+        }
+    };
     ($($x:expr ), +) => {
-        $crate::pool::LOGS.with(|l| {let thread_logs = l.borrow();
-            $(
-                thread_logs.push($x);
-                )*
-        })
+        if $crate::pool::recording() {
+            $crate::pool::LOGS.with(|l| {let thread_logs = l.borrow();
+                $(
+                    let event = $x;
+                    $crate::pool::track(&event);
+                    thread_logs.push(event);
+                    )*
+            })
+        }
+    }
+}
+
+/// Run given closure without recording any event.
+///
+/// The current task ends before the closure and a new task starts after it, so that the graph
+/// stays consistent. Time spent in the closure will appear as idle time.
+/// Recording is only disabled on the current thread (and on threads stealing work from the
+/// closure) so other logged computations running in parallel are not affected.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::unlogged(|| rayon_logs::join(|| 1, || 2)); // not in the logs
+///     rayon_logs::join(|| 3, || 4)
+/// });
+/// // initial task, its continuation and the logged join
+/// assert_eq!(log.tasks().len(), 2 + 3);
+///
+/// // another pool used inside the un-logged closure is still logged
+/// let other_pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (other_log, _) = pool.logging_install(|| {
+///     rayon_logs::unlogged(|| other_pool.logging_install(|| rayon_logs::join(|| 1, || 2)).1)
+/// });
+/// assert_eq!(other_log.tasks().len(), 1 + 3);
+///
+/// // logging stays disabled if it was
+/// rayon_logs::logging_enabled(false);
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::unlogged(|| ());
+///     rayon_logs::join(|| 1, || 2)
+/// });
+/// rayon_logs::logging_enabled(true);
+/// assert!(log.tasks().is_empty());
+/// ```
+pub fn unlogged<R>(op: impl FnOnce() -> R) -> R {
+    if !recording() {
+        return op(); // we are nested in another unlogged region
+    }
+    let continuation_id = next_task_id();
+    logs!(
+        RayonEvent::Child(continuation_id),
+        RayonEvent::TaskEnd(now())
+    );
+    let result = catch_unwind(AssertUnwindSafe(|| sampled_out(op)));
+    log(RayonEvent::TaskStart(continuation_id, now()));
+    result.unwrap_or_else(|payload| resume_unwind(payload))
+}
+
+/// Drop a named instant marker into the logs (like "phase 2 starts here").
+///
+/// Markers do not change the tasks graph. They are displayed in svg files as vertical lines
+/// at the time they were logged.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::join(|| 1, || 2);
+///     rayon_logs::mark("phase 2");
+///     rayon_logs::join(|| 3, || 4)
+/// });
+/// assert_eq!(log.marks().len(), 1);
+/// assert_eq!(log.marks()[0].1, "phase 2");
+/// let last_end = log.tasks().iter().map(|t| t.end_time).max().unwrap();
+/// assert!(log.marks()[0].0 <= last_end);
+/// ```
+pub fn mark(label: &'static str) {
+    log(RayonEvent::Mark(Cow::Borrowed(label), now()))
+}
+
+/// Declare a dependency between two tasks which is not a fork or a join
+/// (when communicating through channels for example).
+///
+/// Obtain it with `dependency_handle` in the producing task and call `arrived` in the consuming
+/// task: the dependency is then displayed as an extra edge between these two tasks.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+/// use std::sync::mpsc::channel;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (received, log) = pool.logging_install(|| {
+///     let (sender, receiver) = channel();
+///     rayon_logs::join(
+///         move || sender.send((1, rayon_logs::dependency_handle())).unwrap(),
+///         move || {
+///             let (value, handle) = receiver.recv().unwrap();
+///             handle.arrived();
+///             value
+///         },
+///     )
+///     .1
+/// });
+/// assert_eq!(received, 1);
+/// assert_eq!(log.dependencies().len(), 1);
+/// let (producer, consumer) = log.dependencies()[0];
+/// assert_ne!(producer, consumer);
+/// ```
+pub struct DependencyHandle {
+    id: usize,
+}
+
+impl DependencyHandle {
+    /// The consuming task is now depending on the task which created the handle.
+    pub fn arrived(self) {
+        log(RayonEvent::DependencyEnd(self.id))
     }
 }
 
+/// Create a `DependencyHandle` starting from the current task.
+pub fn dependency_handle() -> DependencyHandle {
+    let id = IDS.with(|ids| ids.borrow().dependencies.fetch_add(1, Ordering::Relaxed));
+    log(RayonEvent::DependencyStart(id));
+    DependencyHandle { id }
+}
+
 /// We tag all the tasks that op makes as one subgraph.
 ///
 /// `work_type` is a str tag and `work_amount` an integer specifying the expected algorithmic cost
@@ -137,22 +534,202 @@ pub fn subgraph_hardware_event<OP, R>(tag: &'static str, event: HardwareEventTyp
 where
     OP: FnOnce() -> R,
 {
-    custom_subgraph(
+    subgraph_hardware_event_measured(tag, event, op).0
+}
+
+/// Like `subgraph_hardware_event` but also return the counted value.
+#[cfg(feature = "perf")]
+pub fn subgraph_hardware_event_measured<OP, R>(
+    tag: &'static str,
+    event: HardwareEventType,
+    op: OP,
+) -> (R, usize)
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph_measured(
         tag,
-        || {
-            let pc: PerfCounter = PerfCounterBuilderLinux::from_hardware_event(event)
+        || start_counter(&mut PerfCounterBuilderLinux::from_hardware_event(event)),
+        stop_counter,
+        op,
+    )
+}
+
+/// Warn (only once) that performance counters do not work here.
+#[cfg(feature = "perf")]
+fn perf_failure(error: io::Error) {
+    static WARNING: Once = Once::new();
+    WARNING.call_once(|| {
+        eprintln!(
+            "rayon_logs: performance counters unavailable ({}), counting 0 instead",
+            error
+        )
+    });
+}
+
+/// Create and start a counter (none if counters are unavailable).
+#[cfg(feature = "perf")]
+fn start_counter(builder: &mut PerfCounterBuilderLinux) -> Option<PerfCounter> {
+    builder
+        .exclude_idle()
+        .exclude_kernel()
+        .finish()
+        .and_then(|pc| pc.start().map(|_| pc))
+        .map_err(perf_failure)
+        .ok()
+}
+
+/// Stop given counter and return the counted value (0 if counters are unavailable).
+#[cfg(feature = "perf")]
+fn stop_counter(pc: Option<PerfCounter>) -> usize {
+    pc.map_or(0, |mut pc| {
+        pc.stop()
+            .and_then(|_| pc.read())
+            .and_then(|counted_value| pc.reset().map(|_| counted_value as usize))
+            .map_err(perf_failure)
+            .unwrap_or(0)
+    })
+}
+
+/// Can we use performance counters (see `subgraph_hardware_event`).
+///
+/// When they are unavailable (no `perf` feature, `perf_event_paranoid` too high,
+/// containers...) all perf subgraphs still run but count 0 and a warning is displayed once.
+///
+/// # Example
+///
+/// ```
+/// if rayon_logs::perf_available() {
+///     println!("speeds will be computed with hardware counters");
+/// } else {
+///     println!("no hardware counters, perf subgraphs will count 0");
+/// }
+/// ```
+pub fn perf_available() -> bool {
+    #[cfg(feature = "perf")]
+    {
+        static AVAILABLE: OnceLock<bool> = OnceLock::new();
+        *AVAILABLE.get_or_init(|| {
+            PerfCounterBuilderLinux::from_hardware_event(HardwareEventType::CPUCycles)
                 .exclude_idle()
                 .exclude_kernel()
                 .finish()
-                .expect("Could not create counter");
-            pc.start().expect("Can not start the counter");
-            pc
+                .is_ok()
+        })
+    }
+    #[cfg(not(feature = "perf"))]
+    {
+        false
+    }
+}
+
+/// Like `subgraph` but also count given hardware event while running.
+///
+/// The work amount is used for speeds as usual and the counted value is recorded
+/// as a measure with given label: it appears in `RunLog::stats` under
+/// the pseudo-tag "tag/label" (and the work amount under "tag/work").
+///
+/// ```no_run
+/// use rayon_logs::{subgraph_perf, HardwareEventType, ThreadPoolBuilder};
+///
+/// let v: Vec<u32> = (0..1_000_000).collect();
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (max, log) = pool.logging_install(|| {
+///     subgraph_perf("max", v.len(), HardwareEventType::CacheMisses, "Cache Misses", || {
+///         v.iter().max().cloned()
+///     })
+/// });
+/// assert_eq!(max, Some(999_999));
+/// let stats = log.stats();
+/// assert_eq!(stats["max"].0, v.len());
+/// assert!(stats.contains_key("max/Cache Misses"));
+/// ```
+#[cfg(feature = "perf")]
+pub fn subgraph_perf<OP, R>(
+    tag: &'static str,
+    work_amount: usize,
+    event: HardwareEventType,
+    label: &'static str,
+    op: OP,
+) -> R
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph_multi(
+        tag,
+        || start_counter(&mut PerfCounterBuilderLinux::from_hardware_event(event)),
+        |pc| vec![("work", work_amount), (label, stop_counter(pc))],
+        op,
+    )
+}
+
+/// Name of given hardware event (as in `HardwareEventType`).
+#[cfg(feature = "perf")]
+fn hardware_event_name(event: HardwareEventType) -> &'static str {
+    match event {
+        HardwareEventType::CPUCycles => "CPUCycles",
+        HardwareEventType::Instructions => "Instructions",
+        HardwareEventType::CacheReferences => "CacheReferences",
+        HardwareEventType::CacheMisses => "CacheMisses",
+        HardwareEventType::BranchInstructions => "BranchInstructions",
+        HardwareEventType::BranchMisses => "BranchMisses",
+        HardwareEventType::BusCycles => "BusCycles",
+        HardwareEventType::StalledCyclesFrontend => "StalledCyclesFrontend",
+        HardwareEventType::StalledCyclesBackend => "StalledCyclesBackend",
+        HardwareEventType::RefCPUCycles => "RefCPUCycles",
+    }
+}
+
+/// Like `subgraph_hardware_event` but count several hardware events during the same run
+/// (all counters are set up before starting the subgraph, so nothing gets nested).
+///
+/// The first event is the work amount used for speeds. Each counted value is recorded
+/// as a measure labelled with the event's name: it appears in `RunLog::stats` under
+/// a pseudo-tag like "tag/Instructions".
+///
+/// ```no_run
+/// use rayon_logs::{subgraph_hardware_events, HardwareEventType, ThreadPoolBuilder};
+///
+/// let v: Vec<u32> = (0..1_000_000).collect();
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let events = [HardwareEventType::Instructions, HardwareEventType::CPUCycles];
+/// let (max, log) = pool.logging_install(|| {
+///     subgraph_hardware_events("max", &events, || v.iter().max().cloned())
+/// });
+/// assert_eq!(max, Some(999_999));
+/// let stats = log.stats();
+/// let ipc = stats["max/Instructions"].0 as f64 / stats["max/CPUCycles"].0 as f64;
+/// println!("instructions per cycle: {}", ipc);
+/// ```
+#[cfg(feature = "perf")]
+pub fn subgraph_hardware_events<OP, R>(tag: &'static str, events: &[HardwareEventType], op: OP) -> R
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph_multi(
+        tag,
+        || {
+            events
+                .iter()
+                .map(|&event| {
+                    (
+                        hardware_event_name(event),
+                        start_counter(&mut PerfCounterBuilderLinux::from_hardware_event(event)),
+                    )
+                })
+                .collect::<Vec<_>>()
         },
-        |mut pc| {
-            pc.stop().expect("Can not stop the counter");
-            let counted_value = pc.read().unwrap() as usize;
-            pc.reset().expect("Can not reset the counter");
-            counted_value
+        |counters| {
+            counters
+                .into_iter()
+                .map(|(name, pc)| (name, stop_counter(pc)))
+                .collect()
         },
         op,
     )
@@ -193,24 +770,23 @@ pub fn subgraph_software_event<OP, R>(tag: &'static str, event: SoftwareEventTyp
 where
     OP: FnOnce() -> R,
 {
-    //TODO: avoid code duplication by abstracting over events
-    custom_subgraph(
+    subgraph_software_event_measured(tag, event, op).0
+}
+
+/// Like `subgraph_software_event` but also return the counted value.
+#[cfg(feature = "perf")]
+pub fn subgraph_software_event_measured<OP, R>(
+    tag: &'static str,
+    event: SoftwareEventType,
+    op: OP,
+) -> (R, usize)
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph_measured(
         tag,
-        || {
-            let pc: PerfCounter = PerfCounterBuilderLinux::from_software_event(event)
-                .exclude_idle()
-                .exclude_kernel()
-                .finish()
-                .expect("Could not create counter");
-            pc.start().expect("Can not start the counter");
-            pc
-        },
-        |mut pc| {
-            pc.stop().expect("Can not stop the counter");
-            let counted_value = pc.read().unwrap() as usize;
-            pc.reset().expect("Can not reset the counter");
-            counted_value
-        },
+        || start_counter(&mut PerfCounterBuilderLinux::from_software_event(event)),
+        stop_counter,
         op,
     )
 }
@@ -264,75 +840,269 @@ pub fn subgraph_cache_event<OP, R>(
 where
     OP: FnOnce() -> R,
 {
-    //TODO: avoid code duplication by abstracting over events
-    custom_subgraph(
+    subgraph_cache_event_measured(tag, cache_id, cache_op_id, cache_op_result_id, op).0
+}
+
+/// Like `subgraph_cache_event` but also return the counted value.
+#[cfg(feature = "perf")]
+pub fn subgraph_cache_event_measured<OP, R>(
+    tag: &'static str,
+    cache_id: CacheId,
+    cache_op_id: CacheOpId,
+    cache_op_result_id: CacheOpResultId,
+    op: OP,
+) -> (R, usize)
+where
+    OP: FnOnce() -> R,
+{
+    custom_subgraph_measured(
         tag,
         || {
-            let pc: PerfCounter = PerfCounterBuilderLinux::from_cache_event(
+            start_counter(&mut PerfCounterBuilderLinux::from_cache_event(
                 cache_id,
                 cache_op_id,
                 cache_op_result_id,
-            )
-            .exclude_idle()
-            .exclude_kernel()
-            .finish()
-            .expect("Could not create counter");
-            pc.start().expect("Can not start the counter");
-            pc
-        },
-        |mut pc| {
-            pc.stop().expect("Can not stop the counter");
-            let counted_value = pc.read().unwrap() as usize;
-            pc.reset().expect("Can not reset the counter");
-            counted_value
+            ))
         },
+        stop_counter,
         op,
     )
 }
 
+/// Like `subgraph` but the tag can be built at runtime.
+///
+/// Static tags are not allocated, so `subgraph_owned("tag", ...)` costs the same as
+/// `subgraph("tag", ...)`.
+///
+/// Example:
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{join, subgraph_owned, ThreadPoolBuilder};
+///
+/// let v: Vec<u32> = (0..1_000).collect();
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (sums, log) = pool.logging_install(|| {
+///     let (left, right) = v.split_at(500);
+///     join(
+///         || subgraph_owned(format!("chunk_{}", 0), left.len(), || left.iter().sum::<u32>()),
+///         || subgraph_owned(format!("chunk_{}", 1), right.len(), || right.iter().sum::<u32>()),
+///     )
+/// });
+/// assert_eq!(sums.0 + sums.1, v.iter().sum::<u32>());
+/// assert!(log.tags().contains(&"chunk_0".to_string()));
+/// assert!(log.tags().contains(&"chunk_1".to_string()));
+/// ```
+pub fn subgraph_owned<T, OP, R>(tag: T, work_amount: usize, op: OP) -> R
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+{
+    custom_subgraph(tag, || (), |_| work_amount, op)
+}
+
 /// Tag a subgraph with a custom value.
 /// The start function will be called just before running the graph and produce an S.
 /// The end function will be called just after running the graph on this S and produce a usize
 /// which will the be stored for display.
-pub fn custom_subgraph<OP, R, START, END, S>(tag: &'static str, start: START, end: END, op: OP) -> R
+pub fn custom_subgraph<T, OP, R, START, END, S>(tag: T, start: START, end: END, op: OP) -> R
 where
+    T: Into<Cow<'static, str>>,
     OP: FnOnce() -> R,
     START: FnOnce() -> S,
     END: FnOnce(S) -> usize,
 {
-    let s = start();
-    start_subgraph(tag);
-    let r = op();
-    let measured_value = end(s);
-    end_subgraph(tag, measured_value);
-    r
+    if !sampled() {
+        return op();
+    }
+    log_custom_subgraph(tag.into(), start, end, op).0
 }
 
-/// Stop current task (virtually) and start a subgraph.
-/// You most likely don't need to call this function directly but `subgraph` instead.
-pub fn start_subgraph(tag: &'static str) {
+/// Like `custom_subgraph` but also return the measured value to the caller.
+///
+/// The value is measured even when the subgraph is not recorded (see `sample_rate`).
+///
+/// Example:
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{custom_subgraph_measured, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let ((sum, measured), log) = pool.logging_install(|| {
+///     custom_subgraph_measured("sum", || 10, |s| s + 32, || (0..100u32).sum::<u32>())
+/// });
+/// assert_eq!(sum, 4950);
+/// assert_eq!(measured, 42);
+/// assert_eq!(log.subgraphs()[0].3, 42);
+/// ```
+pub fn custom_subgraph_measured<T, OP, R, START, END, S>(
+    tag: T,
+    start: START,
+    end: END,
+    op: OP,
+) -> (R, usize)
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+    START: FnOnce() -> S,
+    END: FnOnce(S) -> usize,
+{
+    if !sampled() {
+        let s = start();
+        let r = op();
+        return (r, end(s));
+    }
+    log_custom_subgraph(tag.into(), start, end, op)
+}
+
+/// Like `custom_subgraph` but the end function produces several labelled measures.
+///
+/// The first measure is the work amount used for computing speeds.
+/// All measures are displayed when hovering on tasks and appear in `RunLog::stats`
+/// as pseudo-tags named after the tag and the label (like `merge/bytes`).
+///
+/// Example:
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{custom_subgraph_multi, ThreadPoolBuilder};
+///
+/// let v: Vec<u64> = (0..1_000).collect();
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (sum, log) = pool.logging_install(|| {
+///     custom_subgraph_multi(
+///         "sum",
+///         || v.len(),
+///         |len| vec![("elements", len), ("bytes", len * std::mem::size_of::<u64>())],
+///         || v.iter().sum::<u64>(),
+///     )
+/// });
+/// assert_eq!(sum, 999 * 1000 / 2);
+/// let stats = log.stats();
+/// assert_eq!(stats["sum"].0, 1_000);
+/// assert_eq!(stats["sum/elements"].0, 1_000);
+/// assert_eq!(stats["sum/bytes"].0, 8_000);
+/// ```
+pub fn custom_subgraph_multi<T, OP, R, START, END, S>(tag: T, start: START, end: END, op: OP) -> R
+where
+    T: Into<Cow<'static, str>>,
+    OP: FnOnce() -> R,
+    START: FnOnce() -> S,
+    END: FnOnce(S) -> Vec<(&'static str, usize)>,
+{
+    if !sampled() {
+        return op();
+    }
+    let tag = tag.into();
+    let s = start();
+    start_subgraph(tag.clone());
+    let r = op();
+    let measures: Vec<_> = end(s)
+        .into_iter()
+        .map(|(label, value)| (Cow::Borrowed(label), value))
+        .collect();
+    let work_amount = measures.first().map(|m| m.1).unwrap_or(0);
+    log_subgraph_end(tag, work_amount, measures);
+    r
+}
+
+/// Run op as a subgraph, returning its result and the measured value.
+fn log_custom_subgraph<OP, R, START, END, S>(
+    tag: Cow<'static, str>,
+    start: START,
+    end: END,
+    op: OP,
+) -> (R, usize)
+where
+    OP: FnOnce() -> R,
+    START: FnOnce() -> S,
+    END: FnOnce(S) -> usize,
+{
+    let s = start();
+    start_subgraph(tag.clone());
+    let r = op();
+    let measured_value = end(s);
+    end_subgraph(tag, measured_value);
+    (r, measured_value)
+}
+
+/// Stop current task (virtually) and start a subgraph.
+/// You most likely don't need to call this function directly but `subgraph` instead.
+///
+/// The subgraph can end on another thread, for example after a join whose second task
+/// (starting the subgraph) was stolen.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{end_subgraph, join, start_subgraph, ThreadPoolBuilder};
+/// use std::thread::sleep;
+/// use std::time::Duration;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| {
+///     // the second task is stolen while the first one sleeps
+///     join(|| sleep(Duration::from_millis(50)), || start_subgraph("stolen"));
+///     end_subgraph("stolen", 1);
+/// });
+/// assert_eq!(log.subgraphs().len(), 1);
+/// let (start, end, _, work) = log.subgraphs()[0];
+/// assert_eq!(work, 1);
+/// assert_ne!(log.tasks()[start].thread_id, log.tasks()[end].thread_id);
+/// assert!(log.stats().contains_key("stolen"));
+/// ```
+pub fn start_subgraph<T: Into<Cow<'static, str>>>(tag: T) {
+    let tag = tag.into();
     let subgraph_start_task_id = next_task_id();
     logs!(
+        time =>
         // log child's work and dependencies.
         RayonEvent::Child(subgraph_start_task_id),
         // end current task
-        RayonEvent::TaskEnd(now()),
+        RayonEvent::TaskEnd(time),
         // execute full sequential task
-        RayonEvent::TaskStart(subgraph_start_task_id, now()),
+        RayonEvent::TaskStart(subgraph_start_task_id, time),
         RayonEvent::SubgraphStart(tag)
     );
 }
 
 /// Stop current task (virtually) and end a subgraph.
 /// You most likely don't need to call this function directly but `subgraph` instead.
-pub fn end_subgraph(tag: &'static str, measured_value: usize) {
+pub fn end_subgraph<T: Into<Cow<'static, str>>>(tag: T, measured_value: usize) {
+    log_subgraph_end(tag.into(), measured_value, Vec::new())
+}
+
+/// Stop current task (virtually) and end a subgraph, recording several measures.
+fn log_subgraph_end(
+    tag: Cow<'static, str>,
+    measured_value: usize,
+    measures: Vec<(Cow<'static, str>, usize)>,
+) {
+    for (label, value) in measures {
+        logs!(RayonEvent::SubgraphMeasure(label, value));
+    }
     let continuation_task_id = next_task_id();
     logs!(
+        time =>
         RayonEvent::SubgraphEnd(tag, measured_value),
         RayonEvent::Child(continuation_task_id),
-        RayonEvent::TaskEnd(now()),
+        RayonEvent::TaskEnd(time),
         // start continuation task
-        RayonEvent::TaskStart(continuation_task_id, now())
+        RayonEvent::TaskStart(continuation_task_id, time)
     );
 }
 
@@ -343,6 +1113,29 @@ pub fn end_subgraph(tag: &'static str, measured_value: usize) {
 /// the second job is stolen by a different thread, or if
 /// `join_context` was called from outside the thread pool to begin
 /// with.
+///
+/// Stolen tasks are marked as `migrated` in the logs.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let stolen = AtomicBool::new(false);
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::join_context(
+///         |_| while !stolen.load(Ordering::SeqCst) {}, // wait for the other thread
+///         |context| stolen.store(context.migrated(), Ordering::SeqCst),
+///     )
+/// });
+/// assert_eq!(log.tasks().iter().filter(|t| t.migrated).count(), 1);
+/// ```
 pub fn join_context<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
 where
     A: FnOnce(FnContext) -> RA + Send,
@@ -350,21 +1143,24 @@ where
     RA: Send,
     RB: Send,
 {
+    if !sampled() {
+        return unsampled_join_context(oper_a, oper_b);
+    }
     let id_c = next_task_id();
     let id_a = next_task_id();
-    let ca = |c| {
-        log(RayonEvent::TaskStart(id_a, now()));
-        let result = oper_a(c);
-        logs!(RayonEvent::Child(id_c), RayonEvent::TaskEnd(now()));
-        result
+    let ca = |c: FnContext| {
+        logged_task(id_a, id_c, || {
+            log_migration(c.migrated());
+            oper_a(c)
+        })
     };
 
     let id_b = next_task_id();
-    let cb = |c| {
-        log(RayonEvent::TaskStart(id_b, now()));
-        let result = oper_b(c);
-        logs!(RayonEvent::Child(id_c), RayonEvent::TaskEnd(now()));
-        result
+    let cb = |c: FnContext| {
+        logged_task(id_b, id_c, || {
+            log_migration(c.migrated());
+            oper_b(c)
+        })
     };
 
     logs!(
@@ -372,9 +1168,7 @@ where
         RayonEvent::Child(id_b),
         RayonEvent::TaskEnd(now())
     );
-    let r = rayon::join_context(ca, cb);
-    log(RayonEvent::TaskStart(id_c, now()));
-    r
+    then_continue(id_c, || rayon::join_context(ca, cb))
 }
 
 /// Takes two closures and *potentially* runs them in parallel. It
@@ -457,6 +1251,26 @@ where
 /// closure, that panic will be propagated and hence `join()` will
 /// panic with the same panic value. If both closures panic, `join()`
 /// will panic with the panic value from the first closure.
+///
+/// Logs stay well formed: the panicking task is ended and marked as panicked
+/// (and outlined in red in svg files).
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+/// use std::panic::catch_unwind;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (caught, log) = pool.logging_install(|| {
+///     catch_unwind(|| rayon_logs::join(|| 1, || panic!("oops"))).is_err()
+/// });
+/// assert!(caught);
+/// assert_eq!(log.tasks().iter().filter(|t| t.panicked).count(), 1);
+/// assert!(log.tasks().iter().all(|t| t.end_time >= t.start_time));
+/// ```
 pub fn join<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
 where
     A: FnOnce() -> RA + Send,
@@ -464,21 +1278,24 @@ where
     RA: Send,
     RB: Send,
 {
+    if !sampled() {
+        return unsampled_join_context(|_| oper_a(), |_| oper_b());
+    }
     let id_c = next_task_id();
     let id_a = next_task_id();
-    let ca = || {
-        log(RayonEvent::TaskStart(id_a, now()));
-        let result = oper_a();
-        logs!(RayonEvent::Child(id_c), RayonEvent::TaskEnd(now()));
-        result
+    let ca = |c: FnContext| {
+        logged_task(id_a, id_c, || {
+            log_migration(c.migrated());
+            oper_a()
+        })
     };
 
     let id_b = next_task_id();
-    let cb = || {
-        log(RayonEvent::TaskStart(id_b, now()));
-        let result = oper_b();
-        logs!(RayonEvent::Child(id_c), RayonEvent::TaskEnd(now()));
-        result
+    let cb = |c: FnContext| {
+        logged_task(id_b, id_c, || {
+            log_migration(c.migrated());
+            oper_b()
+        })
     };
 
     logs!(
@@ -486,56 +1303,701 @@ where
         RayonEvent::Child(id_b),
         RayonEvent::TaskEnd(now())
     );
-    let r = rayon::join(ca, cb);
-    log(RayonEvent::TaskStart(id_c, now()));
-    r
+    then_continue(id_c, || rayon::join_context(ca, cb))
+}
+
+/// Mark the active task as stolen if rayon tells us so.
+fn log_migration(migrated: bool) {
+    if migrated {
+        log(RayonEvent::TaskMigrated)
+    }
+}
+
+/// Plain `rayon::join_context` for sampled-out joins: everything runs in the calling task
+/// except a stolen second closure which has no task to run in and is not recorded.
+fn unsampled_join_context<A, B, RA, RB>(oper_a: A, oper_b: B) -> (RA, RB)
+where
+    A: FnOnce(FnContext) -> RA + Send,
+    B: FnOnce(FnContext) -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    rayon::join_context(oper_a, |c| {
+        if c.migrated() {
+            sampled_out(|| oper_b(c))
+        } else {
+            oper_b(c)
+        }
+    })
+}
+
+/// Logged three-way join: execute the three closures potentially in parallel.
+///
+/// Internally this is two nested `rayon::join` but the logs only show one task forking into
+/// three children, all of them joining into one continuation task.
+/// Like `join`, it counts as one join for sampling (see `sample_rate`) and stolen children
+/// are marked as `migrated`.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (r, log) = pool.logging_install(|| rayon_logs::join3(|| 1, || 2, || 3));
+/// assert_eq!(r, (1, 2, 3));
+/// assert_eq!(log.tasks().len(), 1 + 3 + 1);
+///
+/// // the last two closures are stolen together while the first one waits for them
+/// let done = AtomicBool::new(false);
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::join3(
+///         || while !done.load(Ordering::SeqCst) {},
+///         || (),
+///         || done.store(true, Ordering::SeqCst),
+///     )
+/// });
+/// assert_eq!(log.tasks().iter().filter(|t| t.migrated).count(), 2);
+///
+/// // only one join3 out of 2 is recorded
+/// rayon_logs::sample_rate(2);
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::join3(|| (), || (), || ());
+///     rayon_logs::join3(|| (), || (), || ());
+/// });
+/// rayon_logs::sample_rate(1);
+/// assert_eq!(log.tasks().len(), 1 + 3 + 1);
+/// ```
+pub fn join3<A, B, C, RA, RB, RC>(oper_a: A, oper_b: B, oper_c: C) -> (RA, RB, RC)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    C: FnOnce() -> RC + Send,
+    RA: Send,
+    RB: Send,
+    RC: Send,
+{
+    if !sampled() {
+        let (ra, (rb, rc)) = unsampled_join_context(
+            |_| oper_a(),
+            |_| unsampled_join_context(|_| oper_b(), |_| oper_c()),
+        );
+        return (ra, rb, rc);
+    }
+    let id_c = next_task_id();
+    let ids = [next_task_id(), next_task_id(), next_task_id()];
+    fork(&ids);
+    let (ra, (rb, rc)) = then_continue(id_c, || {
+        rayon::join_context(
+            |c| {
+                logged_task(ids[0], id_c, || {
+                    log_migration(c.migrated());
+                    oper_a()
+                })
+            },
+            |c| join_children([ids[1], ids[2]], id_c, c.migrated(), oper_b, oper_c),
+        )
+    });
+    (ra, rb, rc)
+}
+
+/// Logged four-way join: execute the four closures potentially in parallel.
+///
+/// Internally this is nested `rayon::join` calls but the logs only show one task forking into
+/// four children, all of them joining into one continuation task.
+/// See `join3`.
+pub fn join4<A, B, C, D, RA, RB, RC, RD>(
+    oper_a: A,
+    oper_b: B,
+    oper_c: C,
+    oper_d: D,
+) -> (RA, RB, RC, RD)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    C: FnOnce() -> RC + Send,
+    D: FnOnce() -> RD + Send,
+    RA: Send,
+    RB: Send,
+    RC: Send,
+    RD: Send,
+{
+    if !sampled() {
+        let ((ra, rb), (rc, rd)) = unsampled_join_context(
+            |_| unsampled_join_context(|_| oper_a(), |_| oper_b()),
+            |_| unsampled_join_context(|_| oper_c(), |_| oper_d()),
+        );
+        return (ra, rb, rc, rd);
+    }
+    let id_c = next_task_id();
+    let ids = [
+        next_task_id(),
+        next_task_id(),
+        next_task_id(),
+        next_task_id(),
+    ];
+    fork(&ids);
+    let ((ra, rb), (rc, rd)) = then_continue(id_c, || {
+        rayon::join_context(
+            |c| join_children([ids[0], ids[1]], id_c, c.migrated(), oper_a, oper_b),
+            |c| join_children([ids[2], ids[3]], id_c, c.migrated(), oper_c, oper_d),
+        )
+    });
+    (ra, rb, rc, rd)
+}
+
+/// Run two children of a n-way fork (see `join3`) potentially in parallel, as tasks `ids`
+/// followed by task `continuation_id`.
+/// `migrated` tells if we are already running on another thread than the forking one.
+fn join_children<A, B, RA, RB>(
+    ids: [TaskId; 2],
+    continuation_id: TaskId,
+    migrated: bool,
+    oper_a: A,
+    oper_b: B,
+) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    rayon::join_context(
+        |c| {
+            logged_task(ids[0], continuation_id, || {
+                log_migration(migrated || c.migrated());
+                oper_a()
+            })
+        },
+        |c| {
+            logged_task(ids[1], continuation_id, || {
+                log_migration(migrated || c.migrated());
+                oper_b()
+            })
+        },
+    )
+}
+
+/// End active task, forking into tasks with given ids.
+fn fork(children: &[TaskId]) {
+    for child in children {
+        log(RayonEvent::Child(*child));
+    }
+    log(RayonEvent::TaskEnd(now()));
+}
+
+/// Run given closure as task `id`, followed by task `continuation_id`.
+/// If the closure panics the task is still ended (and marked as panicked) before unwinding.
+pub(crate) fn logged_task<R>(id: TaskId, continuation_id: TaskId, oper: impl FnOnce() -> R) -> R {
+    log(RayonEvent::TaskStart(id, now()));
+    let result = end_task_on_panic(oper);
+    logs!(
+        RayonEvent::Child(continuation_id),
+        RayonEvent::TaskEnd(now())
+    );
+    result.unwrap_or_else(|payload| resume_unwind(payload))
+}
+
+/// Run given closure in the active task, marking the task as panicked if it panics.
+/// The panic is caught so that the caller can log the task's closing events before
+/// resuming it.
+pub(crate) fn end_task_on_panic<R>(oper: impl FnOnce() -> R) -> std::thread::Result<R> {
+    let result = catch_unwind(AssertUnwindSafe(oper));
+    if result.is_err() {
+        log(RayonEvent::TaskPanicked);
+    }
+    result
+}
+
+/// Run given forking closure and then start the continuation task,
+/// even if the closure panicked (panic is then resumed).
+pub(crate) fn then_continue<R>(continuation_id: TaskId, forking: impl FnOnce() -> R) -> R {
+    let result = catch_unwind(AssertUnwindSafe(forking));
+    log(RayonEvent::TaskStart(continuation_id, now()));
+    result.unwrap_or_else(|payload| resume_unwind(payload))
+}
+
+// logged pool used instead of rayon's global pool (see `logging_install`)
+static GLOBAL_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// Logged equivalent of running on rayon's global pool: execute given closure in a logged
+/// pool of the default size (`RAYON_NUM_THREADS` or the number of cpus), built on first use
+/// and shared by all later calls.
+/// Return the closure's result together with the `RunLog` (see `ThreadPool::logging_install`).
+///
+/// Rayon's global pool cannot be logged since we need to set up each of its threads when
+/// it is built.
+/// Parallel iterators and joins used outside of this function still run on rayon's global
+/// pool, unlogged.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::prelude::*;
+///
+/// let v: Vec<u64> = (0..1_000).collect();
+/// let (sum, log) = rayon_logs::logging_install(|| v.par_iter().sum::<u64>());
+/// assert_eq!(sum, 999 * 1_000 / 2);
+/// assert_eq!(log.threads_number(), rayon::current_num_threads());
+/// assert!(log.tasks().len() > 1);
+/// // the pool is reused
+/// let (_, log) = rayon_logs::logging_install(|| rayon_logs::join(|| 1, || 2));
+/// assert_eq!(log.threads_number(), rayon::current_num_threads());
+/// ```
+pub fn logging_install<OP, R>(op: OP) -> (R, RunLog)
+where
+    OP: FnOnce() -> R + Send,
+    R: Send,
+{
+    GLOBAL_POOL
+        .get_or_init(|| {
+            crate::ThreadPoolBuilder::new()
+                .save_logs(None)
+                .build()
+                .expect("building global logged pool failed")
+        })
+        .logging_install(op)
+}
+
+/// Logged version of `rayon::spawn`: puts given task in the global (or current) pool's queue
+/// and returns immediately.
+///
+/// The spawning task is split in two: the spawned task and the continuation of the spawning
+/// task are both children of the part executed before the spawn.
+/// Since nothing waits for the spawned task, it is a leaf of the graph: there is no task
+/// which could follow it. Synchronizations with it (like a channel) are invisible to us,
+/// use `dependency_handle` to display them or `scope` whose end is a continuation task
+/// waiting for all its spawned tasks.
+///
+/// Caveat: logs are post-processed as soon as `logging_install` returns, so a spawned task
+/// only appears in the graph if it finished before. You need to synchronize with it yourself
+/// (with a channel for example), or use `scope` which waits for all its spawned tasks.
+///
+/// Must be called from a logged computation (inside a logged pool's install): elsewhere
+/// the task would run in rayon's global pool and its events would be lost.
+/// This is checked in debug builds.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+/// use std::sync::mpsc::channel;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let (sum, log) = pool.logging_install(|| {
+///     let (sender, receiver) = channel();
+///     for i in 0..4u32 {
+///         let sender = sender.clone();
+///         rayon_logs::spawn(move || sender.send(i).expect("sending failed"));
+///     }
+///     receiver.iter().take(4).sum::<u32>()
+/// });
+/// assert_eq!(sum, 6);
+/// assert_eq!(log.tasks().len(), 1 + 2 * 4);
+/// ```
+pub fn spawn<OP>(op: OP)
+where
+    OP: FnOnce() + Send + 'static,
+{
+    if in_sampled_out_region() {
+        return rayon::spawn(move || sampled_out(op));
+    }
+    debug_assert!(
+        !recording() || current_task_id().is_some(),
+        "logged spawn outside of a logged computation"
+    );
+    let spawned_id = next_task_id();
+    let seq_id = next_task_id();
+    logs!(RayonEvent::Child(spawned_id), RayonEvent::Child(seq_id));
+    rayon::spawn(move || {
+        log(RayonEvent::TaskStart(spawned_id, now()));
+        let result = end_task_on_panic(op);
+        log(RayonEvent::TaskEnd(now()));
+        result.unwrap_or_else(|payload| resume_unwind(payload))
+    });
+    logs!(
+        time =>
+        RayonEvent::TaskEnd(time),
+        RayonEvent::TaskStart(seq_id, time)
+    );
+}
+
+/// Logged version of `rayon::broadcast`: executes `op` within every thread of the current pool
+/// and returns all results once each thread is done.
+///
+/// The calling task forks into one task per thread, all of them joining into a common
+/// continuation task (barrier).
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(4)
+///     .build()
+///     .expect("building pool failed");
+/// let (indices, log) = pool.logging_install(|| rayon_logs::broadcast(|context| context.index()));
+/// assert_eq!(indices, vec![0, 1, 2, 3]);
+/// // initial task, 4 broadcast tasks and the continuation
+/// assert_eq!(log.tasks().len(), 1 + 4 + 1);
+/// ```
+pub fn broadcast<OP, R>(op: OP) -> Vec<R>
+where
+    OP: Fn(rayon::BroadcastContext<'_>) -> R + Sync,
+    R: Send,
+{
+    if in_sampled_out_region() {
+        return rayon::broadcast(|context| sampled_out(|| op(context)));
+    }
+    let id_c = next_task_id();
+    let ids: Vec<TaskId> = (0..rayon::current_num_threads())
+        .map(|_| next_task_id())
+        .collect();
+    fork(&ids);
+    then_continue(id_c, || {
+        rayon::broadcast(|context| logged_task(ids[context.index()], id_c, || op(context)))
+    })
 }
 
 // small global counter to increment file names
 static INSTALL_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 /// We wrap rayon's pool into our own struct to overload the install method.
+///
+/// Each pool has its own logs storage and ids, so different pools can log concurrently.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{join, ThreadPoolBuilder};
+///
+/// fn count(n: usize) -> usize {
+///     if n <= 1 {
+///         1
+///     } else {
+///         let (a, b) = join(|| count(n / 2), || count(n - n / 2));
+///         a + b
+///     }
+/// }
+///
+/// let run = || {
+///     let pool = ThreadPoolBuilder::new()
+///         .num_threads(2)
+///         .build()
+///         .expect("building pool failed");
+///     (0..10)
+///         .map(|_| pool.logging_install(|| count(64)).1.tasks().len())
+///         .collect::<Vec<_>>()
+/// };
+/// let (first, second) = std::thread::scope(|s| {
+///     let first = s.spawn(run);
+///     let second = s.spawn(run);
+///     (first.join().unwrap(), second.join().unwrap())
+/// });
+/// // each join creates 3 tasks and we have 63 of them
+/// assert!(first.iter().chain(second.iter()).all(|&tasks| tasks == 1 + 3 * 63));
+/// ```
+///
+/// Logged installs on the same pool reset its logs, so they are serialized:
+/// concurrent calls wait for each other and each one gets its own logs.
+/// (Calling `logging_install` on a pool from inside one of its own logged installs deadlocks.)
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{join, ThreadPoolBuilder};
+///
+/// fn count(n: usize) -> usize {
+///     if n <= 1 {
+///         1
+///     } else {
+///         let (a, b) = join(|| count(n / 2), || count(n - n / 2));
+///         a + b
+///     }
+/// }
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let run = || {
+///     (0..10)
+///         .map(|_| pool.logging_install(|| count(64)).1)
+///         .collect::<Vec<_>>()
+/// };
+/// let (first, second) = std::thread::scope(|s| {
+///     let first = s.spawn(run);
+///     let second = s.spawn(run);
+///     (first.join().unwrap(), second.join().unwrap())
+/// });
+/// for log in first.iter().chain(second.iter()) {
+///     assert!(log.validate().is_empty());
+///     assert_eq!(log.tasks().len(), 1 + 3 * 63);
+/// }
+/// ```
 pub struct ThreadPool {
     pub(crate) logs: Arc<Mutex<Vec<Arc<Storage<RayonEvent>>>>>,
+    /// information on each thread (same order as logs)
+    pub(crate) threads_info: Arc<Mutex<Vec<ThreadInfo>>>,
+    /// ids generators shared by all threads of the pool
+    pub(crate) ids: Arc<IdsGenerators>,
+    /// when the last logged install started
+    pub(crate) start: AtomicU64,
+    /// clock used by all threads of the pool
+    pub(crate) clock: Clock,
     pub(crate) pool: rayon::ThreadPool,
+    /// where `install` saves logs (see `ThreadPoolBuilder::save_logs`)
+    pub(crate) save_logs: Option<String>,
+    /// held during each logged install since they reset the logs
+    pub(crate) installing: Mutex<()>,
 }
 
 impl ThreadPool {
-    /// Reset all logs and counters to initial condition.
+    /// Reset all logs and counters of this pool to initial condition.
     fn reset(&self) {
-        NEXT_TASK_ID.store(0, Ordering::SeqCst);
-        NEXT_ITERATOR_ID.store(0, Ordering::SeqCst);
-        let logs = &*self.logs.lock().unwrap(); // oh yeah baby
-        for log in logs {
-            log.clear();
+        self.ids.reset();
+        // each worker clears its own storage so that no storage is ever modified by two threads
+        // (workers might still be logging detached tasks from a previous install).
+        // we cannot hold the logs lock here since starting threads need it to register.
+        // we take the lock while clearing so that no one reads the logs at the same time.
+        let logs = &self.logs;
+        self.pool.broadcast(|_| {
+            let _readers = logs.lock().unwrap();
+            LOGS.with(|l| l.borrow().clear())
+        });
+    }
+
+    /// Post-process all events logged so far by the current (or last) logged install,
+    /// without disturbing it.
+    ///
+    /// Tasks still running are marked as `in_progress` and end at the last observed time.
+    /// Subgraphs not finished yet are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (snapshot, log) = pool.logging_install(|| {
+    ///     rayon_logs::join(|| (0..1_000).sum::<u32>(), || 2);
+    ///     pool.snapshot().expect("invalid snapshot")
+    /// });
+    /// // only the task after the join was still running
+    /// assert_eq!(snapshot.tasks().iter().filter(|t| t.in_progress).count(), 1);
+    /// assert!(log.tasks().iter().all(|t| !t.in_progress));
+    /// ```
+    pub fn snapshot(&self) -> Result<RunLog, LogError> {
+        self.raw_logs().run_log()
+    }
+
+    /// Copy all raw events logged so far by the current (or last) logged install,
+    /// without disturbing it.
+    /// Use it for custom analysis (see `RawLogs`).
+    pub fn raw_logs(&self) -> RawLogs {
+        // we copy all events first so that all task ids we see are already allocated
+        let threads = self
+            .logs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|thread_logs| thread_logs.iter().cloned().collect())
+            .collect();
+        RawLogs {
+            threads,
+            start: self.start.load(Ordering::SeqCst),
+            tasks_number: self.ids.tasks.load(Ordering::SeqCst),
+            iterators_number: self.ids.iterators.load(Ordering::SeqCst),
+            sampling_rate: SAMPLE_RATE.load(Ordering::Relaxed),
+            threads_info: self.threads_info.lock().unwrap().clone(),
+            clock: self.clock,
         }
     }
 
+    /// Save an animated svg of a `snapshot` of the logs to given path.
+    ///
+    /// Logs are kept until the next logged install so this also works once it is over.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .save_logs(None)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// pool.install(|| rayon_logs::join(|| 1, || 2));
+    /// let path = std::env::temp_dir().join(format!("snapshot_{}.svg", std::process::id()));
+    /// pool.snapshot_svg(&path).expect("saving svg failed");
+    /// let svg = std::fs::read_to_string(&path).expect("reading svg failed");
+    /// std::fs::remove_file(&path).expect("removing failed");
+    /// assert!(svg.starts_with("<?xml"));
+    /// ```
+    pub fn snapshot_svg<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        self.snapshot().map_err(io::Error::other)?.save_svg(path)
+    }
+
+    /// Number of events currently logged by each thread of the pool
+    /// (threads are in the same order as in the logs).
+    /// Logs are kept until the next logged install.
+    pub fn logged_events(&self) -> Vec<usize> {
+        self.logs.lock().unwrap().iter().map(|l| l.len()).collect()
+    }
+
+    /// Memory (in bytes) currently used by the logs of all threads of the pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let small_blocks = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .log_block_size(10)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let default_blocks = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// for pool in &[&small_blocks, &default_blocks] {
+    ///     pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+    ///     // join logs 3 tasks (start and end each) plus children
+    ///     assert!(pool.logged_events().iter().sum::<usize>() >= 6);
+    /// }
+    /// assert!(small_blocks.logs_memory_footprint() < default_blocks.logs_memory_footprint());
+    /// ```
+    pub fn logs_memory_footprint(&self) -> usize {
+        self.logs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|l| l.memory_footprint())
+            .sum()
+    }
+
     /// Execute given closure in the thread pool, logging it's task as the initial one.
     /// After running, we post-process the logs and return a `RunLog` together with the closure's
     /// result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the logs are invalid (see `try_logging_install`).
     pub fn logging_install<OP, R>(&self, op: OP) -> (R, RunLog)
     where
         OP: FnOnce() -> R + Send,
         R: Send,
     {
+        let (r, log) = self.try_logging_install(op);
+        (r, log.unwrap_or_else(|e| panic!("{}", e)))
+    }
+
+    /// Like `logging_install` but return an error instead of panicking if logs cannot be
+    /// post-processed (which happens when mixing logged and un-logged computations).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// // un-logged broadcast: the other thread joins without any active task
+    /// let (_, log) = pool.try_logging_install(|| rayon::broadcast(|_| rayon_logs::join(|| 1, || 2)));
+    /// let error = log.expect_err("logs should be invalid");
+    /// assert_eq!(error.description, "child with no active task as father");
+    /// ```
+    ///
+    /// Detached tasks spawned by a previous install might still be running when the next one
+    /// starts. Their late events can make the next logs invalid but they never corrupt them.
+    ///
+    /// ```
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(4)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// for _ in 0..200 {
+    ///     let (r, _) = pool.try_logging_install(|| {
+    ///         for _ in 0..4 {
+    ///             rayon_logs::spawn(|| {
+    ///                 rayon_logs::join(|| (0..100).sum::<u32>(), || (0..100).sum::<u32>());
+    ///             })
+    ///         }
+    ///         rayon_logs::join(|| 1, || 2)
+    ///     });
+    ///     assert_eq!(r, (1, 2));
+    /// }
+    /// ```
+    pub fn try_logging_install<OP, R>(&self, op: OP) -> (R, Result<RunLog, LogError>)
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.logged_install(op, |start| self.run_log_since(start))
+    }
+
+    /// Execute given closure in the thread pool, logging it's task as the initial one,
+    /// then call `post_process` with the start time while still preventing other installs.
+    fn logged_install<OP, R, P, T>(&self, op: OP, post_process: P) -> (R, T)
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+        P: FnOnce(TimeStamp) -> T,
+    {
+        // a poisoned lock only means a previous install panicked: logs are reset anyway
+        let _installing = self
+            .installing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
         self.reset();
-        let id = next_task_id();
+        let id = self.ids.tasks.fetch_add(1, Ordering::Relaxed);
         let c = || {
             log(RayonEvent::TaskStart(id, now()));
             let result = op();
             log(RayonEvent::TaskEnd(now()));
             result
         };
-        let start = now();
+        let start = self.clock.now();
+        self.start.store(start, Ordering::SeqCst);
         let r = self.pool.install(c);
-        let log = RunLog::new(
-            NEXT_TASK_ID.load(Ordering::Relaxed),
-            NEXT_ITERATOR_ID.load(Ordering::Relaxed),
-            &*self.logs.lock().unwrap(),
+        (r, post_process(start))
+    }
+
+    /// Post-process all events logged since given start time.
+    fn run_log_since(&self, start: TimeStamp) -> Result<RunLog, LogError> {
+        RunLog::new(
+            self.ids.tasks.load(Ordering::Relaxed),
+            self.ids.iterators.load(Ordering::Relaxed),
+            self.logs.lock().unwrap().iter().map(|l| l.iter()).collect(),
             start,
-        );
-        (r, log)
+            SAMPLE_RATE.load(Ordering::Relaxed),
+            self.threads_info.lock().unwrap().clone(),
+            self.clock,
+        )
     }
 
     /// Creates a scope that executes within this thread-pool.
@@ -562,21 +2024,101 @@ impl ThreadPool {
     }
 
     /// Execute given closure in the thread pool, logging it's task as the initial one.
-    /// After running, we save a json file with filename being an incremental counter.
+    /// After running, we save a json file with filename being an incremental counter
+    /// (see `ThreadPoolBuilder::save_logs`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the logs cannot be post-processed or saved (see `try_install`).
+    /// Like rayon's `install` this returns the closure's result alone, so there is no other way
+    /// to report that the logs we were asked to save are lost.
+    /// Nothing is post-processed nor saved with `ThreadPoolBuilder::save_logs(None)`
+    /// so this never panics then.
     pub fn install<OP, R>(&self, op: OP) -> R
     where
         OP: FnOnce() -> R + Send,
         R: Send,
     {
-        let (r, log) = self.logging_install(op);
-        log.save(format!(
-            "log_{}.json",
-            INSTALL_COUNT.fetch_add(1, Ordering::SeqCst)
-        ))
-        .expect("saving json failed");
+        let (r, saved) = self.try_install(op);
+        if let Err(e) = saved {
+            panic!(
+                "saving json failed ({}), use ThreadPoolBuilder::save_logs(None) to disable saving",
+                e
+            )
+        }
         r
     }
 
+    /// Like `install` but return the result of post-processing and saving the logs instead of
+    /// panicking if it fails (invalid logs are reported as `InvalidData` errors).
+    /// The closure has been executed in all cases so its result is always returned.
+    /// Logs are not post-processed when they are not saved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .save_logs(Some("/non/existing/directory"))
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (result, saved) = pool.try_install(|| rayon_logs::join(|| 1, || 2));
+    /// assert_eq!(result, (1, 2));
+    /// assert!(saved.is_err());
+    /// ```
+    ///
+    /// Invalid logs are reported instead of panicking:
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    /// use std::io::ErrorKind;
+    ///
+    /// let directory = std::env::temp_dir().join(format!("try_install_{}", std::process::id()));
+    /// std::fs::create_dir_all(&directory).expect("creating directory failed");
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .save_logs(directory.to_str())
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// // un-logged broadcast: the other thread joins without any active task
+    /// let (_, saved) = pool.try_install(|| rayon::broadcast(|_| rayon_logs::join(|| 1, || 2)));
+    /// assert_eq!(saved.expect_err("logs are invalid").kind(), ErrorKind::InvalidData);
+    /// // nothing is post-processed when not saving
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .save_logs(None)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, saved) = pool.try_install(|| rayon::broadcast(|_| rayon_logs::join(|| 1, || 2)));
+    /// assert!(saved.is_ok());
+    /// std::fs::remove_dir_all(&directory).expect("removing directory failed");
+    /// ```
+    pub fn try_install<OP, R>(&self, op: OP) -> (R, Result<(), io::Error>)
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        let pattern = match &self.save_logs {
+            Some(pattern) => pattern,
+            None => return self.logged_install(op, |_| Ok(())),
+        };
+        self.logged_install(op, |start| {
+            let log = self
+                .run_log_since(start)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let count = INSTALL_COUNT.fetch_add(1, Ordering::SeqCst).to_string();
+            let path = if pattern.contains("{}") {
+                PathBuf::from(pattern.replace("{}", &count))
+            } else {
+                Path::new(pattern).join(format!("log_{}.json", count))
+            };
+            log.save(path)
+        })
+    }
+
     ///This function simply returns a comparator that allows us to add algorithms for comparison.
     pub fn compare(&self) -> Comparator {
         Comparator::new(self)
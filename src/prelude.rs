@@ -118,4 +118,38 @@ impl<'data, I: rayon::prelude::IntoParallelRefMutIterator<'data>> IntoParallelRe
 {
 }
 
+/// `LogParallelIterator` tags all tasks of a parallel iterator.
+///
+/// This trait is automatically implemented for all parallel iterators.
+pub trait LogParallelIterator: ParallelIterator {
+    /// Tag all tasks of this iterator with given label
+    /// (they appear in the svg's tags and in `RunLog::stats`).
+    ///
+    /// Tasks are logged by the `Logged` iterators it contains (like the ones created
+    /// by this prelude's `par_iter`) so only use it on them.
+    /// Use `Logged::named` to log and tag an iterator coming directly from rayon.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let v: Vec<u32> = (0..10_000).collect();
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (max, log) = pool.logging_install(|| v.par_iter().map(|x| x * 2).log("max").max());
+    /// assert_eq!(max, Some(19_998));
+    /// assert_eq!(log.stats()["max"].0, 10_000);
+    /// ```
+    fn log(self, label: &'static str) -> Logged<Self> {
+        Logged::tagging(self, label)
+    }
+}
+
+impl<I: ParallelIterator> LogParallelIterator for I {}
+
 pub use crate::rayon_algorithms::slice::ParallelSliceMut;
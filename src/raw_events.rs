@@ -2,7 +2,15 @@
 //! Events which are very fast to log and logged on a per thread basis.
 //! These events will be post-processed after execution in order to generate
 //! a tasks graph.
+//! They are also available as `RawLogs` for custom analysis.
+use crate::clock::Clock;
+use crate::log::{LogError, RunLog, ThreadInfo};
+use itertools::Itertools;
 use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
 
 /// unique task identifier
 pub(crate) type TaskId = usize;
@@ -11,38 +19,451 @@ pub(crate) type IteratorId = usize;
 /// at which time (in nanoseconds) does the event happen
 pub(crate) type TimeStamp = u64;
 
-use lazy_static::lazy_static;
-lazy_static! {
-    static ref START_TIME: std::time::Instant = std::time::Instant::now();
-}
-
-/// Return number of nano seconds since start.
-pub(crate) fn now() -> TimeStamp {
-    START_TIME.elapsed().as_nanos() as TimeStamp
-}
+pub(crate) use crate::clock::now;
 
 /// All types of raw events we can log.
-#[derive(Debug, Serialize, Deserialize)]
-pub(crate) enum RayonEvent {
+///
+/// Each thread logs its own sequence of events. At any time a thread has at most one
+/// active task (between its `TaskStart` and `TaskEnd`) and most events apply to it.
+/// Times are in nanoseconds from an arbitrary origin shared by all threads.
+///
+/// Events are kept small since they are logged on the hot path
+/// (no larger than a tag and two integers):
+///
+/// ```
+/// use std::borrow::Cow;
+/// use std::mem::size_of;
+/// assert!(size_of::<rayon_logs::RayonEvent>() <= size_of::<(Cow<'static, str>, u64, u64)>());
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RayonEvent {
     /// A task starts.
     TaskStart(TaskId, TimeStamp),
     /// Active task ends.
     TaskEnd(TimeStamp),
     /// Direct link in the graph between two tasks (active one and given one).
     Child(TaskId),
-    /// Start a subgraph.
-    SubgraphStart(&'static str),
+    /// Start a subgraph (tags are only allocated when built at runtime).
+    SubgraphStart(Cow<'static, str>),
     /// End a subgraph and register a work amount.
-    SubgraphEnd(&'static str, usize),
+    SubgraphEnd(Cow<'static, str>, usize),
+    /// Register a labelled measure of the subgraph about to end (logged before its `SubgraphEnd`).
+    SubgraphMeasure(Cow<'static, str>, usize),
+    /// Active task panicked (it is still ended normally afterwards).
+    TaskPanicked,
+    /// Active task was stolen (rayon reported it as migrated).
+    TaskMigrated,
+    /// A named instant marker.
+    Mark(Cow<'static, str>, TimeStamp),
+    /// Active task produces something a later task will depend on (dependency id).
+    DependencyStart(usize),
+    /// Active task depends on what was produced at given dependency's start.
+    DependencyEnd(usize),
+    /// Active task folded given number of items of given logged iterator.
+    IteratorWork(IteratorId, usize),
+    /// Active task splits a logged indexed iterator at given position
+    /// into parts of given sizes (left, right).
+    IteratorSplit(usize, usize, usize),
 }
 
 impl RayonEvent {
     /// return event time or 0 if none
-    pub(crate) fn time(&self) -> TimeStamp {
+    pub fn time(&self) -> TimeStamp {
         match *self {
             RayonEvent::TaskStart(_, t) => t,
             RayonEvent::TaskEnd(t) => t,
+            RayonEvent::Mark(_, t) => t,
             _ => 0,
         }
     }
 }
+
+/// Tag, enclosing subgraph, where it started (thread and event index) and is it ended
+/// for each subgraph (see `RawLogs::validate`).
+type SubgraphState<'a> = (&'a str, Option<usize>, (usize, usize), bool);
+
+/// All raw events logged by all threads of a pool during a logged install
+/// (see `ThreadPool::raw_logs`).
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::{RawLogs, RayonEvent, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// pool.logging_install(|| rayon_logs::join(|| 1, || 2));
+/// let raw_logs = pool.raw_logs();
+/// let starts = raw_logs
+///     .threads()
+///     .flat_map(|events| events.iter())
+///     .filter(|event| matches!(event, RayonEvent::TaskStart(..)))
+///     .count();
+/// // the initial task, the two join tasks and the task after the join
+/// assert_eq!(starts, 4);
+/// let log = raw_logs.run_log().expect("invalid logs");
+/// assert_eq!(log.tasks().len(), starts);
+///
+/// let path = std::env::temp_dir().join(format!("raw_logs_{}.json", std::process::id()));
+/// raw_logs.save(&path).expect("saving failed");
+/// let loaded = RawLogs::load(&path).expect("loading failed");
+/// std::fs::remove_file(&path).expect("removing failed");
+/// assert_eq!(loaded.run_log().expect("invalid logs").tasks().len(), starts);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawLogs {
+    /// events of each thread
+    pub(crate) threads: Vec<Vec<RayonEvent>>,
+    /// when the logged install started
+    pub(crate) start: TimeStamp,
+    /// how many task ids were allocated
+    pub(crate) tasks_number: usize,
+    /// how many iterator ids were allocated
+    pub(crate) iterators_number: usize,
+    /// sampling rate (see `sample_rate`)
+    pub(crate) sampling_rate: usize,
+    /// information on each thread (same order as events)
+    #[serde(default)]
+    pub(crate) threads_info: Vec<ThreadInfo>,
+    /// clock used for timestamps
+    #[serde(default)]
+    pub(crate) clock: Clock,
+}
+
+impl RawLogs {
+    /// Iterate on the events of each thread (in logging order).
+    pub fn threads(&self) -> impl Iterator<Item = &[RayonEvent]> {
+        self.threads.iter().map(|events| events.as_slice())
+    }
+
+    /// Information on each thread (same order as `threads`).
+    pub fn threads_info(&self) -> &[ThreadInfo] {
+        &self.threads_info
+    }
+
+    /// Clock used for all timestamps.
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// When the logged install started (same time origin as events).
+    pub fn start(&self) -> TimeStamp {
+        self.start
+    }
+
+    /// Post-process events into a tasks graph.
+    ///
+    /// Events of all threads are replayed by increasing times. Events without a time
+    /// (like `Child`) happen at the time of the previous timed event of their thread
+    /// and ties are broken by thread then position.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::RawLogs;
+    ///
+    /// // a join: task 0 forks tasks 1 and 2 (both starting at the same time)
+    /// // which are followed by task 3.
+    /// let raw_logs: RawLogs = serde_json::from_str(
+    ///     r#"{"start": 0, "tasks_number": 4, "iterators_number": 0, "sampling_rate": 1,
+    ///         "threads": [
+    ///             [{"TaskStart": [0, 0]}, {"Child": 1}, {"Child": 2}, {"TaskEnd": 10},
+    ///              {"TaskStart": [1, 10]}, {"Child": 3}, {"TaskEnd": 20},
+    ///              {"TaskStart": [3, 30]}, {"TaskEnd": 40}],
+    ///             [{"TaskStart": [2, 10]}, {"Child": 3}, {"TaskEnd": 25}]
+    ///         ]}"#,
+    /// )
+    /// .expect("invalid raw logs");
+    /// let log = raw_logs.run_log().expect("invalid logs");
+    /// let children: Vec<_> = log.tasks().iter().map(|t| t.children.clone()).collect();
+    /// assert_eq!(children, vec![vec![1, 2], vec![3], vec![3], vec![]]);
+    /// assert_eq!(log.tasks()[2].thread_id, 1);
+    /// assert_eq!(log.tasks()[3].scheduling_delay, 5);
+    /// assert_eq!(log.duration(), 40);
+    /// assert!(log.warnings().is_empty());
+    /// ```
+    ///
+    /// Skewed clocks can make tasks end before starting. They are extended to last 1ns:
+    ///
+    /// ```
+    /// use rayon_logs::RawLogs;
+    ///
+    /// let raw_logs: RawLogs = serde_json::from_str(
+    ///     r#"{"start": 0, "tasks_number": 4, "iterators_number": 0, "sampling_rate": 1,
+    ///         "threads": [
+    ///             [{"TaskStart": [0, 0]}, {"SubgraphStart": "skewed"}, {"Child": 1},
+    ///              {"Child": 2}, {"TaskEnd": 10},
+    ///              {"TaskStart": [1, 10]}, {"Child": 3}, {"TaskEnd": 20},
+    ///              {"TaskStart": [3, 30]}, {"SubgraphEnd": ["skewed", 0]}, {"TaskEnd": 30}],
+    ///             [{"TaskStart": [2, 15]}, {"Child": 3}, {"TaskEnd": 12}]
+    ///         ]}"#,
+    /// )
+    /// .expect("invalid raw logs");
+    /// let log = raw_logs.run_log().expect("invalid logs");
+    /// assert_eq!(log.tasks()[2].duration(), 1);
+    /// assert_eq!(log.tasks()[3].duration(), 1);
+    /// assert_eq!(log.warnings().len(), 1);
+    /// assert!(log.stats()["skewed"].2.is_finite());
+    /// let mut svg = Vec::new();
+    /// log.write_svg(&mut svg).expect("rendering failed");
+    /// let svg = String::from_utf8(svg).unwrap();
+    /// assert!(!svg.contains("NaN") && !svg.contains("inf"));
+    /// ```
+    ///
+    /// Inconsistent events are reported instead of panicking:
+    ///
+    /// ```
+    /// use rayon_logs::RawLogs;
+    ///
+    /// let raw_logs: RawLogs = serde_json::from_str(
+    ///     r#"{"start": 0, "tasks_number": 2, "iterators_number": 0, "sampling_rate": 1,
+    ///         "threads": [
+    ///             [{"TaskStart": [0, 0]}, {"Child": 1}, {"Child": 7}, {"TaskEnd": 10},
+    ///              {"TaskStart": [1, 10]}, {"TaskEnd": 20}]
+    ///         ]}"#,
+    /// )
+    /// .expect("invalid raw logs");
+    /// let error = raw_logs.run_log().expect_err("child 7 does not exist");
+    /// assert_eq!(error.description, "unknown child");
+    /// assert_eq!((error.thread_id, error.event_index), (0, 2));
+    /// ```
+    pub fn run_log(&self) -> Result<RunLog, LogError> {
+        RunLog::new(
+            self.tasks_number,
+            self.iterators_number,
+            self.threads.iter().map(|events| events.iter()).collect(),
+            self.start,
+            self.sampling_rate,
+            self.threads_info.clone(),
+            self.clock,
+        )
+    }
+
+    /// Look for structural problems in the events (tasks ending without starting or never
+    /// ending, children which never start, unbalanced subgraphs, time going backwards
+    /// on a thread). Each problem found is reported with its thread and event index.
+    /// Nothing is returned for valid logs.
+    ///
+    /// `run_log` only reports the first problem preventing post-processing
+    /// while this looks for all of them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon_logs::{RawLogs, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// pool.logging_install(|| rayon_logs::subgraph("join", 2, || rayon_logs::join(|| 1, || 2)));
+    /// assert!(pool.raw_logs().validate().is_empty());
+    ///
+    /// // task 1 ends twice, task 2 never starts and the subgraph never ends
+    /// let raw_logs: RawLogs = serde_json::from_str(
+    ///     r#"{"start": 0, "tasks_number": 3, "iterators_number": 0, "sampling_rate": 1,
+    ///         "threads": [
+    ///             [{"TaskStart": [0, 0]}, {"SubgraphStart": "s"}, {"Child": 1}, {"Child": 2},
+    ///              {"TaskEnd": 10}],
+    ///             [{"TaskStart": [1, 12]}, {"TaskEnd": 11}, {"TaskEnd": 13}]
+    ///         ]}"#,
+    /// )
+    /// .expect("invalid raw logs");
+    /// let findings: Vec<_> = raw_logs
+    ///     .validate()
+    ///     .into_iter()
+    ///     .map(|f| (f.thread_id, f.event_index, f.description))
+    ///     .collect();
+    /// assert_eq!(
+    ///     findings,
+    ///     vec![
+    ///         (0, 1, "subgraph s never ends".to_string()),
+    ///         (0, 3, "child 2 never starts".to_string()),
+    ///         (1, 1, "time goes backwards (11 after 12)".to_string()),
+    ///         (1, 2, "task end without matching task start".to_string()),
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<LogError> {
+        let mut findings = Vec::new();
+        let finding = |thread_id, event_index, description: String| LogError {
+            thread_id,
+            event_index,
+            description,
+        };
+        let mut started = vec![false; self.tasks_number];
+        for (thread_id, events) in self.threads.iter().enumerate() {
+            for (event_index, event) in events.iter().enumerate() {
+                if let RayonEvent::TaskStart(task, _) = *event {
+                    match started.get_mut(task) {
+                        None => findings.push(finding(
+                            thread_id,
+                            event_index,
+                            format!("starting unknown task {}", task),
+                        )),
+                        Some(true) => findings.push(finding(
+                            thread_id,
+                            event_index,
+                            format!("task {} starts twice", task),
+                        )),
+                        Some(s) => *s = true,
+                    }
+                }
+            }
+        }
+        for (thread_id, events) in self.threads.iter().enumerate() {
+            // active task and where it started
+            let mut active_task: Option<(TaskId, usize)> = None;
+            let mut last_time = None;
+            for (event_index, event) in events.iter().enumerate() {
+                let mut found =
+                    |description| findings.push(finding(thread_id, event_index, description));
+                match *event {
+                    RayonEvent::TaskStart(..) | RayonEvent::TaskEnd(_) | RayonEvent::Mark(..) => {
+                        let time = event.time();
+                        match last_time {
+                            Some(last) if time < last => {
+                                found(format!("time goes backwards ({} after {})", time, last))
+                            }
+                            _ => last_time = Some(time),
+                        }
+                    }
+                    _ => (),
+                }
+                match *event {
+                    RayonEvent::TaskStart(task, _) => {
+                        if let Some((previous, _)) = active_task {
+                            found(format!(
+                                "task {} starts while task {} never ended",
+                                task, previous
+                            ));
+                        }
+                        active_task = Some((task, event_index));
+                    }
+                    RayonEvent::TaskEnd(_) => {
+                        if active_task.is_none() {
+                            found("task end without matching task start".to_string());
+                        }
+                        active_task = None;
+                    }
+                    RayonEvent::Child(child) => {
+                        if active_task.is_none() {
+                            found(format!("child {} with no active task as father", child));
+                        }
+                        if !started.get(child).cloned().unwrap_or(false) {
+                            found(format!("child {} never starts", child));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            if let Some((task, event_index)) = active_task {
+                findings.push(finding(
+                    thread_id,
+                    event_index,
+                    format!("task {} never ends", task),
+                ));
+            }
+        }
+        findings.extend(self.unbalanced_subgraphs());
+        findings.sort_by_key(|f| (f.thread_id, f.event_index));
+        findings
+    }
+
+    /// Look for subgraphs ending without starting, never ending or ending out of order.
+    /// Subgraphs can end on other tasks than their starting one: tasks inherit the open
+    /// subgraph of their fathers.
+    fn unbalanced_subgraphs(&self) -> Vec<LogError> {
+        let mut findings = Vec::new();
+        let mut subgraphs: Vec<SubgraphState> = Vec::new();
+        // innermost open subgraph of each task
+        let mut tasks_subgraphs: Vec<Option<usize>> = vec![None; self.tasks_number];
+        let mut active_tasks: Vec<Option<TaskId>> = vec![None; self.threads.len()];
+        // same order as `RunLog::new`
+        for (_, thread_id, event_index, event) in self
+            .threads
+            .iter()
+            .enumerate()
+            .map(|(thread_id, events)| {
+                events
+                    .iter()
+                    .enumerate()
+                    .scan(0, move |time, (event_index, event)| {
+                        *time = event.time().max(*time);
+                        Some((*time, thread_id, event_index, event))
+                    })
+            })
+            .kmerge_by(|a, b| (a.0, a.1, a.2) < (b.0, b.1, b.2))
+        {
+            let active_task = &mut active_tasks[thread_id];
+            let open_subgraph =
+                active_task.and_then(|task| tasks_subgraphs.get(task).cloned().flatten());
+            let mut found = |description| {
+                findings.push(LogError {
+                    thread_id,
+                    event_index,
+                    description,
+                })
+            };
+            match *event {
+                RayonEvent::TaskStart(task, _) => *active_task = Some(task),
+                RayonEvent::TaskEnd(_) => *active_task = None,
+                RayonEvent::Child(child) => {
+                    if let Some(subgraph) = tasks_subgraphs.get_mut(child) {
+                        *subgraph = (*subgraph).max(open_subgraph);
+                    }
+                }
+                RayonEvent::SubgraphStart(ref tag) => match *active_task {
+                    Some(task) if task < self.tasks_number => {
+                        subgraphs.push((tag, open_subgraph, (thread_id, event_index), false));
+                        tasks_subgraphs[task] = Some(subgraphs.len() - 1);
+                    }
+                    _ => found(format!("subgraph {} starts with no active task", tag)),
+                },
+                RayonEvent::SubgraphEnd(ref tag, _) => match (*active_task, open_subgraph) {
+                    (Some(task), Some(subgraph)) if task < self.tasks_number => {
+                        let (open_tag, enclosing, _, ended) = &mut subgraphs[subgraph];
+                        if *open_tag != tag {
+                            found(format!(
+                                "subgraph {} ends while subgraph {} is open",
+                                tag, open_tag
+                            ));
+                        }
+                        *ended = true;
+                        tasks_subgraphs[task] = *enclosing;
+                    }
+                    (Some(_), _) => found(format!("subgraph {} ends without starting", tag)),
+                    (None, _) => found(format!("subgraph {} ends with no active task", tag)),
+                },
+                RayonEvent::SubgraphMeasure(ref label, _) if open_subgraph.is_none() => {
+                    found(format!("measure {} outside of any subgraph", label))
+                }
+                _ => (),
+            }
+        }
+        findings.extend(subgraphs.iter().filter(|(_, _, _, ended)| !ended).map(
+            |(tag, _, (thread_id, event_index), _)| LogError {
+                thread_id: *thread_id,
+                event_index: *event_index,
+                description: format!("subgraph {} never ends", tag),
+            },
+        ));
+        findings
+    }
+
+    /// Load raw logs saved with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<RawLogs, io::Error> {
+        let file = BufReader::new(File::open(path)?);
+        serde_json::from_reader(file).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Save raw logs as json.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, &self)?;
+        file.flush()
+    }
+}
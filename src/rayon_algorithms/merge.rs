@@ -0,0 +1,120 @@
+//! Parallel merge of two sorted slices.
+use crate::{join, subgraph};
+use std::cmp::Ordering;
+
+// Outputs up to this length are merged sequentially.
+const MAX_SEQUENTIAL: usize = 5_000;
+
+/// Merge sorted slices `left` and `right` into `out` (stably: on equal elements,
+/// the ones from `left` come first).
+/// The whole merge is tagged as a "merge" subgraph with `out.len()` as work.
+///
+/// The larger input is split in its middle and the other one around the corresponding
+/// element so parts stay balanced even with many duplicates.
+///
+/// # Panics
+///
+/// Panics if `out.len()` is not `left.len() + right.len()`.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rand::Rng;
+/// use rayon_logs::{par_merge, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let mut rng = rand::thread_rng();
+/// for &(left_len, right_len, values) in &[
+///     (0, 0, 10),
+///     (0, 1_000, 10),
+///     (1_000, 0, 10),
+///     (50_000, 30_000, 3),
+///     (20_000, 60_000, 1),
+///     (40_000, 40_000, 1_000_000),
+/// ] {
+///     // elements are (value, origin) and only values are compared
+///     let mut sorted_input = |len, origin| {
+///         let mut v: Vec<(u32, usize)> = (0..len)
+///             .map(|_| (rng.gen_range(0, values), origin))
+///             .collect();
+///         v.sort();
+///         v
+///     };
+///     let left = sorted_input(left_len, 0);
+///     let right = sorted_input(right_len, 1);
+///     let mut expected: Vec<_> = left.iter().chain(right.iter()).cloned().collect();
+///     expected.sort_by_key(|e| e.0); // stable
+///     let mut out = vec![(0, 0); left_len + right_len];
+///     let log = pool
+///         .logging_install(|| par_merge(&left, &right, &mut out, |a, b| a.0.cmp(&b.0)))
+///         .1;
+///     assert_eq!(out, expected);
+///     assert_eq!(log.stats()["merge"].0, left_len + right_len);
+/// }
+/// ```
+pub fn par_merge<T, F>(left: &[T], right: &[T], out: &mut [T], cmp: F)
+where
+    T: Clone + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    assert_eq!(
+        out.len(),
+        left.len() + right.len(),
+        "output size does not match inputs"
+    );
+    subgraph("merge", out.len(), || {
+        merge_recursively(left, right, out, &cmp)
+    })
+}
+
+fn merge_recursively<T, F>(left: &[T], right: &[T], out: &mut [T], cmp: &F)
+where
+    T: Clone + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    if out.len() <= MAX_SEQUENTIAL || left.is_empty() || right.is_empty() {
+        return sequential_merge(left, right, out, cmp);
+    }
+    let (left_split, right_split) = if left.len() >= right.len() {
+        // right elements equal to the pivot go after it
+        let left_split = left.len() / 2;
+        let pivot = &left[left_split];
+        let right_split = right.partition_point(|e| cmp(e, pivot) == Ordering::Less);
+        (left_split, right_split)
+    } else {
+        // left elements equal to the pivot go before it
+        let right_split = right.len() / 2;
+        let pivot = &right[right_split];
+        let left_split = left.partition_point(|e| cmp(e, pivot) != Ordering::Greater);
+        (left_split, right_split)
+    };
+    let (left_1, left_2) = left.split_at(left_split);
+    let (right_1, right_2) = right.split_at(right_split);
+    let (out_1, out_2) = out.split_at_mut(left_split + right_split);
+    join(
+        || merge_recursively(left_1, right_1, out_1, cmp),
+        || merge_recursively(left_2, right_2, out_2, cmp),
+    );
+}
+
+fn sequential_merge<T, F>(left: &[T], right: &[T], out: &mut [T], cmp: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let (mut left, mut right) = (left.iter().peekable(), right.iter().peekable());
+    for o in out.iter_mut() {
+        let take_left = match (left.peek(), right.peek()) {
+            (Some(l), Some(r)) => cmp(r, l) != Ordering::Less,
+            (Some(_), None) => true,
+            _ => false,
+        };
+        *o = if take_left { left.next() } else { right.next() }
+            .expect("output larger than inputs")
+            .clone();
+    }
+}
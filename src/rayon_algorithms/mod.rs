@@ -1,4 +1,7 @@
 //! we duplicate rayon's code here.
 //! this is the only possibility to trace rayon's own parallel algorithm
 //! without adding tracing hooks inside rayon.
+//! we also provide some classical parallel algorithms, tagged with subgraphs.
+pub mod merge;
+pub mod scan;
 pub mod slice;
@@ -0,0 +1,117 @@
+//! Parallel prefix sums (scans).
+//!
+//! The classical two-pass algorithm: chunks are scanned in parallel ("local_scan" subgraphs),
+//! then the totals of all chunks are scanned sequentially and finally each chunk is updated
+//! in parallel with the total of all previous chunks ("update" subgraphs).
+use crate::{subgraph, Logged};
+use rayon::prelude::*;
+
+/// Compute in place all prefix sums of given slice for given associative operation.
+/// After the call, `slice[i]` contains `slice[0] op slice[1] op ... op slice[i]`.
+///
+/// The slice is cut into chunks of about `len / (4 * threads)` elements
+/// (see `par_prefix_sum_with_chunk_size`).
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rand::Rng;
+/// use rayon_logs::{par_prefix_sum, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let mut rng = rand::thread_rng();
+/// for &len in &[0, 1, 7, 1_000, 100_003] {
+///     let input: Vec<u64> = (0..len).map(|_| rng.gen_range(0, 1_000)).collect();
+///     let expected: Vec<u64> = input
+///         .iter()
+///         .scan(0, |sum, e| {
+///             *sum += e;
+///             Some(*sum)
+///         })
+///         .collect();
+///     let mut v = input;
+///     let log = pool.logging_install(|| par_prefix_sum(&mut v, |a, b| a + b)).1;
+///     assert_eq!(v, expected);
+///     if len > 1_000 {
+///         let stats = log.stats();
+///         assert_eq!(stats["local_scan"].0, len);
+///         assert!(stats["update"].0 < len);
+///     }
+/// }
+/// ```
+pub fn par_prefix_sum<T, OP>(slice: &mut [T], op: OP)
+where
+    T: Clone + Send + Sync,
+    OP: Fn(&T, &T) -> T + Sync,
+{
+    let chunk_size = slice.len() / (4 * rayon::current_num_threads());
+    par_prefix_sum_with_chunk_size(slice, chunk_size.max(1), op)
+}
+
+/// Compute in place all prefix sums of given slice for given associative operation,
+/// cutting the slice into chunks of given size.
+/// Smaller chunks balance the load better but the sequential scan of chunks totals gets longer.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::par_prefix_sum_with_chunk_size;
+///
+/// let mut v: Vec<u32> = vec![1; 10_000];
+/// par_prefix_sum_with_chunk_size(&mut v, 100, |a, b| a + b);
+/// assert!(v.iter().copied().eq(1..=10_000));
+///
+/// let mut words: Vec<String> = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+/// par_prefix_sum_with_chunk_size(&mut words, 2, |a, b| format!("{}{}", a, b));
+/// assert_eq!(words, vec!["a", "ab", "abc"]);
+/// ```
+pub fn par_prefix_sum_with_chunk_size<T, OP>(slice: &mut [T], chunk_size: usize, op: OP)
+where
+    T: Clone + Send + Sync,
+    OP: Fn(&T, &T) -> T + Sync,
+{
+    assert!(chunk_size > 0, "chunks must not be empty");
+    if slice.is_empty() {
+        return;
+    }
+    let op = &op;
+    let mut totals: Vec<T> = Logged::new(slice.par_chunks_mut(chunk_size))
+        .map(|chunk| {
+            subgraph("local_scan", chunk.len(), || {
+                sequential_prefix_sum(chunk, op)
+            })
+        })
+        .collect();
+    sequential_prefix_sum(&mut totals, op);
+    Logged::new(
+        slice
+            .par_chunks_mut(chunk_size)
+            .skip(1)
+            .zip(totals.par_iter()),
+    )
+    .for_each(|(chunk, previous_total)| {
+        subgraph("update", chunk.len(), || {
+            chunk.iter_mut().for_each(|e| *e = op(previous_total, e))
+        })
+    });
+}
+
+/// Scan given (non-empty) slice sequentially and return its total.
+fn sequential_prefix_sum<T, OP>(slice: &mut [T], op: &OP) -> T
+where
+    T: Clone,
+    OP: Fn(&T, &T) -> T,
+{
+    for i in 1..slice.len() {
+        slice[i] = op(&slice[i - 1], &slice[i]);
+    }
+    slice[slice.len() - 1].clone()
+}
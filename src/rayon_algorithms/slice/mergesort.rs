@@ -4,7 +4,7 @@
 //! The only difference from the original is that the sequential `mergesort` returns
 //! `MergesortResult` and leaves descending arrays intact.
 
-use crate::join;
+use crate::{join, subgraph};
 use rayon::prelude::*;
 use std::mem;
 use std::mem::size_of;
@@ -614,7 +614,9 @@ unsafe fn recurse<T, F>(
     // Merge chunks `(start, mid)` and `(mid, end)` from `src` into `dest`.
     let src_left = slice::from_raw_parts_mut(src.offset(start as isize), mid - start);
     let src_right = slice::from_raw_parts_mut(src.offset(mid as isize), end - mid);
-    par_merge(src_left, src_right, dest.offset(start as isize), is_less);
+    subgraph("merge", end - start, || {
+        par_merge(src_left, src_right, dest.offset(start as isize), is_less)
+    });
 }
 
 /// Sorts `v` using merge sort in parallel.
@@ -642,11 +644,13 @@ where
 
     // Short slices get sorted in-place via insertion sort to avoid allocations.
     if len <= MAX_INSERTION {
-        if len >= 2 {
-            for i in (0..len - 1).rev() {
-                insert_head(&mut v[i..], &is_less);
+        subgraph("sort_run", len, || {
+            if len >= 2 {
+                for i in (0..len - 1).rev() {
+                    insert_head(&mut v[i..], &is_less);
+                }
             }
-        }
+        });
         return;
     }
 
@@ -658,10 +662,12 @@ where
 
     // If the slice is not longer than one chunk would be, do sequential merge sort and return.
     if len <= CHUNK_LENGTH {
-        let res = unsafe { mergesort(v, buf, &is_less) };
-        if res == MergesortResult::Descending {
-            v.reverse();
-        }
+        subgraph("sort_run", len, || {
+            let res = unsafe { mergesort(v, buf, &is_less) };
+            if res == MergesortResult::Descending {
+                v.reverse();
+            }
+        });
         return;
     }
 
@@ -675,10 +681,11 @@ where
             .map(|(i, chunk)| {
                 let l = CHUNK_LENGTH * i;
                 let r = l + chunk.len();
-                unsafe {
+                let res = subgraph("sort_run", chunk.len(), || unsafe {
                     let buf = (buf as *mut T).offset(l as isize);
-                    (l, r, mergesort(chunk, buf, &is_less))
-                }
+                    mergesort(chunk, buf, &is_less)
+                });
+                (l, r, res)
             })
             .collect::<Vec<_>>()
             .into_iter()
@@ -6,9 +6,14 @@
 //! [std::slice]: https://doc.rust-lang.org/stable/std/slice/
 
 mod mergesort;
+mod quicksort;
 use self::mergesort::par_mergesort;
+use self::quicksort::par_quicksort;
+use crate::Logged;
 use rayon::prelude::ParallelSliceMut as RayonParallelSliceMut;
+use rayon::prelude::*;
 use std::cmp::Ordering;
+use std::mem::size_of;
 
 /// Parallel extensions for mutable slices.
 pub trait ParallelSliceMut<T: Send>: RayonParallelSliceMut<T> {
@@ -45,6 +50,25 @@ pub trait ParallelSliceMut<T: Send>: RayonParallelSliceMut<T> {
     /// v.par_sort();
     /// assert_eq!(v, [-5, -3, 1, 2, 4]);
     /// ```
+    ///
+    /// Sorting chunks and merging them are tagged as "sort_run" and "merge" subgraphs.
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let mut v: Vec<u32> = (0..100_000).map(|i| (i * 7919) % 100_000).collect();
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let (_, log) = pool.logging_install(|| v.par_sort());
+    /// assert!(v.windows(2).all(|w| w[0] <= w[1]));
+    /// let stats = log.stats();
+    /// assert_eq!(stats["sort_run"].0, 100_000);
+    /// assert!(stats["merge"].0 > 0);
+    /// ```
     fn par_sort(&mut self)
     where
         T: Ord,
@@ -130,6 +154,10 @@ pub trait ParallelSliceMut<T: Send>: RayonParallelSliceMut<T> {
     /// v.par_sort_by_key(|k| k.abs());
     /// assert_eq!(v, [1, 2, -3, 4, -5]);
     /// ```
+    ///
+    /// Like `slice::sort_by_key`, the key function is called twice per comparison.
+    /// For expensive key functions, [`par_sort_by_cached_key`](#method.par_sort_by_cached_key)
+    /// is likely to be faster.
     fn par_sort_by_key<B, F>(&mut self, f: F)
     where
         B: Ord,
@@ -137,6 +165,271 @@ pub trait ParallelSliceMut<T: Send>: RayonParallelSliceMut<T> {
     {
         par_mergesort(self.as_parallel_slice_mut(), |a, b| f(a).lt(&f(b)));
     }
+
+    /// Sorts the slice in parallel with a key extraction function.
+    ///
+    /// During sorting, the key function is called at most once per element, by using
+    /// temporary storage to remember the results of key evaluation.
+    /// The key function is called in parallel, so the order of calls is completely unspecified.
+    ///
+    /// This sort is stable (i.e., does not reorder equal elements) and *O*(*m* \* *n* + *n* \* log(*n*))
+    /// worst-case, where the key function is *O*(*m*).
+    ///
+    /// For simple key functions (e.g., functions that are property accesses or
+    /// basic operations), [`par_sort_by_key`](#method.par_sort_by_key) is likely to be
+    /// faster.
+    ///
+    /// # Current implementation
+    ///
+    /// Keys are computed by a logged parallel iterator and sorted together with the elements'
+    /// indices using [`par_sort_unstable`](#method.par_sort_unstable).
+    /// Finally, the item positions are updated sequentially.
+    ///
+    /// In the worst case, the algorithm allocates temporary storage in a `Vec<(K, usize)>` the
+    /// length of the slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    ///
+    /// let mut v = [-5i32, 4, 32, -3, 2];
+    ///
+    /// v.par_sort_by_cached_key(|k| k.to_string());
+    /// assert!(v == [-3, -5, 2, 32, 4]);
+    /// ```
+    ///
+    /// The key function is called exactly once per element.
+    ///
+    /// ```
+    /// use rayon_logs::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let calls = AtomicUsize::new(0);
+    /// let mut v: Vec<u32> = (0..10_000).map(|i| (i * 7919) % 10_000).collect();
+    /// v.par_sort_by_cached_key(|x| {
+    ///     calls.fetch_add(1, Ordering::Relaxed);
+    ///     x / 10
+    /// });
+    /// assert_eq!(calls.load(Ordering::Relaxed), 10_000);
+    /// assert!(v.windows(2).all(|w| w[0] / 10 <= w[1] / 10));
+    /// ```
+    fn par_sort_by_cached_key<K, F>(&mut self, f: F)
+    where
+        F: Fn(&T) -> K + Sync,
+        K: Ord + Send,
+    {
+        let slice = self.as_parallel_slice_mut();
+        let len = slice.len();
+        if len < 2 {
+            return;
+        }
+
+        // Helper macro for indexing our vector by the smallest possible type, to reduce allocation.
+        macro_rules! sort_by_key {
+            ($t:ty) => {{
+                let mut indices: Vec<_> = Logged::new(slice.par_iter_mut().enumerate())
+                    .map(|(i, x)| (f(&*x), i as $t))
+                    .collect();
+                // The elements of `indices` are unique, as they are indexed, so any sort will be
+                // stable with respect to the original slice. We use an unstable sort here because
+                // it requires less memory allocation.
+                par_quicksort(&mut indices, |a, b| a.lt(b));
+                for i in 0..len {
+                    let mut index = indices[i].1;
+                    while (index as usize) < i {
+                        index = indices[index as usize].1;
+                    }
+                    indices[i].1 = index;
+                    slice.swap(i, index as usize);
+                }
+            }};
+        }
+
+        let sz_u8 = size_of::<(K, u8)>();
+        let sz_u16 = size_of::<(K, u16)>();
+        let sz_u32 = size_of::<(K, u32)>();
+        let sz_usize = size_of::<(K, usize)>();
+
+        if sz_u8 < sz_u16 && len <= (u8::MAX as usize) {
+            return sort_by_key!(u8);
+        }
+        if sz_u16 < sz_u32 && len <= (u16::MAX as usize) {
+            return sort_by_key!(u16);
+        }
+        if sz_u32 < sz_usize && len <= (u32::MAX as usize) {
+            return sort_by_key!(u32);
+        }
+        sort_by_key!(usize)
+    }
+    /// Sorts the slice in parallel, but might not preserve the order of equal elements.
+    ///
+    /// This sort is unstable (i.e., may reorder equal elements), in-place
+    /// (i.e., does not allocate), and *O*(*n* \* log(*n*)) worst-case.
+    ///
+    /// # Current implementation
+    ///
+    /// The current algorithm is based on [pattern-defeating quicksort][pdqsort] by Orson Peters,
+    /// which combines the fast average case of randomized quicksort with the fast worst case of
+    /// heapsort, while achieving linear time on slices with certain patterns. It uses some
+    /// randomization to avoid degenerate cases, but with a fixed seed to always provide
+    /// deterministic behavior.
+    ///
+    /// It is typically faster than stable sorting, except in a few special cases, e.g., when the
+    /// slice consists of several concatenated sorted sequences.
+    ///
+    /// All quicksorts work in two stages: partitioning into two halves followed by recursive
+    /// calls. The partitioning phase is sequential, but the two recursive calls are performed in
+    /// parallel.
+    /// Partitions are tagged as "partition" subgraphs and sequential sorts of small parts
+    /// as "leaf_sort" subgraphs.
+    ///
+    /// [pdqsort]: https://github.com/orlp/pdqsort
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    ///
+    /// let mut v = [-5, 4, 1, -3, 2];
+    ///
+    /// v.par_sort_unstable();
+    /// assert_eq!(v, [-5, -3, 1, 2, 4]);
+    /// ```
+    ///
+    /// Results are the same as sequential sorts, on random inputs as well as on inputs
+    /// with patterns.
+    ///
+    /// ```
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rand::Rng;
+    /// use rayon_logs::prelude::*;
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let mut rng = rand::thread_rng();
+    /// let len = 50_000;
+    /// let inputs: Vec<Vec<u32>> = vec![
+    ///     (0..len).map(|_| rng.gen()).collect(),
+    ///     (0..len).map(|_| rng.gen_range(0, 10)).collect(),
+    ///     (0..len).collect(),
+    ///     (0..len).rev().collect(),
+    ///     vec![7; len as usize],
+    ///     (0..len).map(|i| if i < len / 2 { i } else { len - i }).collect(),
+    ///     (0..len).map(|i| if i % 100 == 0 { len - i } else { i }).collect(),
+    ///     (0..17).collect(),
+    ///     Vec::new(),
+    /// ];
+    /// for (index, input) in inputs.into_iter().enumerate() {
+    ///     let mut expected = input.clone();
+    ///     expected.sort_unstable();
+    ///     let mut v = input;
+    ///     let log = pool.logging_install(|| v.par_sort_unstable()).1;
+    ///     assert_eq!(v, expected);
+    ///     // random inputs are partitioned down to leaves (sorted parts are only checked)
+    ///     if index == 0 {
+    ///         assert!(log.tags().contains(&"partition".to_string()));
+    ///         assert!(log.tags().contains(&"leaf_sort".to_string()));
+    ///     }
+    /// }
+    /// ```
+    fn par_sort_unstable(&mut self)
+    where
+        T: Ord,
+    {
+        par_quicksort(self.as_parallel_slice_mut(), |a, b| a.lt(b));
+    }
+
+    /// Sorts the slice in parallel with a comparator function, but might not preserve the order of
+    /// equal elements.
+    ///
+    /// This sort is unstable (i.e., may reorder equal elements), in-place
+    /// (i.e., does not allocate), and *O*(*n* \* log(*n*)) worst-case.
+    ///
+    /// # Current implementation
+    ///
+    /// The current algorithm is based on [pattern-defeating quicksort][pdqsort] by Orson Peters,
+    /// which combines the fast average case of randomized quicksort with the fast worst case of
+    /// heapsort, while achieving linear time on slices with certain patterns. It uses some
+    /// randomization to avoid degenerate cases, but with a fixed seed to always provide
+    /// deterministic behavior.
+    ///
+    /// It is typically faster than stable sorting, except in a few special cases, e.g., when the
+    /// slice consists of several concatenated sorted sequences.
+    ///
+    /// All quicksorts work in two stages: partitioning into two halves followed by recursive
+    /// calls. The partitioning phase is sequential, but the two recursive calls are performed in
+    /// parallel.
+    /// Partitions are tagged as "partition" subgraphs and sequential sorts of small parts
+    /// as "leaf_sort" subgraphs.
+    ///
+    /// [pdqsort]: https://github.com/orlp/pdqsort
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    ///
+    /// let mut v = [5, 4, 1, 3, 2];
+    /// v.par_sort_unstable_by(|a, b| a.cmp(b));
+    /// assert_eq!(v, [1, 2, 3, 4, 5]);
+    ///
+    /// // reverse sorting
+    /// v.par_sort_unstable_by(|a, b| b.cmp(a));
+    /// assert_eq!(v, [5, 4, 3, 2, 1]);
+    /// ```
+    fn par_sort_unstable_by<F>(&mut self, compare: F)
+    where
+        F: Fn(&T, &T) -> Ordering + Sync,
+    {
+        par_quicksort(self.as_parallel_slice_mut(), |a, b| {
+            compare(a, b) == Ordering::Less
+        });
+    }
+
+    /// Sorts the slice in parallel with a key extraction function, but might not preserve the order
+    /// of equal elements.
+    ///
+    /// This sort is unstable (i.e., may reorder equal elements), in-place
+    /// (i.e., does not allocate), and *O*(m \* *n* \* log(*n*)) worst-case,
+    /// where the key function is *O*(*m*).
+    ///
+    /// # Current implementation
+    ///
+    /// The current algorithm is based on [pattern-defeating quicksort][pdqsort] by Orson Peters,
+    /// which combines the fast average case of randomized quicksort with the fast worst case of
+    /// heapsort, while achieving linear time on slices with certain patterns. It uses some
+    /// randomization to avoid degenerate cases, but with a fixed seed to always provide
+    /// deterministic behavior.
+    ///
+    /// All quicksorts work in two stages: partitioning into two halves followed by recursive
+    /// calls. The partitioning phase is sequential, but the two recursive calls are performed in
+    /// parallel.
+    /// Partitions are tagged as "partition" subgraphs and sequential sorts of small parts
+    /// as "leaf_sort" subgraphs.
+    ///
+    /// [pdqsort]: https://github.com/orlp/pdqsort
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    ///
+    /// let mut v = [-5i32, 4, 1, -3, 2];
+    ///
+    /// v.par_sort_unstable_by_key(|k| k.abs());
+    /// assert_eq!(v, [1, 2, -3, 4, -5]);
+    /// ```
+    fn par_sort_unstable_by_key<B, F>(&mut self, f: F)
+    where
+        B: Ord,
+        F: Fn(&T) -> B + Sync,
+    {
+        par_quicksort(self.as_parallel_slice_mut(), |a, b| f(a).lt(&f(b)));
+    }
 }
 
 impl<T: Send> ParallelSliceMut<T> for [T] {}
@@ -0,0 +1,200 @@
+//! Statistics computed by a `Comparator`, available programmatically.
+use crate::log::RunLog;
+use crate::stats::{deviations, mann_whitney, Stats};
+use itertools::izip;
+use serde_derive::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+/// Statistics of one tag for one algorithm.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagResults {
+    /// the tag.
+    pub tag: String,
+    /// average time spent in tagged subgraphs per run.
+    pub mean_duration: u64,
+    /// half width of the 95% confidence interval of `mean_duration`.
+    pub confidence_half_width: f64,
+    /// number of tagged subgraphs in the median run.
+    pub median_count: usize,
+    /// time spent in tagged subgraphs in the median run.
+    pub median_duration: u64,
+    /// normalised speed of tagged subgraphs in the median run.
+    pub median_speed: f64,
+    /// number of tasks in tagged subgraphs in the median run.
+    pub median_tasks_count: usize,
+    /// work recorded by each thread in the median run (attributed to the thread
+    /// starting each subgraph).
+    pub median_threads_works: Vec<usize>,
+}
+
+/// Statistics of one algorithm.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlgorithmResults {
+    /// the algorithm's label.
+    pub label: String,
+    /// durations (in nanoseconds) of all runs, sorted.
+    pub durations: Vec<u64>,
+    /// average duration.
+    pub mean_duration: u64,
+    /// standard deviation of the durations.
+    pub deviation: f64,
+    /// 95% confidence interval of the average duration.
+    pub confidence_interval: (u64, u64),
+    /// duration of the median run.
+    pub median_duration: u64,
+    /// average idle time over all runs.
+    pub mean_idle_time: u64,
+    /// average number of tasks per run.
+    pub mean_tasks_count: f64,
+    /// average number of successful steals (forks whose child moved to another thread) per run.
+    pub mean_steals: f64,
+    /// average time spent with at least 90% of the threads working per run.
+    pub mean_high_utilization_time: u64,
+    /// idle time of the median run.
+    pub median_idle_time: u64,
+    /// for each thread of the median run its idle time split into
+    /// (starvation, no available parallelism).
+    pub median_idle_causes: Vec<(u64, u64)>,
+    /// duration of the median run multiplied by its number of threads.
+    pub median_unrolled_time: u64,
+    /// number of tasks in the median run.
+    pub median_tasks_count: usize,
+    /// statistics for each tag.
+    pub tags: Vec<TagResults>,
+}
+
+/// All statistics computed when comparing algorithms.
+/// Obtained with `Comparator::results`.
+///
+/// Example:
+///
+/// ```
+/// use rayon_logs::prelude::*;
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let v: Vec<u64> = (0..10_000).collect();
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let results = pool
+///     .compare()
+///     .runs_number(5)
+///     .attach_algorithm_nodisplay("sum", || assert!(v.par_iter().sum::<u64>() > 0))
+///     .attach_algorithm_nodisplay("max", || assert!(v.par_iter().max().is_some()))
+///     .results()
+///     .expect("saving raw logs failed");
+/// let sum = results.algorithm("sum").expect("no sum");
+/// assert_eq!(sum.durations.len(), 5);
+/// assert!(sum.median_duration <= sum.durations[4]);
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparisonResults {
+    /// all tags, in the same order as each algorithm's tags statistics.
+    pub tags: Vec<String>,
+    /// statistics for each algorithm, in attachment order.
+    pub algorithms: Vec<AlgorithmResults>,
+}
+
+impl ComparisonResults {
+    /// Compute all statistics for given sorted logs (whose tags are already fused).
+    pub(crate) fn new(labels: &[String], logs: &[Vec<RunLog>], tags: Vec<String>) -> Self {
+        let statistics = Stats::get_statistics(logs);
+        let algorithms = izip!(
+            labels,
+            logs,
+            statistics.mean_rows(&tags),
+            statistics.median_rows(&tags),
+            statistics.get_median_task_counts(),
+            statistics.tasks_split_median(&tags)
+        )
+        .map(
+            |(label, runs, mean_row, median_row, median_tasks_count, tagged_tasks_counts)| {
+                AlgorithmResults {
+                    label: label.clone(),
+                    durations: runs.iter().map(|run| run.duration).collect(),
+                    mean_duration: mean_row.total_time,
+                    deviation: mean_row.deviation,
+                    confidence_interval: mean_row.confidence_interval,
+                    median_duration: median_row.total_time,
+                    mean_idle_time: mean_row.idle_time,
+                    mean_tasks_count: mean_row.tasks_count,
+                    mean_steals: mean_row.steals,
+                    mean_high_utilization_time: mean_row.high_utilization_time,
+                    median_idle_time: median_row.idle_time,
+                    median_idle_causes: median_row.idle_causes,
+                    median_unrolled_time: median_row.unrolled_time,
+                    median_tasks_count,
+                    tags: izip!(
+                        &tags,
+                        mean_row.tagged_times,
+                        median_row.tagged_stats,
+                        median_row.tagged_threads_works,
+                        tagged_tasks_counts
+                    )
+                    .map(
+                        |(
+                            tag,
+                            (mean_duration, confidence_half_width),
+                            median,
+                            threads_works,
+                            tasks_count,
+                        )| {
+                            TagResults {
+                                tag: tag.clone(),
+                                mean_duration,
+                                confidence_half_width,
+                                median_count: median.0,
+                                median_duration: median.1,
+                                median_speed: median.2,
+                                median_tasks_count: tasks_count,
+                                median_threads_works: threads_works,
+                            }
+                        },
+                    )
+                    .collect(),
+                }
+            },
+        )
+        .collect();
+        ComparisonResults { tags, algorithms }
+    }
+
+    /// Return statistics of algorithm with given label.
+    pub fn algorithm(&self, label: &str) -> Option<&AlgorithmResults> {
+        self.algorithms.iter().find(|a| a.label == label)
+    }
+
+    /// Compare the runs durations of each algorithm (except the first one) against the first
+    /// algorithm with a Mann-Whitney U test.
+    /// Return for each one the p-value and a verdict.
+    pub fn significance_tests<'a>(&'a self) -> impl Iterator<Item = (f64, &'static str)> + 'a {
+        let reference: &[u64] = self
+            .algorithms
+            .first()
+            .map(|a| a.durations.as_slice())
+            .unwrap_or_default();
+        self.algorithms.iter().skip(1).map(move |algorithm| {
+            let (_, p_value) = mann_whitney(reference, &algorithm.durations);
+            let verdict = if p_value >= 0.05 {
+                "no significant difference"
+            } else if deviations(algorithm.durations.iter().cloned()).0
+                < deviations(reference.iter().cloned()).0
+            {
+                "likely faster"
+            } else {
+                "likely slower"
+            };
+            (p_value, verdict)
+        })
+    }
+
+    /// Save all statistics as json.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut file, &self)?;
+        file.flush()
+    }
+}
@@ -1,6 +1,8 @@
 //! Logging scope and Scope.
+use crate::pool::{
+    in_sampled_out_region, log, logged_task, next_task_id, sampled_out, then_continue,
+};
 use crate::raw_events::{now, RayonEvent, TaskId};
-use crate::{pool::log, pool::next_task_id};
 use std::mem::transmute;
 
 ///Represents a fork-join scope which can be used to spawn any number of tasks. See [`scope()`] for more information.
@@ -57,6 +59,43 @@ impl<'scope> Scope<'scope> {
     /// assert_eq!(value_c, Some(66));
     /// ```
     ///
+    /// The same example with logs: the spawning task is the father of both the spawned
+    /// task and its own continuation, even inside spawned tasks, so the graph stays connected.
+    ///
+    /// ```rust
+    /// # if cfg!(feature = "disabled") { return; } // logs are empty
+    /// use rayon_logs::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .expect("building pool failed");
+    /// let mut value_a = None;
+    /// let mut value_b = None;
+    /// let mut value_c = None;
+    /// let (_, log) = pool.logging_install(|| {
+    ///     rayon_logs::scope(|s| {
+    ///         s.spawn(|s1| {
+    ///             value_a = Some(22);
+    ///             s1.spawn(|_| {
+    ///                 value_b = Some(44);
+    ///             });
+    ///         });
+    ///         s.spawn(|_| {
+    ///             value_c = Some(66);
+    ///         });
+    ///     })
+    /// });
+    /// assert_eq!((value_a, value_b, value_c), (Some(22), Some(44), Some(66)));
+    /// assert!(log.validate().is_empty());
+    /// let roots = (0..log.tasks().len())
+    ///     .filter(|id| log.tasks().iter().all(|t| !t.children.contains(id)))
+    ///     .count();
+    /// assert_eq!(roots, 1);
+    /// let mut svg = Vec::new();
+    /// log.write_svg(&mut svg).expect("rendering failed");
+    /// ```
+    ///
     /// # See also
     ///
     /// The [`scope` function] has more extensive documentation about
@@ -73,18 +112,65 @@ impl<'scope> Scope<'scope> {
         // sorry I need to erase the borrow's lifetime.
         // it's ok though since the pointed self will survive all spawned tasks.
         let floating_self: &'scope Scope<'scope> = unsafe { transmute(self) };
+        let unsampled = in_sampled_out_region();
         let logged_body = move |_: &rayon::Scope<'scope>| {
-            log(RayonEvent::TaskStart(spawned_id, now()));
-            body(floating_self);
-            logs!(
-                RayonEvent::Child(floating_self.continuing_task_id),
-                RayonEvent::TaskEnd(now())
-            );
+            if unsampled {
+                sampled_out(|| body(floating_self))
+            } else {
+                logged_task(spawned_id, floating_self.continuing_task_id, || {
+                    body(floating_self)
+                })
+            }
         };
         self.rayon_scope.as_ref().unwrap().spawn(logged_body);
         logs!(
-            RayonEvent::TaskEnd(now()),
-            RayonEvent::TaskStart(seq_id, now())
+            time =>
+            RayonEvent::TaskEnd(time),
+            RayonEvent::TaskStart(seq_id, time)
+        );
+    }
+
+    /// Spawns a job into every thread of the fork-join scope `self`. This job will
+    /// execute on each thread sometime before the fork-join scope completes.  The
+    /// job is specified as a closure, and this closure receives its own reference
+    /// to the scope `self` as argument, as well as a `BroadcastContext`.
+    ///
+    /// Each thread gets its own task in the logs.
+    pub fn spawn_broadcast<BODY>(&self, body: BODY)
+    where
+        BODY: Fn(&Scope<'scope>, rayon::BroadcastContext<'_>) + Send + Sync + 'scope,
+    {
+        let spawned_ids: Vec<TaskId> = (0..rayon::current_num_threads())
+            .map(|_| next_task_id())
+            .collect();
+        let seq_id = next_task_id();
+        for spawned_id in &spawned_ids {
+            log(RayonEvent::Child(*spawned_id));
+        }
+        log(RayonEvent::Child(seq_id));
+        // sorry I need to erase the borrow's lifetime.
+        // it's ok though since the pointed self will survive all spawned tasks.
+        let floating_self: &'scope Scope<'scope> = unsafe { transmute(self) };
+        let unsampled = in_sampled_out_region();
+        let logged_body = move |_: &rayon::Scope<'scope>, context: rayon::BroadcastContext| {
+            if unsampled {
+                sampled_out(|| body(floating_self, context))
+            } else {
+                logged_task(
+                    spawned_ids[context.index()],
+                    floating_self.continuing_task_id,
+                    || body(floating_self, context),
+                )
+            }
+        };
+        self.rayon_scope
+            .as_ref()
+            .unwrap()
+            .spawn_broadcast(logged_body);
+        logs!(
+            time =>
+            RayonEvent::TaskEnd(time),
+            RayonEvent::TaskStart(seq_id, time)
         );
     }
 }
@@ -95,6 +181,40 @@ impl<'scope> Scope<'scope> {
 /// closure; they may themselves spawn additional tasks into `s`. When
 /// the closure returns, it will block until all tasks that have been
 /// spawned into `s` complete.
+///
+/// The task following the scope depends on all spawned tasks.
+///
+/// # Example
+///
+/// ```
+/// # if cfg!(feature = "disabled") { return; } // logs are empty
+/// use rayon_logs::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(3)
+///     .build()
+///     .expect("building pool failed");
+/// let (_, log) = pool.logging_install(|| {
+///     rayon_logs::scope(|s| {
+///         s.spawn(|_| ());
+///         s.spawn(|_| ());
+///         s.spawn(|_| ());
+///     });
+///     rayon_logs::join(|| 1, || 2)
+/// });
+/// // the continuation has several fathers in different blocks
+/// let fathers_number = |task_id| {
+///     log.tasks()
+///         .iter()
+///         .filter(|t| t.children.contains(&task_id))
+///         .count()
+/// };
+/// assert!((0..log.tasks().len()).any(|t| fathers_number(t) == 4));
+/// assert!(log.validate().is_empty());
+/// let mut svg = Vec::new();
+/// log.write_svg(&mut svg).expect("rendering failed");
+/// assert!(!svg.is_empty());
+/// ```
 pub fn scope<'scope, OP, R>(op: OP) -> R
 where
     OP: for<'s> FnOnce(&'s Scope<'scope>) -> R + 'scope + Send,
@@ -110,20 +230,14 @@ where
         continuing_task_id,
     };
     let borrowed_scope_ref = &mut borrowed_scope;
-    let r = rayon::scope(move |s| {
-        log(RayonEvent::TaskStart(scope_id, now()));
-        // I'm sorry, there is no other way to do it without changing
-        // the API. Because I can only access a reference to the underlying rayon::Scope
-        borrowed_scope_ref.rayon_scope = unsafe { transmute(Some(s)) };
-        let r = op(borrowed_scope_ref);
-        logs!(
-            RayonEvent::Child(continuing_task_id),
-            RayonEvent::TaskEnd(now())
-        );
-        r
-    });
-    log(RayonEvent::TaskStart(continuing_task_id, now()));
-    r
+    then_continue(continuing_task_id, || {
+        rayon::scope(move |s| {
+            // I'm sorry, there is no other way to do it without changing
+            // the API. Because I can only access a reference to the underlying rayon::Scope
+            borrowed_scope_ref.rayon_scope = unsafe { transmute(Some(s)) };
+            logged_task(scope_id, continuing_task_id, || op(borrowed_scope_ref))
+        })
+    })
 }
 
 /// Obtained from `scope_fifo`.
@@ -144,18 +258,21 @@ impl<'scope> ScopeFifo<'scope> {
         // sorry I need to erase the borrow's lifetime.
         // it's ok though since the pointed self will survive all spawned tasks.
         let floating_self: &'scope ScopeFifo<'scope> = unsafe { transmute(self) };
+        let unsampled = in_sampled_out_region();
         let logged_body = move |_: &rayon::ScopeFifo<'scope>| {
-            log(RayonEvent::TaskStart(spawned_id, now()));
-            body(floating_self);
-            logs!(
-                RayonEvent::Child(floating_self.continuing_task_id),
-                RayonEvent::TaskEnd(now())
-            );
+            if unsampled {
+                sampled_out(|| body(floating_self))
+            } else {
+                logged_task(spawned_id, floating_self.continuing_task_id, || {
+                    body(floating_self)
+                })
+            }
         };
         self.rayon_scope.as_ref().unwrap().spawn_fifo(logged_body);
         logs!(
-            RayonEvent::TaskEnd(now()),
-            RayonEvent::TaskStart(seq_id, now())
+            time =>
+            RayonEvent::TaskEnd(time),
+            RayonEvent::TaskStart(seq_id, time)
         );
     }
 }
@@ -176,18 +293,12 @@ where
         continuing_task_id,
     };
     let borrowed_scope_ref = &mut borrowed_scope;
-    let r = rayon::scope_fifo(move |s| {
-        log(RayonEvent::TaskStart(scope_id, now()));
-        // I'm sorry, there is no other way to do it without changing
-        // the API. Because I can only access a reference to the underlying rayon::Scope
-        borrowed_scope_ref.rayon_scope = unsafe { transmute(Some(s)) };
-        let r = op(borrowed_scope_ref);
-        logs!(
-            RayonEvent::Child(continuing_task_id),
-            RayonEvent::TaskEnd(now())
-        );
-        r
-    });
-    log(RayonEvent::TaskStart(continuing_task_id, now()));
-    r
+    then_continue(continuing_task_id, || {
+        rayon::scope_fifo(move |s| {
+            // I'm sorry, there is no other way to do it without changing
+            // the API. Because I can only access a reference to the underlying rayon::Scope
+            borrowed_scope_ref.rayon_scope = unsafe { transmute(Some(s)) };
+            logged_task(scope_id, continuing_task_id, || op(borrowed_scope_ref))
+        })
+    })
 }
@@ -1,17 +1,345 @@
-//! `LoggedPool` structure for logging raw tasks events.
+//! Statistics on the runs of compared algorithms (see `Comparator`).
 use std::{collections::HashMap, iter::repeat};
 
 // use crate::fork_join_graph::{create_graph, Block};
 use crate::{log::RunLog, raw_events::TimeStamp};
+use itertools::izip;
+
+/// Utilization above which a run is considered to use the whole pool.
+const HIGH_UTILIZATION: f64 = 0.9;
+
+/// Number of time windows used to compute runs utilizations.
+const UTILIZATION_BUCKETS: usize = 100;
+
+/// Two-sided 95% quantiles of student's t distribution for 1 to 30 degrees of freedom.
+const STUDENT_T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// Compute (mean, standard deviation, half width of the 95% confidence interval of the mean)
+/// for given values.
+/// With less than two values there is no deviation and we return 0 for both.
+pub(crate) fn deviations<I: IntoIterator<Item = u64>>(values: I) -> (f64, f64, f64) {
+    let values: Vec<f64> = values.into_iter().map(|v| v as f64).collect();
+    let n = values.len();
+    if n == 0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / n as f64;
+    if n == 1 {
+        return (mean, 0.0, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1) as f64;
+    let deviation = variance.sqrt();
+    let t = STUDENT_T_95.get(n - 2).cloned().unwrap_or(1.96);
+    (mean, deviation, t * deviation / (n as f64).sqrt())
+}
+
+/// Return the quantile of given sorted values (linear interpolation between closest ranks).
+/// There is no quantile for no values and we return 0.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::stats::quantile;
+///
+/// // odd number of values: quartiles fall on values
+/// let odd = [1, 2, 3, 4, 5];
+/// assert_eq!(quantile(&odd, 0.0), 1.0);
+/// assert_eq!(quantile(&odd, 0.25), 2.0);
+/// assert_eq!(quantile(&odd, 0.5), 3.0);
+/// assert_eq!(quantile(&odd, 0.75), 4.0);
+/// assert_eq!(quantile(&odd, 1.0), 5.0);
+/// // even number of values: interpolate between closest values
+/// let even = [1, 2, 3, 4];
+/// assert_eq!(quantile(&even, 0.25), 1.75);
+/// assert_eq!(quantile(&even, 0.5), 2.5);
+/// assert_eq!(quantile(&even, 0.75), 3.25);
+/// assert_eq!(quantile(&[7], 0.5), 7.0);
+/// assert_eq!(quantile(&[], 0.5), 0.0);
+/// ```
+pub fn quantile(sorted_values: &[u64], q: f64) -> f64 {
+    if sorted_values.is_empty() {
+        return 0.0;
+    }
+    let position = q * (sorted_values.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let fraction = position - below as f64;
+    sorted_values[below] as f64 * (1.0 - fraction) + sorted_values[above] as f64 * fraction
+}
+
+/// Compute a box plot for given sorted values.
+/// Return the whiskers and quartiles (lower whisker, q1, median, q3, upper whisker)
+/// and all outliers (values further than 1.5 inter-quartile range from the box).
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::stats::box_plot;
+///
+/// // q1 = 3, q3 = 7 so values outside of [-3, 13] are outliers
+/// let (boxes, outliers) = box_plot(&[1, 2, 3, 4, 5, 6, 7, 8, 100]);
+/// assert_eq!(boxes, [1.0, 3.0, 5.0, 7.0, 8.0]);
+/// assert_eq!(outliers, vec![100]);
+/// // values on the limits are not outliers
+/// let (boxes, outliers) = box_plot(&[1, 2, 3, 4, 5, 6, 7, 8, 13]);
+/// assert_eq!(boxes, [1.0, 3.0, 5.0, 7.0, 13.0]);
+/// assert!(outliers.is_empty());
+/// // even number of values
+/// let (boxes, outliers) = box_plot(&[10, 20, 30, 40]);
+/// assert_eq!(boxes, [10.0, 17.5, 25.0, 32.5, 40.0]);
+/// assert!(outliers.is_empty());
+/// ```
+pub fn box_plot(sorted_values: &[u64]) -> ([f64; 5], Vec<u64>) {
+    let q1 = quantile(sorted_values, 0.25);
+    let median = quantile(sorted_values, 0.5);
+    let q3 = quantile(sorted_values, 0.75);
+    let iqr = q3 - q1;
+    let (low_limit, high_limit) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+    let is_inlier = |v: &&u64| **v as f64 >= low_limit && **v as f64 <= high_limit;
+    let low_whisker = sorted_values.iter().find(is_inlier).map(|v| *v as f64);
+    let high_whisker = sorted_values
+        .iter()
+        .rev()
+        .find(is_inlier)
+        .map(|v| *v as f64);
+    let outliers = sorted_values
+        .iter()
+        .filter(|v| !is_inlier(v))
+        .cloned()
+        .collect();
+    (
+        [
+            low_whisker.unwrap_or(q1),
+            q1,
+            median,
+            q3,
+            high_whisker.unwrap_or(q3),
+        ],
+        outliers,
+    )
+}
+
+/// Complementary error function (numerical recipes' `erfcc`, fractional error below 1.2e-7).
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::stats::erfc;
+///
+/// // tabulated values
+/// for &(x, expected) in &[
+///     (0.0, 1.0),
+///     (0.5, 0.479_500_122),
+///     (1.0, 0.157_299_207),
+///     (2.0, 0.004_677_735),
+///     (-1.0, 1.842_700_793),
+/// ] {
+///     assert!((erfc(x) - expected).abs() <= 1.2e-7 * expected);
+/// }
+/// ```
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98
+                                    + t * (1.488_515_87
+                                        + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Two-sided Mann-Whitney U (Wilcoxon rank-sum) test.
+/// Return the U statistic of the first sample (number of pairs where its value is the
+/// largest, ties counting for one half) and the p-value of the hypothesis that both samples
+/// come from the same distribution.
+/// We use the normal approximation with ties and continuity corrections.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::stats::mann_whitney;
+///
+/// // all values of the first sample are smaller: U = 0 and z = (8 - 0.5) / sqrt(12) = 2.165
+/// let (u, p) = mann_whitney(&[1, 2, 3, 4], &[5, 6, 7, 8]);
+/// assert_eq!(u, 0.0);
+/// assert!((p - 0.0304).abs() < 1e-4);
+/// let (u, p) = mann_whitney(&[5, 6, 7, 8], &[1, 2, 3, 4]);
+/// assert_eq!(u, 16.0);
+/// assert!((p - 0.0304).abs() < 1e-4);
+/// // ties count for one half
+/// let (u, _) = mann_whitney(&[1, 2, 2, 3], &[2, 3, 4, 5]);
+/// assert_eq!(u, 2.5);
+/// // identical samples
+/// assert_eq!(mann_whitney(&[3, 3, 3], &[3, 3]), (3.0, 1.0));
+/// assert!((mann_whitney(&[1, 2, 3], &[1, 2, 3]).1 - 1.0).abs() < 1e-6);
+/// ```
+pub fn mann_whitney(first: &[u64], second: &[u64]) -> (f64, f64) {
+    let (n1, n2) = (first.len() as f64, second.len() as f64);
+    if first.is_empty() || second.is_empty() {
+        return (0.0, 1.0);
+    }
+    // rank all values together, tied values get the average of their ranks
+    let mut values: Vec<(u64, bool)> = first
+        .iter()
+        .map(|&v| (v, true))
+        .chain(second.iter().map(|&v| (v, false)))
+        .collect();
+    values.sort_unstable_by_key(|&(v, _)| v);
+    let mut first_ranks_sum = 0.0;
+    let mut ties_correction = 0.0;
+    let mut start = 0;
+    while start < values.len() {
+        let ties = values[start..]
+            .iter()
+            .take_while(|(v, _)| *v == values[start].0)
+            .count();
+        let end = start + ties;
+        let rank = (start + 1 + end) as f64 / 2.0;
+        let firsts = values[start..end].iter().filter(|(_, f)| *f).count();
+        first_ranks_sum += rank * firsts as f64;
+        ties_correction += (ties * ties * ties - ties) as f64;
+        start = end;
+    }
+    let u = first_ranks_sum - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return (u, 1.0); // all values are identical
+    }
+    let z = ((u - n1 * n2 / 2.0).abs() - 0.5).max(0.0) / variance.sqrt();
+    (u, erfc(z / std::f64::consts::SQRT_2))
+}
+
+/// Return the median run of given sorted runs (none if there are no runs).
+fn median_run(runs: &[RunLog]) -> Option<&RunLog> {
+    runs.get(runs.len() / 2)
+}
+
+/// Mean statistics of one algorithm.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::stats::Stats;
+/// use rayon_logs::RunLog;
+///
+/// let run = |json: &str| -> RunLog { serde_json::from_str(json).expect("invalid log") };
+/// // two runs of the same algorithm (sorted by duration), each one forking once
+/// let logs = vec![vec![
+///     run(r#"{"threads_number": 2, "duration": 100,
+///         "tasks_logs": [
+///             {"start_time": 0, "end_time": 40, "thread_id": 0, "children": [1, 2]},
+///             {"start_time": 40, "end_time": 100, "thread_id": 0, "children": []},
+///             {"start_time": 40, "end_time": 90, "thread_id": 1, "children": []}],
+///         "tags": [], "subgraphs": []}"#),
+///     run(r#"{"threads_number": 2, "duration": 300,
+///         "tasks_logs": [
+///             {"start_time": 0, "end_time": 100, "thread_id": 0, "children": [1, 2]},
+///             {"start_time": 100, "end_time": 300, "thread_id": 0, "children": []},
+///             {"start_time": 100, "end_time": 250, "thread_id": 1, "children": []}],
+///         "tags": [], "subgraphs": []}"#),
+/// ]];
+/// let stats = Stats::get_statistics(&logs);
+/// let row = &stats.mean_rows(&[])[0];
+/// assert_eq!(row.total_time, 200);
+/// assert!((row.deviation - 20_000f64.sqrt()).abs() < 1e-9);
+/// // with one degree of freedom the half width is 12.706 * deviation / sqrt(2)
+/// assert_eq!(row.confidence_interval, (0, 1470));
+/// assert!(row.tagged_times.is_empty());
+/// assert_eq!(row.idle_time, (50 + 150) / 2);
+/// assert_eq!(row.tasks_count, 3.0);
+/// // the second child of each fork runs on the other thread
+/// assert_eq!(row.steals, 1.0);
+/// ```
+pub struct MeanRow {
+    /// average duration of all runs.
+    pub total_time: u64,
+    /// standard deviation of the runs durations.
+    pub deviation: f64,
+    /// 95% confidence interval of the average duration.
+    pub confidence_interval: (u64, u64),
+    /// for each tag the average time and half width of its 95% confidence interval.
+    pub tagged_times: Vec<(u64, f64)>,
+    /// average idle time.
+    pub idle_time: u64,
+    /// average number of tasks.
+    pub tasks_count: f64,
+    /// average number of successful steals.
+    pub steals: f64,
+    /// average time spent above 90% utilization.
+    pub high_utilization_time: u64,
+}
+
+/// Statistics of the median run of one algorithm.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::stats::Stats;
+/// use rayon_logs::RunLog;
+///
+/// let run = |json: &str| -> RunLog { serde_json::from_str(json).expect("invalid log") };
+/// // two runs of the same algorithm (sorted by duration), each one forking once
+/// let logs = vec![vec![
+///     run(r#"{"threads_number": 2, "duration": 100,
+///         "tasks_logs": [
+///             {"start_time": 0, "end_time": 40, "thread_id": 0, "children": [1, 2]},
+///             {"start_time": 40, "end_time": 100, "thread_id": 0, "children": []},
+///             {"start_time": 40, "end_time": 90, "thread_id": 1, "children": []}],
+///         "tags": [], "subgraphs": []}"#),
+///     run(r#"{"threads_number": 2, "duration": 300,
+///         "tasks_logs": [
+///             {"start_time": 0, "end_time": 100, "thread_id": 0, "children": [1, 2]},
+///             {"start_time": 100, "end_time": 300, "thread_id": 0, "children": []},
+///             {"start_time": 100, "end_time": 250, "thread_id": 1, "children": []}],
+///         "tags": [], "subgraphs": []}"#),
+/// ]];
+/// let stats = Stats::get_statistics(&logs);
+/// // the median run is the longest one
+/// let row = &stats.median_rows(&[])[0];
+/// assert_eq!(row.total_time, 300);
+/// assert_eq!(row.unrolled_time, 600);
+/// assert!(row.tagged_stats.is_empty() && row.tagged_threads_works.is_empty());
+/// assert_eq!(row.idle_time, 150);
+/// // the second thread waits for the fork, then for the end of the first thread
+/// assert_eq!(row.idle_causes, vec![(0, 0), (0, 150)]);
+/// ```
+pub struct MedianRow {
+    /// duration of the median run.
+    pub total_time: u64,
+    /// duration multiplied by the number of threads.
+    pub unrolled_time: u64,
+    /// for each tag the (count, duration, normalised speed).
+    pub tagged_stats: Vec<(usize, u64, f64)>,
+    /// for each tag the work recorded by each thread.
+    pub tagged_threads_works: Vec<Vec<usize>>,
+    /// idle time of the median run.
+    pub idle_time: u64,
+    /// for each thread of the median run its idle time split into
+    /// (starvation, no available parallelism).
+    pub idle_causes: Vec<(u64, u64)>,
+}
 
 /// This struct mainly supplies the methods that can be used to get various statistics.
 pub struct Stats<'a> {
     /// This is a slice of algorithms, for each algorithm, there is a vector of RunLogs.
-    /// The vector contains one RunLog for each run of the algorithm, as per runs_number in the
-    /// pool.
+    /// The vector contains one RunLog for each run of the algorithm, sorted by duration.
+    /// Algorithms might have different numbers of runs.
     logs: &'a [Vec<RunLog>],
-    threads_number: usize,
-    runs_number: usize,
     /// for each algorithm associate to each tag a vec of stats per run.
     /// This is an n-tuple (count, duration, normalised_speed)
     tagged_stats: Vec<HashMap<String, Vec<(usize, u64, f64)>>>,
@@ -20,11 +348,7 @@ pub struct Stats<'a> {
 impl<'l> Stats<'l> {
     /// This method returns a statistics object.
     // logs given to this function are already sorted as per wall-time.
-    pub fn get_statistics(
-        logs: &'l Vec<Vec<RunLog>>,
-        threads_number: usize,
-        runs_number: usize,
-    ) -> Self {
+    pub fn get_statistics(logs: &'l [Vec<RunLog>]) -> Self {
         let tagged_stats = logs
             .iter()
             .map(|algorithm| {
@@ -46,183 +370,247 @@ impl<'l> Stats<'l> {
                 tag_stats
             })
             .collect();
-        Stats {
-            logs,
-            threads_number,
-            runs_number,
-            tagged_stats,
-        }
+        Stats { logs, tagged_stats }
     }
 
     /// This returns the total time summed across all runs for all experiments.
     pub fn total_times<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = u64> + 'a {
+        self.logs.iter().map(|algorithm| {
+            algorithm.iter().map(|run| run.duration).sum::<u64>() / algorithm.len().max(1) as u64
+        })
+    }
+
+    /// For each algorithm, return the (mean, standard deviation, 95% confidence interval
+    /// half width) of the runs durations.
+    pub fn total_times_deviations<'a, 'b: 'a>(
+        &'b self,
+    ) -> impl Iterator<Item = (f64, f64, f64)> + 'a {
         self.logs
             .iter()
-            .map(|algorithm| algorithm.iter().map(|run| run.duration).sum())
-            .map(move |total_runs_duration: u64| total_runs_duration / self.runs_number as u64)
+            .map(|algorithm| deviations(algorithm.iter().map(|run| run.duration)))
     }
 
-    /// This iterates on strings for html table in compare.
+    /// For each algorithm, the average time of each tag and the half width of its 95%
+    /// confidence interval.
     pub fn average_tagged_times<'a>(
         &'a self,
         tags: &'a [String],
-    ) -> impl Iterator<Item = String> + 'a {
-        self.tagged_stats.iter().map(move |algorithm| {
-            tags.iter()
-                .map(|t| {
-                    algorithm
-                        .get(t)
-                        .map(|times| {
-                            times.iter().map(|nple| nple.1).sum::<u64>() / self.runs_number as u64
-                        })
-                        .unwrap_or(0)
-                })
-                .map(|t| format!("<td>{}</td>", crate::compare::time_string(t)))
-                .collect::<String>()
-        })
+    ) -> impl Iterator<Item = Vec<(u64, f64)>> + 'a {
+        self.tagged_stats
+            .iter()
+            .zip(self.logs)
+            .map(move |(algorithm, runs)| {
+                tags.iter()
+                    .map(|t| {
+                        algorithm
+                            .get(t)
+                            .map(|times| {
+                                (
+                                    times.iter().map(|nple| nple.1).sum::<u64>()
+                                        / runs.len().max(1) as u64,
+                                    deviations(times.iter().map(|nple| nple.1)).2,
+                                )
+                            })
+                            .unwrap_or((0, 0.0))
+                    })
+                    .collect()
+            })
     }
 
-    /// This iterates on strings for html table in compare.
-    pub fn median_tagged_times<'a>(
+    /// For each algorithm, the (count, duration, normalised speed) of each tag in the median run.
+    /// Normalisation happens across tags for the same algorithm.
+    pub fn median_tagged_allstats<'a>(
         &'a self,
         tags: &'a [String],
-    ) -> impl Iterator<Item = String> + 'a {
+    ) -> impl Iterator<Item = Vec<(usize, u64, f64)>> + 'a {
         self.tagged_stats.iter().map(move |algorithm| {
             tags.iter()
                 .map(|t| {
                     algorithm
                         .get(t)
-                        .map(|times| times[self.runs_number / 2].1)
-                        .unwrap_or(0)
+                        .map(|times| times[times.len() / 2])
+                        .unwrap_or((0, 0, 0.0))
                 })
-                .map(|t| format!("<td>{}</td>", crate::compare::time_string(t)))
-                .collect::<String>()
+                .collect()
         })
     }
 
-    pub fn median_tagged_counts<'a>(
+    /// For each algorithm, the work of each tag recorded by each thread in the median run
+    /// (see `RunLog::threads_works`).
+    pub fn median_tagged_threads_works<'a>(
         &'a self,
         tags: &'a [String],
-    ) -> impl Iterator<Item = String> + 'a {
-        self.tagged_stats.iter().map(move |algorithm| {
+    ) -> impl Iterator<Item = Vec<Vec<usize>>> + 'a {
+        self.logs.iter().map(move |algorithm| {
+            let mut works = median_run(algorithm)
+                .map(|run| run.threads_works())
+                .unwrap_or_default();
             tags.iter()
-                .map(|t| {
-                    algorithm
-                        .get(t)
-                        .map(|times| times[self.runs_number / 2].0)
-                        .unwrap_or(0)
-                })
-                .map(|t| format!("<td>{}</td>", t))
-                .collect::<String>()
+                .map(|tag| works.remove(tag).unwrap_or_default())
+                .collect()
         })
     }
 
-    /// Normalised speeds of each tag for median the run of each algorithm.
-    /// Normalisation happens across tags for the same algorithm.
-    pub fn median_tagged_speeds<'a>(
-        &'a self,
-        tags: &'a [String],
-    ) -> impl Iterator<Item = String> + 'a {
-        self.tagged_stats.iter().map(move |algorithm| {
-            tags.iter()
-                .map(|t| {
-                    algorithm
-                        .get(t)
-                        .map(|times| times[self.runs_number / 2].2)
-                        .unwrap_or(0.0)
-                })
-                .map(|t| format!("<td>{}</td>", t))
-                .collect::<String>()
+    /// One row of the mean statistics table for each algorithm.
+    pub fn mean_rows(&self, tags: &[String]) -> Vec<MeanRow> {
+        izip!(
+            self.total_times(),
+            self.total_times_deviations(),
+            self.average_tagged_times(tags),
+            self.idle_times(),
+            self.average_tasks_counts(),
+            self.average_steals(),
+            self.high_utilization_times()
+        )
+        .map(
+            |(
+                total_time,
+                (mean, deviation, interval),
+                tagged_times,
+                idle_time,
+                tasks_count,
+                steals,
+                high_utilization_time,
+            )| MeanRow {
+                total_time,
+                deviation,
+                confidence_interval: ((mean - interval).max(0.0) as u64, (mean + interval) as u64),
+                tagged_times,
+                idle_time,
+                tasks_count,
+                steals,
+                high_utilization_time,
+            },
+        )
+        .collect()
+    }
+
+    /// One row of the median statistics table for each algorithm.
+    pub fn median_rows(&self, tags: &[String]) -> Vec<MedianRow> {
+        izip!(
+            self.total_times_median(),
+            self.unrolled_times_median(),
+            self.median_tagged_allstats(tags),
+            self.median_tagged_threads_works(tags),
+            self.idle_times_median(),
+            self.idle_causes_median()
+        )
+        .map(
+            |(
+                total_time,
+                unrolled_time,
+                tagged_stats,
+                tagged_threads_works,
+                idle_time,
+                idle_causes,
+            )| MedianRow {
+                total_time,
+                unrolled_time,
+                tagged_stats,
+                tagged_threads_works,
+                idle_time,
+                idle_causes,
+            },
+        )
+        .collect()
+    }
+    /// Return the average number of tasks per run.
+    pub fn average_tasks_counts<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = f64> + 'a {
+        self.logs.iter().map(|algorithm| {
+            algorithm
+                .iter()
+                .map(|run| run.tasks_logs.len())
+                .sum::<usize>() as f64
+                / algorithm.len().max(1) as f64
         })
     }
 
-    /// Splits a table cell into three, to print all stats
-    pub fn median_tagged_allstats<'a>(
-        &'a self,
-        tags: &'a [String],
-    ) -> impl Iterator<Item = String> + 'a {
-        self.tagged_stats.iter().map(move |algorithm| {
-            tags.iter()
-                .map(|t| {
-                    algorithm
-                        .get(t)
-                        .map(|times| times[self.runs_number / 2])
-                        .unwrap_or((0, 0, 0.0))
-                })
-                .map(|t| {
-                    format!(
-                        "<td><table><tr><td>{}</td><td>{}</td><td>{}</td></tr></table></td>",
-                        t.0,
-                        crate::compare::time_string(t.1),
-                        t.2
-                    )
+    /// Return the average number of successful steals per run (forks whose children were
+    /// reported as stolen or moved to another thread).
+    /// Only forks are considered: join continuations (single child) also change thread
+    /// but are not steals. A fork counts at most one steal.
+    pub fn average_steals<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = f64> + 'a {
+        self.logs.iter().map(|algorithm| {
+            algorithm
+                .iter()
+                .map(|run| {
+                    run.tasks_logs
+                        .iter()
+                        .filter(|t| t.children.len() > 1)
+                        .filter(|t| {
+                            t.children.iter().any(|&c| {
+                                run.tasks_logs[c].migrated
+                                    || run.tasks_logs[c].thread_id != t.thread_id
+                            })
+                        })
+                        .count()
                 })
-                .collect::<String>()
+                .sum::<usize>() as f64
+                / algorithm.len().max(1) as f64
         })
     }
-    //    /// Return the number of succesfull steals (tasks which moved between threads).
-    //    pub fn succesfull_average_steals<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = usize> + 'a {
-    //        self.logs.iter().map(move |algorithm| {
-    //            algorithm
-    //                .iter()
-    //                .map(|run| {
-    //                    run.tasks_logs
-    //                        .iter()
-    //                        .filter(|&t| t.children.len() == 2)
-    //                        .map(|t| {
-    //                            t.children
-    //                                .iter()
-    //                                .filter(|&c| run.tasks_logs[*c].thread_id != t.thread_id)
-    //                                .count()
-    //                        })
-    //                        .sum::<usize>()
-    //                })
-    //                .sum::<usize>()
-    //                / self.runs_number
-    //        })
-    //    }
-    //
+
+    /// For each algorithm, the number of tasks created by each tag in the median run.
     pub fn tasks_split_median<'a, 'b: 'a>(
         &'b self,
         tags: &'a [String],
-    ) -> impl Iterator<Item = String> + 'a {
+    ) -> impl Iterator<Item = Vec<usize>> + 'a {
         self.logs.iter().map(move |algorithm| {
-            let count = algorithm[self.runs_number / 2].count_tasks();
+            let count = median_run(algorithm)
+                .map(|run| run.count_tasks())
+                .unwrap_or_default();
             tags.iter()
                 .map(|tag| count.get(tag.as_str()).copied().unwrap_or(0))
-                .map(|v| format!("<td>{}</td>", v))
-                .collect::<String>()
+                .collect()
         })
     }
 
+    /// For each algorithm, the number of tasks in the median run.
     pub fn get_median_task_counts<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = usize> + 'a {
         self.logs
             .iter()
-            .map(move |alg| alg[self.runs_number / 2].tasks_logs.iter().count())
+            .map(|alg| median_run(alg).map(|run| run.tasks_logs.len()).unwrap_or(0))
+    }
+
+    /// This returns the average time per run spent with at least 90% of the threads working.
+    pub fn high_utilization_times<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = u64> + 'a {
+        self.logs.iter().map(|algorithm| {
+            algorithm
+                .iter()
+                .map(|run| {
+                    let busy_windows = run
+                        .utilization_timeline(UTILIZATION_BUCKETS)
+                        .into_iter()
+                        .filter(|&u| u >= HIGH_UTILIZATION)
+                        .count() as u64;
+                    run.duration * busy_windows / UTILIZATION_BUCKETS as u64
+                })
+                .sum::<u64>()
+                / algorithm.len().max(1) as u64
+        })
     }
 
-    /// This returns the idle time summed across all runs for all experiments.
+    /// This returns the average idle time across all runs for all experiments.
     pub fn idle_times<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = u64> + 'a {
-        let tasks_times = self
-            .logs
-            .iter()
-            .map(|algorithm| {
-                algorithm
-                    .iter()
-                    .map(move |run| run.tasks_logs.iter().map(|log| log.duration()).sum::<u64>())
-                    .sum()
-            })
-            .map(move |total_tasks_times: u64| total_tasks_times / self.runs_number as u64);
-        self.total_times()
-            .zip(tasks_times)
-            .map(move |(duration, activity)| duration * self.threads_number as u64 - activity)
+        self.logs.iter().map(|algorithm| {
+            algorithm
+                .iter()
+                .map(|run| {
+                    let activity = run.tasks_logs.iter().map(|log| log.duration()).sum::<u64>();
+                    // logging overhead can make activity exceed the available time
+                    (run.duration.saturating_mul(run.threads_number as u64))
+                        .saturating_sub(activity)
+                })
+                .sum::<u64>()
+                / algorithm.len().max(1) as u64
+        })
     }
 
     /// This returns the total time for the median runs for all experiments.
     pub fn total_times_median<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = u64> + 'a {
         self.logs
             .iter()
-            .map(move |algorithm| algorithm[self.runs_number / 2].duration as u64)
+            .map(|algorithm| median_run(algorithm).map(|run| run.duration).unwrap_or(0))
     }
 
     /// This is the area of the Gantt chart of the median run of each algorithm.
@@ -232,32 +620,48 @@ impl<'l> Stats<'l> {
     /// measure.
     pub fn unrolled_times_median<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = u64> + 'a {
         self.total_times_median()
-            .map(move |algorithm_time| algorithm_time * self.threads_number as u64)
+            .zip(self.logs)
+            .map(|(algorithm_time, algorithm)| {
+                algorithm_time
+                    * median_run(algorithm)
+                        .map(|run| run.threads_number as u64)
+                        .unwrap_or(0)
+            })
     }
 
     /// This returns the idle time for the median run for all experiments.
     /// Goes deep inside the execution trace and computes the regions of inactivity for each
     /// thread, then sums it up
     pub fn idle_times_median<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = u64> + 'a {
-        self.logs.iter().map(move |algorithm| {
-            let tasks = algorithm[self.runs_number / 2].tasks_logs.clone();
+        self.logs.iter().map(|algorithm| {
+            let run = match median_run(algorithm) {
+                Some(run) => run,
+                None => return 0,
+            };
+            let threads_number = run.threads_number;
+            let tasks = &run.tasks_logs;
             // do one pass to figure out the last recorded time.
             // we need it to figure out who is idle at the end.
-            let last_time = tasks.iter().map(|t| t.end_time).max().unwrap();
-            let first_time = tasks.iter().map(|t| t.start_time).min().unwrap();
+            let (first_time, last_time) = match (
+                tasks.iter().map(|t| t.start_time).min(),
+                tasks.iter().map(|t| t.end_time).max(),
+            ) {
+                (Some(first_time), Some(last_time)) => (first_time, last_time),
+                _ => return 0,
+            };
 
             // sort everyone by time (yes i know, again).
             // we add fake tasks at the end for last idle periods.
             let mut sorted_tasks: Vec<(usize, TimeStamp, TimeStamp)> = tasks
                 .iter()
                 .map(|t| (t.thread_id, t.start_time, t.end_time))
-                .chain((0..self.threads_number).map(|i| (i, last_time, last_time + 1)))
+                .chain((0..threads_number).map(|i| (i, last_time, last_time + 1)))
                 .collect();
 
             sorted_tasks.sort_by(|t1, t2| t1.1.partial_cmp(&t2.1).unwrap());
 
             let mut previous_activities: Vec<TimeStamp> =
-                repeat(first_time).take(self.threads_number).collect();
+                repeat(first_time).take(threads_number).collect();
             let mut inactivities = 0;
 
             // replay execution, figuring out idle times
@@ -271,4 +675,14 @@ impl<'l> Stats<'l> {
             inactivities
         })
     }
+
+    /// For the median run of each experiment, split each thread's idle time into
+    /// (starvation, no available parallelism) (see `RunLog::threads_idle_causes`).
+    pub fn idle_causes_median<'a, 'b: 'a>(&'b self) -> impl Iterator<Item = Vec<(u64, u64)>> + 'a {
+        self.logs.iter().map(|algorithm| {
+            median_run(algorithm)
+                .map(|run| run.threads_idle_causes())
+                .unwrap_or_default()
+        })
+    }
 }
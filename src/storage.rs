@@ -1,56 +1,93 @@
 //! provides a `Storage` structure with O(1) WORST CASE very fast insertions.
 //! every thread has its own storage and will be the only one to write in it.
-//! however after computations end, a master thread will extract all elements
-//! from all storages. it thus requires an `UnsafeCell`.
+//! however other threads might read all elements while we are still writing
+//! (a master thread extracts all elements from all storages after computations end).
+//! each block publishes its length atomically so that readers only ever see
+//! fully written elements.
 use std::cell::UnsafeCell;
-use std::collections::LinkedList;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 
-const BLOCK_SIZE: usize = 10_000;
+/// Default number of elements in each block.
+pub(crate) const DEFAULT_BLOCK_SIZE: usize = 10_000;
 
 /// We store elements in a list of blocks.
 /// Each `Block` is a contiguous memory block.
 struct Block<T> {
-    data: Vec<T>,
-}
-
-impl<T> Default for Block<T> {
-    fn default() -> Self {
-        Block::new()
-    }
+    data: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// number of elements written (and readable)
+    len: AtomicUsize,
+    /// previous (older) block
+    next: *mut Block<T>,
 }
 
 impl<T> Block<T> {
-    /// Create a new block.
-    fn new() -> Self {
+    /// Create a new block with space for `size` elements, in front of given one.
+    fn new(size: usize, next: *mut Block<T>) -> Self {
         Block {
-            data: Vec::with_capacity(BLOCK_SIZE),
+            data: (0..size)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            len: AtomicUsize::new(0),
+            next,
         }
     }
 
     /// Add given element to block.
-    fn push(&mut self, element: T) {
-        debug_assert!(self.data.len() != BLOCK_SIZE);
-        self.data.push(element)
+    /// Only the owning thread can push.
+    fn push(&self, element: T) {
+        let len = self.len.load(Ordering::Relaxed);
+        debug_assert!(len != self.data.len());
+        unsafe { (*self.data[len].get()).as_mut_ptr().write(element) };
+        // the element is written before we publish it
+        self.len.store(len + 1, Ordering::Release)
     }
 
     /// Is there some space left.
     fn is_full(&self) -> bool {
-        self.data.len() == BLOCK_SIZE
+        self.len.load(Ordering::Relaxed) == self.data.len()
     }
 
-    /// Iterator on all elements.
+    /// Iterator on all elements written so far.
     fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T> + 'a {
-        self.data.iter()
+        let len = self.len.load(Ordering::Acquire);
+        self.data[..len]
+            .iter()
+            .map(|cell| unsafe { &*(*cell.get()).as_ptr() })
+    }
+}
+
+impl<T> Drop for Block<T> {
+    fn drop(&mut self) {
+        let len = *self.len.get_mut();
+        for cell in &mut self.data[..len] {
+            unsafe { ptr::drop_in_place(cell.get_mut().as_mut_ptr()) }
+        }
+    }
+}
+
+/// Free given block and all older ones.
+unsafe fn free_blocks<T>(mut block: *mut Block<T>) {
+    while !block.is_null() {
+        let freed = Box::from_raw(block);
+        block = freed.next;
     }
 }
 
 /// Fast structure (worst case O(1)) for pushing
 /// logs in a thread.
 pub(crate) struct Storage<T> {
-    data: UnsafeCell<LinkedList<Block<T>>>,
+    /// newest block (blocks are linked from newest to oldest)
+    head: AtomicPtr<Block<T>>,
+    /// number of elements in each block
+    block_size: usize,
+    _elements: PhantomData<Box<Block<T>>>,
 }
 
-unsafe impl<T: Sync> Sync for Storage<T> {}
+unsafe impl<T: Send> Send for Storage<T> {}
+unsafe impl<T: Send + Sync> Sync for Storage<T> {}
 
 impl<T> Default for Storage<T> {
     fn default() -> Self {
@@ -58,43 +95,78 @@ impl<T> Default for Storage<T> {
     }
 }
 
+impl<T> Drop for Storage<T> {
+    fn drop(&mut self) {
+        unsafe { free_blocks(*self.head.get_mut()) }
+    }
+}
+
 impl<T> Storage<T> {
     /// Create a new storage space.
     pub fn new() -> Self {
-        let first_block = Block::new();
-        let mut list = LinkedList::new();
-        list.push_front(first_block);
+        Storage::with_block_size(DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Create a new storage space allocating `block_size` elements at a time.
+    pub fn with_block_size(block_size: usize) -> Self {
+        assert!(block_size > 0, "blocks cannot be empty");
+        let first_block = Box::new(Block::new(block_size, ptr::null_mut()));
         Storage {
-            data: UnsafeCell::new(list),
+            head: AtomicPtr::new(Box::into_raw(first_block)),
+            block_size,
+            _elements: PhantomData,
         }
     }
 
     /// Destroy all elements (frees all block memory).
+    /// Only the owning thread can clear and no other thread should be iterating.
     pub fn clear(&self) {
-        let list = unsafe { self.data.get().as_mut() }.unwrap();
-        list.clear();
-        let first_block = Block::new();
-        list.push_front(first_block);
+        let first_block = Box::into_raw(Box::new(Block::new(self.block_size, ptr::null_mut())));
+        let old_blocks = self.head.swap(first_block, Ordering::AcqRel);
+        unsafe { free_blocks(old_blocks) }
     }
 
     /// Add given element to storage space.
+    /// Only the owning thread can push.
     pub fn push(&self, element: T) {
-        let list = unsafe { self.data.get().as_mut() }.unwrap();
-        let space_needed = list.front().unwrap().is_full();
-        if space_needed {
-            list.push_front(Block::new());
+        let mut head = self.head.load(Ordering::Relaxed);
+        if unsafe { &*head }.is_full() {
+            head = Box::into_raw(Box::new(Block::new(self.block_size, head)));
+            // the new block is initialized before we publish it
+            self.head.store(head, Ordering::Release);
         }
-        list.front_mut().unwrap().push(element)
+        unsafe { &*head }.push(element)
     }
-}
 
-impl<'a, T: 'a> Storage<T> {
     /// Iterate on all elements inside us.
-    pub fn iter(&self) -> impl Iterator<Item = &'a T> + 'a {
-        unsafe { self.data.get().as_ref() }
-            .unwrap()
+    /// Elements borrow the storage so that it cannot be dropped while we iterate.
+    /// Elements pushed concurrently might or might not be seen.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = &'a T> + 'a {
+        self.blocks().into_iter().rev().flat_map(|b| b.iter()) // blocks are stored from newest to oldest
+    }
+
+    /// Number of elements inside us.
+    pub fn len(&self) -> usize {
+        self.blocks()
             .iter()
-            .rev() // blocks are stored from newest to oldest
-            .flat_map(|b| b.iter())
+            .map(|b| b.len.load(Ordering::Acquire))
+            .sum()
+    }
+
+    /// Memory used (in bytes), including reserved but unused space.
+    pub fn memory_footprint(&self) -> usize {
+        self.blocks().len()
+            * (std::mem::size_of::<Block<T>>() + self.block_size * std::mem::size_of::<T>())
+    }
+
+    /// All blocks, from newest to oldest.
+    fn blocks(&self) -> Vec<&Block<T>> {
+        let mut blocks = Vec::new();
+        let mut block = self.head.load(Ordering::Acquire);
+        while let Some(b) = unsafe { block.as_ref() } {
+            blocks.push(b);
+            block = b.next;
+        }
+        blocks
     }
 }
@@ -1,15 +1,16 @@
 //! Small module with display related functions.
 
+use crate::compare::time_string;
 use crate::log::RunLog;
 use itertools::Itertools;
 use std::cmp::max;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::prelude::*;
-use std::io::Error;
+use std::io::{BufWriter, Error};
 use std::iter::once;
-use std::iter::repeat;
-use std::iter::repeat_with;
 use std::path::Path;
 
 /// all svg colors names used for histograms displays
@@ -17,9 +18,41 @@ pub const HISTOGRAM_COLORS: [&str; 7] = [
     "red", "blue", "green", "yellow", "purple", "brown", "orange",
 ];
 
-pub(crate) type Point = (f64, f64);
+/// how long (in ms) the animation of a full run lasts
+const ANIMATION_DURATION: u64 = 60_000;
+
+/// how many different times are displayed by the time cursor during the animation
+const TIME_READOUTS: u64 = 60;
+
+/// Escape given text for including it in html or svg, as text or attribute value.
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Quote given text as a javascript string literal.
+/// The literal contains no '<', '>' or '&' so it is also safe inside
+/// html script elements and xml CDATA sections.
+pub(crate) fn js_string(text: &str) -> String {
+    serde_json::to_string(text)
+        .expect("strings are always valid json")
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+/// A point in scene coordinates: x in nanoseconds, y in rows.
+pub type Point = (f64, f64);
 
 /// all graphics elements for one `RunLog` display.
+///
+/// Coordinates are the same for all elements: x is expressed in nanoseconds and y in abstract
+/// rows (each task is one row high).
 pub struct Scene {
     /// Each task is an animated rectangle.
     /// We also display a black rectangle underneath.
@@ -29,16 +62,24 @@ pub struct Scene {
     pub segments: Vec<(Point, Point)>,
     /// All available tags
     pub tags: Vec<String>,
+    /// Texts displayed at given positions (logs headers for example).
+    pub texts: Vec<(Point, String)>,
+    /// Instant markers: time (in nanoseconds since the start of the run) and label.
+    /// They are displayed as vertical lines on the time cursor's axis.
+    pub marks: Vec<(u64, String)>,
 }
 
 impl Scene {
-    pub fn new(logs: &RunLog) -> Self {
+    /// Create an empty scene using all tags from given log.
+    pub(crate) fn new(logs: &RunLog) -> Self {
         Scene {
             rectangles: Vec::new(),
             segments: Vec::new(),
             tags: once("_NO_TAGS_".to_string())
                 .chain(logs.tags.iter().cloned())
                 .collect(),
+            texts: Vec::new(),
+            marks: logs.marks.clone(),
         }
     }
 }
@@ -57,20 +98,27 @@ pub(crate) const COLORS: [[f32; 3]; 8] = [
 
 /// Tasks are animated as a set of rectangles.
 pub struct Rectangle {
-    /// color (rgb+alpha)
+    /// color (rgb, each component between 0 and 1)
     pub color: [f32; 3],
-    /// x coordinate
+    /// x coordinate (in nanoseconds)
     pub x: f64,
-    /// y coordinate
+    /// y coordinate (in rows)
     pub y: f64,
-    /// width
+    /// width (in nanoseconds)
     pub width: f64,
-    /// height
+    /// height (in rows)
     pub height: f64,
-    /// when animation starts and ends
+    /// when animation starts and ends (in nanoseconds since the start of the run)
     pub animation: (u64, u64),
-    /// to each tag its label and opacity
+    /// to each tag its label and opacity.
+    /// untagged information is stored under the "_NO_TAGS_" tag.
     pub information: HashMap<String, (String, f64)>,
+    /// is this a task which panicked (displayed with a red outline)
+    pub panicked: bool,
+    /// was the task still running when logs were taken (drawn hatched)
+    pub in_progress: bool,
+    /// id of the displayed task (none for idle times and other decorations)
+    pub task: Option<usize>,
 }
 
 impl Rectangle {
@@ -90,6 +138,9 @@ impl Rectangle {
             height: sizes.1,
             animation,
             information,
+            panicked: false,
+            in_progress: false,
+            task: None,
         }
     }
 }
@@ -97,44 +148,84 @@ impl Rectangle {
 /// saves a set of rectangles and edges as an animated svg file.
 /// 1 animated second is 1 milli second of run.
 pub(crate) fn write_svg_file<P: AsRef<Path>>(scene: &Scene, path: P) -> Result<(), Error> {
-    let mut file = File::create(path)?;
-    fill_svg_file(scene, &mut file)
+    let mut file = BufWriter::new(File::create(path)?);
+    write_svg(scene, &mut file)?;
+    file.flush()
+}
+
+/// writes a standalone svg document (with its xml prolog) to given writer.
+pub(crate) fn write_svg<W: Write>(scene: &Scene, writer: &mut W) -> Result<(), Error> {
+    writeln!(
+        writer,
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#
+    )?;
+    fill_svg_file(scene, writer)
+}
+
+/// Identifier for all svg elements of given scene.
+/// Several svgs can be in the same html page so ids must differ between scenes, but rendering
+/// the same scene twice should produce the same document: we hash the scene's geometry.
+fn scene_id(scene: &Scene) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for rectangle in &scene.rectangles {
+        for coordinate in &[rectangle.x, rectangle.y, rectangle.width, rectangle.height] {
+            coordinate.to_bits().hash(&mut hasher);
+        }
+        rectangle.animation.hash(&mut hasher);
+    }
+    for (start, end) in &scene.segments {
+        for coordinate in &[start.0, start.1, end.0, end.1] {
+            coordinate.to_bits().hash(&mut hasher);
+        }
+    }
+    for (position, text) in &scene.texts {
+        position.0.to_bits().hash(&mut hasher);
+        position.1.to_bits().hash(&mut hasher);
+        text.hash(&mut hasher);
+    }
+    scene.tags.hash(&mut hasher);
+    scene.marks.hash(&mut hasher);
+    hasher.finish()
 }
 
-/// fill given file with a set of rectangles and edges as an animated svg.
-pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error> {
+/// fill given writer with a set of rectangles and edges as an animated svg
+/// (without xml prolog, so it can be embedded in html).
+pub(crate) fn fill_svg_file<W: Write>(scene: &Scene, file: &mut W) -> Result<(), Error> {
     let svg_width: u32 = 1920; // this is just an aspect ratio
     let svg_height: u32 = 1080;
 
+    // an empty scene (empty log) is displayed as an empty unit square
     let xmax = scene
         .rectangles
         .iter()
         .map(|r| r.width + r.x)
         .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+        .unwrap_or(1.0);
     let ymax = scene
         .rectangles
         .iter()
         .map(|r| r.height + r.y)
         .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+        .unwrap_or(1.0);
     let xmin = scene
         .rectangles
         .iter()
         .map(|r| r.x)
+        .chain(scene.texts.iter().map(|(p, _)| p.0))
         .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+        .unwrap_or(0.0);
     let ymin = scene
         .rectangles
         .iter()
         .map(|r| r.y)
+        .chain(scene.texts.iter().map(|(p, _)| p.1))
         .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+        .unwrap_or(0.0);
 
     let xscale = f64::from(svg_width) / (xmax - xmin);
     let yscale = f64::from(svg_height) / (ymax - ymin);
 
-    let random_id: usize = rand::random();
+    let random_id = scene_id(scene);
 
     // Header
     writeln!(
@@ -143,6 +234,16 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
 <svg viewBox=\"0 0 {} {}\" version=\"1.1\" xmlns=\"http://www.w3.org/2000/svg\">",
         svg_width, svg_height,
     )?;
+    // hatching for tasks still running when logs were taken
+    writeln!(
+        file,
+        "<defs><pattern id=\"in_progress_{}\" width=\"8\" height=\"8\" patternUnits=\"userSpaceOnUse\" patternTransform=\"rotate(45)\">
+<line x1=\"0\" y1=\"0\" x2=\"0\" y2=\"8\" stroke=\"white\" stroke-width=\"3\"/>
+</pattern></defs>",
+        random_id
+    )?;
+    // everything drawn goes in a viewport group which can be zoomed and panned
+    writeln!(file, "<g id=\"viewport_{}\">", random_id)?;
     // we start by edges so they will end up below tasks
     for (start, end) in &scene.segments {
         writeln!(
@@ -151,7 +252,16 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
             (start.0 - xmin) * xscale,
             (start.1 - ymin) * yscale,
             (end.0 - xmin) * xscale,
-            (end.1 - xmin) * yscale
+            (end.1 - ymin) * yscale
+        )?;
+    }
+    for (position, text) in &scene.texts {
+        writeln!(
+            file,
+            "<text x=\"{}\" y=\"{}\" font-size=\"20\" dominant-baseline=\"hanging\">{}</text>",
+            (position.0 - xmin) * xscale,
+            (position.1 - ymin) * yscale,
+            html_escape(text)
         )?;
     }
     let min_time = scene
@@ -159,29 +269,35 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
         .iter()
         .map(|r| r.animation.0)
         .min()
-        .unwrap();
+        .unwrap_or(0);
     let max_time = scene
         .rectangles
         .iter()
         .map(|r| r.animation.1)
         .max()
-        .unwrap();
-    let total_time = max_time - min_time;
+        .unwrap_or(0);
+    // a single instantaneous task still needs a non empty timeline
+    let total_time = (max_time - min_time).max(1);
 
     for rectangle in &scene.rectangles {
         // first a black rectangle
         writeln!(
             file,
-            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"black\"/>",
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"black\"{}/>",
             (rectangle.x - xmin) * xscale,
             (rectangle.y - ymin) * yscale,
             rectangle.width * xscale,
             rectangle.height * yscale,
+            if rectangle.panicked {
+                " stroke=\"red\" stroke-width=\"4\""
+            } else {
+                ""
+            }
         )?;
     }
 
     for (tag_index, tag) in scene.tags.iter().enumerate() {
-        writeln!(file, "<g id=\"tasks_colors_{}_{}\">", random_id, tag)?;
+        writeln!(file, "<g id=\"tasks_colors_{}_{}\">", random_id, tag_index)?;
         for (index, rectangle) in scene.rectangles.iter().enumerate() {
             if let Some((label, opacity)) = rectangle.information.get(tag) {
                 // now the animated one
@@ -201,8 +317,8 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
         (rectangle.color[2] * 255.0) as u32,
         opacity,
         rectangle.width*xscale,
-        max(((start_time-min_time)*60_000) / total_time, 1),
-        max(((end_time - start_time)*60_000) / total_time, 1),
+        max(((start_time-min_time)*ANIMATION_DURATION) / total_time, 1),
+        max((end_time.saturating_sub(start_time)*ANIMATION_DURATION) / total_time, 1),
         )?;
 
                 // the labels now
@@ -219,7 +335,13 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
         )?;
                 for line in label.lines() {
                     y += 20;
-                    writeln!(file, "<text x=\"{}\" y=\"{}\">{}</text>", x + 5, y, line)?;
+                    writeln!(
+                        file,
+                        "<text x=\"{}\" y=\"{}\">{}</text>",
+                        x + 5,
+                        y,
+                        html_escape(line)
+                    )?;
                 }
                 writeln!(file, "</g>")?;
             }
@@ -228,6 +350,65 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
         writeln!(file, "</g>")?;
     }
 
+    // tasks still running are hatched once drawn
+    for rectangle in scene.rectangles.iter().filter(|r| r.in_progress) {
+        let (start_time, end_time) = rectangle.animation;
+        writeln!(
+            file,
+            "<rect x=\"{}\" y=\"{}\" width=\"0\" height=\"{}\" fill=\"url(#in_progress_{})\" pointer-events=\"none\">
+<animate attributeType=\"XML\" attributeName=\"width\" from=\"0\" to=\"{}\" begin=\"{}ms\" dur=\"{}ms\" fill=\"freeze\"/>
+</rect>",
+            (rectangle.x - xmin) * xscale,
+            (rectangle.y - ymin) * yscale,
+            rectangle.height * yscale,
+            random_id,
+            rectangle.width * xscale,
+            max(((start_time - min_time) * ANIMATION_DURATION) / total_time, 1),
+            max((end_time.saturating_sub(start_time) * ANIMATION_DURATION) / total_time, 1),
+        )?;
+    }
+
+    // a vertical time cursor moving with the animation, together with the current time
+    writeln!(
+        file,
+        "<line x1=\"0\" y1=\"0\" x2=\"0\" y2=\"{height}\" stroke=\"grey\" stroke-width=\"1.0\">
+<animate attributeType=\"XML\" attributeName=\"x1\" from=\"0\" to=\"{width}\" begin=\"0ms\" dur=\"{duration}ms\" fill=\"freeze\"/>
+<animate attributeType=\"XML\" attributeName=\"x2\" from=\"0\" to=\"{width}\" begin=\"0ms\" dur=\"{duration}ms\" fill=\"freeze\"/>
+</line>",
+        height = svg_height,
+        width = svg_width,
+        duration = ANIMATION_DURATION,
+    )?;
+    // markers are placed where the time cursor is when they are reached
+    for (time, label) in &scene.marks {
+        let x = time.saturating_sub(min_time) as f64 * f64::from(svg_width) / total_time as f64;
+        writeln!(
+            file,
+            "<line x1=\"{x}\" y1=\"0\" x2=\"{x}\" y2=\"{height}\" stroke=\"blue\" stroke-width=\"1.0\"/>
+<text x=\"{x}\" y=\"30\" font-size=\"15\" fill=\"blue\" transform=\"rotate(90 {x} 30)\">{label}</text>",
+            x = x,
+            height = svg_height,
+            label = html_escape(label),
+        )?;
+    }
+    let readout_duration = ANIMATION_DURATION / TIME_READOUTS;
+    for readout in 0..=TIME_READOUTS {
+        writeln!(
+            file,
+            "<text x=\"10\" y=\"20\" visibility=\"hidden\">{}
+<set attributeName=\"visibility\" to=\"visible\" begin=\"{}ms\" dur=\"{}\"/>
+</text>",
+            time_string(readout * total_time / TIME_READOUTS),
+            readout * readout_duration,
+            if readout == TIME_READOUTS {
+                "indefinite".to_string()
+            } else {
+                format!("{}ms", readout_duration)
+            }
+        )?;
+    }
+    writeln!(file, "</g>")?;
+
     // this part will allow to get more info on tasks by hovering over them
     writeln!(
         file,
@@ -279,14 +460,72 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
     }}
 
     function displayTags_{id}() {{
-        tags_{id}.forEach(function(tag) {{
-            document.getElementById('tasks_colors_{id}_'+tag).style.display = 'none';
+        tags_{id}.forEach(function(tag, index) {{
+            document.getElementById('tasks_colors_{id}_'+index).style.display = 'none';
         }});
-        document.getElementById('tasks_colors_{id}_'+tags_{id}[current_tag_{id}]).style.display = 'block';
+        document.getElementById('tasks_colors_{id}_'+current_tag_{id}).style.display = 'block';
         document.getElementById('tag_label_{id}').innerHTML = \"<text>\"+tags_{id}[current_tag_{id}]+\"</text>\";
     }}
 
+    // zoom with the mouse wheel around the cursor and pan by dragging.
+    // we only touch the viewBox so hovering and tags toggling keep working.
+    var svg_{id} = document.getElementById('viewport_{id}').ownerSVGElement;
+    var view_box_{id} = [0, 0, {width}, {height}];
+    var drag_start_{id} = null;
+
+    function svgPoint_{id}(event) {{
+        let point = svg_{id}.createSVGPoint();
+        point.x = event.clientX;
+        point.y = event.clientY;
+        return point.matrixTransform(svg_{id}.getScreenCTM().inverse());
+    }}
+
+    function updateViewBox_{id}() {{
+        svg_{id}.setAttribute('viewBox', view_box_{id}.join(' '));
+    }}
+
+    svg_{id}.addEventListener('wheel', (event) => {{
+        event.preventDefault();
+        let point = svgPoint_{id}(event);
+        let factor = (event.deltaY < 0) ? 0.8 : 1.25;
+        view_box_{id}[0] = point.x - (point.x - view_box_{id}[0]) * factor;
+        view_box_{id}[1] = point.y - (point.y - view_box_{id}[1]) * factor;
+        view_box_{id}[2] *= factor;
+        view_box_{id}[3] *= factor;
+        updateViewBox_{id}();
+    }}, {{ passive: false }});
+
+    svg_{id}.addEventListener('mousedown', (event) => {{
+        if (event.button === 0) {{
+            event.preventDefault();
+            drag_start_{id} = svgPoint_{id}(event);
+        }}
+    }});
+
+    window.addEventListener('mousemove', (event) => {{
+        if (drag_start_{id} !== null) {{
+            svg_{id}.classList.add('panning');
+            let point = svgPoint_{id}(event);
+            view_box_{id}[0] -= point.x - drag_start_{id}.x;
+            view_box_{id}[1] -= point.y - drag_start_{id}.y;
+            updateViewBox_{id}();
+        }}
+    }});
+
+    window.addEventListener('mouseup', () => {{
+        drag_start_{id} = null;
+        svg_{id}.classList.remove('panning');
+    }});
+
+    svg_{id}.addEventListener('dblclick', () => {{
+        view_box_{id} = [0, 0, {width}, {height}];
+        updateViewBox_{id}();
+    }});
+
     function mouseOverEffect() {{
+      if (this.ownerSVGElement.classList.contains('panning')) {{
+        return;
+      }}
       this.classList.add(\"task-highlight\");
       this.tip.style.display='block';
     }}
@@ -297,8 +536,15 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
     }}
   ]]></script>",
         tag_x = svg_width - 300,
+        width = svg_width,
+        height = svg_height,
         tag_y = 20,
-        tags = scene.tags.iter().map(|s| format!("\"{}\"", s)).join(", "),
+        // tags are displayed through innerHTML
+        tags = scene
+            .tags
+            .iter()
+            .map(|tag| js_string(&html_escape(tag)))
+            .join(", "),
         id = random_id
     )?;
 
@@ -306,56 +552,109 @@ pub(crate) fn fill_svg_file(scene: &Scene, file: &mut File) -> Result<(), Error>
     Ok(())
 }
 
-/// Display histogram for given logs set inside html file.
-pub(crate) fn histogram(
-    file: &mut File,
-    logs: &[Vec<RunLog>],
-    bars_number: usize,
-) -> Result<(), Error> {
-    let min_duration = logs
-        .iter()
-        .map(|l| l.first().map(|fl| fl.duration).unwrap())
-        .min()
-        .unwrap();
-    let max_duration = logs
-        .iter()
-        .map(|l| l.last().map(|ll| ll.duration).unwrap())
-        .max()
-        .unwrap();
+/// Count durations of each algorithm falling in each of `bars_number` ranges of equal widths
+/// between the smallest and the largest duration.
+/// Return the smallest duration, the largest duration and the counts of each algorithm.
+fn histogram_bars(durations: &[Vec<u64>], bars_number: usize) -> (u64, u64, Vec<Vec<usize>>) {
+    assert!(bars_number > 0, "histograms need at least one bar");
+    let min_duration = durations.iter().flatten().min().cloned().unwrap_or(0);
+    let max_duration = durations.iter().flatten().max().cloned().unwrap_or(0);
 
     // lets compute how many durations go in each bar
-    let mut bars: Vec<Vec<usize>> = repeat_with(|| repeat(0).take(bars_number).collect())
-        .take(logs.len())
-        .collect();
+    let mut bars: Vec<Vec<usize>> = vec![vec![0; bars_number]; durations.len()];
     let slot = (max_duration - min_duration) / bars_number as u64;
-    for (algorithm, algorithm_logs) in logs.iter().enumerate() {
-        for duration in algorithm_logs.iter().map(|l| l.duration) {
-            let mut index = if slot == 0 {
-                0 // if there is only one duration it's not really a histogram
-                  // but display it nonetheless
-            } else {
-                ((duration - min_duration) / slot) as usize
-            };
-            if index == bars_number {
-                index -= 1;
-            }
+    for (algorithm, algorithm_durations) in durations.iter().enumerate() {
+        for &duration in algorithm_durations {
+            // if there is only one duration it's not really a histogram
+            // but display it nonetheless
+            let index = (duration - min_duration)
+                .checked_div(slot)
+                // rounding down the slot can push the largest durations past the last bar
+                .map(|index| (index as usize).min(bars_number - 1))
+                .unwrap_or(0);
             bars[algorithm][index] += 1;
         }
     }
+    (min_duration, max_duration, bars)
+}
+
+/// Count the runs whose durations fall in each of `bars_number` ranges of equal widths,
+/// as (start of the range in nanoseconds, number of runs), by increasing durations.
+/// This is the data displayed by `duration_histogram`.
+///
+/// Panics if no bars are wanted.
+///
+/// # Example
+///
+/// ```
+/// use rayon_logs::prelude::*;
+/// use rayon_logs::{duration_histogram, duration_histogram_counts, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .num_threads(2)
+///     .build()
+///     .expect("building pool failed");
+/// let runs: Vec<_> = (0..10)
+///     .map(|_| pool.logging_install(|| (0..10_000u64).into_par_iter().sum::<u64>()).1)
+///     .collect();
+/// let counts = duration_histogram_counts(&runs, 5);
+/// assert_eq!(counts.len(), 5);
+/// assert_eq!(counts.iter().map(|&(_, count)| count).sum::<usize>(), 10);
+/// let shortest = runs.iter().map(|run| run.duration()).min();
+/// assert_eq!(Some(counts[0].0), shortest);
+/// assert!(counts.windows(2).all(|w| w[0].0 <= w[1].0));
+/// let mut svg = Vec::new();
+/// duration_histogram(&runs, 5, &mut svg).expect("writing svg failed");
+/// assert!(svg.starts_with(b"<svg"));
+/// ```
+pub fn duration_histogram_counts(runs: &[RunLog], bars_number: usize) -> Vec<(u64, usize)> {
+    let durations: Vec<u64> = runs.iter().map(|run| run.duration).collect();
+    let (min_duration, max_duration, bars) = histogram_bars(&[durations], bars_number);
+    let slot = (max_duration - min_duration) / bars_number as u64;
+    bars[0]
+        .iter()
+        .enumerate()
+        .map(|(index, &count)| (min_duration + index as u64 * slot, count))
+        .collect()
+}
+
+/// Write the svg histogram of the durations of given runs of a same algorithm
+/// (see `duration_histogram_counts`).
+/// This is the histogram the `Comparator` displays, without the comparator.
+pub fn duration_histogram<W: Write>(
+    runs: &[RunLog],
+    bars_number: usize,
+    writer: &mut W,
+) -> Result<(), Error> {
+    let durations: Vec<u64> = runs.iter().map(|run| run.duration).collect();
+    histogram(writer, &[durations], bars_number)
+}
+
+/// Display histogram for the durations of each algorithm inside html file.
+pub(crate) fn histogram<W: Write>(
+    file: &mut W,
+    durations: &[Vec<u64>],
+    bars_number: usize,
+) -> Result<(), Error> {
+    let (min_duration, max_duration, bars) = histogram_bars(durations, bars_number);
 
     // now, just draw one rectangle for each bar
     let width = 1920;
     let height = 1080;
-    write!(file, "<svg viewBox=\"0 0 {} {}\">", width, height)?;
+    write!(
+        file,
+        "<svg viewBox=\"0 0 {} {}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        width, height
+    )?;
     write!(
         file,
         "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
         width, height
     )?;
-    let max_count = bars.iter().flat_map(|b| b.iter()).max().unwrap();
-    let unit_height = (height - 100) as f32 / *max_count as f32;
+    let max_count = bars.iter().flatten().max().cloned().unwrap_or(0);
+    let unit_height = (height - 100) as f32 / max_count.max(1) as f32;
     let unit_width = width as f32 / bars_number as f32;
-    let algorithms_number = logs.len() as f32;
+    let algorithms_number = durations.len() as f32;
     for (algorithm_index, (counts, color)) in
         bars.iter().zip(HISTOGRAM_COLORS.iter().cycle()).enumerate()
     {
@@ -397,3 +696,194 @@ pub(crate) fn histogram(
     write!(file, "</svg>")?;
     Ok(())
 }
+
+/// Display box plots (one per algorithm, stacked vertically) inside html file.
+/// Each box plot is given as (lower whisker, q1, median, q3, upper whisker) and outliers.
+pub(crate) fn box_plots<W: Write>(
+    file: &mut W,
+    boxes: &[([f64; 5], Vec<u64>)],
+) -> Result<(), Error> {
+    let width = 1920.0;
+    let row_height = 100.0;
+    let margin = 100.0;
+    let height = row_height * boxes.len() as f64 + margin;
+    let min_duration = boxes
+        .iter()
+        .map(|(b, o)| o.iter().map(|d| *d as f64).fold(b[0], f64::min))
+        .fold(f64::INFINITY, f64::min);
+    let max_duration = boxes
+        .iter()
+        .map(|(b, o)| o.iter().map(|d| *d as f64).fold(b[4], f64::max))
+        .fold(0.0, f64::max);
+    let x = |duration: f64| {
+        if max_duration > min_duration {
+            margin
+                + (duration - min_duration) / (max_duration - min_duration) * (width - 2.0 * margin)
+        } else {
+            width / 2.0
+        }
+    };
+    write!(file, "<svg viewBox=\"0 0 {} {}\">", width, height)?;
+    write!(
+        file,
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
+        width, height
+    )?;
+    for (index, ((b, outliers), color)) in boxes
+        .iter()
+        .zip(HISTOGRAM_COLORS.iter().cycle())
+        .enumerate()
+    {
+        let middle = row_height * (index as f64 + 0.5);
+        // whiskers
+        write!(
+            file,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\"/>",
+            x(b[0]),
+            middle,
+            x(b[4]),
+            middle
+        )?;
+        for &whisker in &[b[0], b[4]] {
+            write!(
+                file,
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\"/>",
+                x(whisker),
+                middle - row_height / 4.0,
+                x(whisker),
+                middle + row_height / 4.0
+            )?;
+        }
+        // the box and the median
+        write!(
+            file,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"black\"/>",
+            x(b[1]),
+            middle - row_height / 3.0,
+            x(b[3]) - x(b[1]),
+            row_height * 2.0 / 3.0,
+            color
+        )?;
+        write!(
+            file,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"3\"/>",
+            x(b[2]),
+            middle - row_height / 3.0,
+            x(b[2]),
+            middle + row_height / 3.0
+        )?;
+        for outlier in outliers {
+            write!(
+                file,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"5\" fill=\"none\" stroke=\"{}\"/>",
+                x(*outlier as f64),
+                middle,
+                color
+            )?;
+        }
+    }
+    write!(
+        file,
+        "<text x=\"{}\" y=\"{}\">{}</text><text x=\"{}\" y=\"{}\">{}</text>",
+        margin,
+        height - margin / 2.0,
+        time_string(min_duration as u64),
+        width - margin,
+        height - margin / 2.0,
+        time_string(max_duration as u64)
+    )?;
+    write!(file, "</svg>")?;
+    Ok(())
+}
+
+/// Display speedups curves (one polyline per algorithm) inside html file.
+/// The ideal speedup is displayed as a dashed line.
+/// Algorithms without speedups are skipped (but keep their color).
+pub(crate) fn speedup_chart<W: Write>(
+    file: &mut W,
+    threads_numbers: &[usize],
+    speedups: &[Option<Vec<f64>>],
+) -> Result<(), Error> {
+    let width = 1920.0;
+    let height = 1080.0;
+    let margin = 100.0;
+    let min_threads = threads_numbers.iter().cloned().min().unwrap() as f64;
+    let max_threads = threads_numbers.iter().cloned().max().unwrap() as f64;
+    let ideal_speedup = max_threads / min_threads;
+    let max_speedup = speedups
+        .iter()
+        .flatten()
+        .flatten()
+        .cloned()
+        .chain(once(ideal_speedup))
+        .fold(1.0, f64::max);
+    let x = |threads: f64| {
+        if max_threads > min_threads {
+            margin + (threads - min_threads) / (max_threads - min_threads) * (width - 2.0 * margin)
+        } else {
+            width / 2.0
+        }
+    };
+    let y = |speedup: f64| height - margin - speedup / max_speedup * (height - 2.0 * margin);
+
+    write!(file, "<svg viewBox=\"0 0 {} {}\">", width, height)?;
+    write!(
+        file,
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
+        width, height
+    )?;
+    // axes
+    write!(
+        file,
+        "<polyline points=\"{},{} {},{} {},{}\" fill=\"none\" stroke=\"black\"/>",
+        margin,
+        margin,
+        margin,
+        height - margin,
+        width - margin,
+        height - margin
+    )?;
+    for &threads in threads_numbers {
+        write!(
+            file,
+            "<text x=\"{}\" y=\"{}\">{}</text>",
+            x(threads as f64),
+            height - margin / 2.0,
+            threads
+        )?;
+    }
+    write!(
+        file,
+        "<text x=\"10\" y=\"{}\">{:.1}</text><text x=\"10\" y=\"{}\">1</text>",
+        y(max_speedup),
+        max_speedup,
+        y(1.0)
+    )?;
+    // ideal speedup
+    write!(
+        file,
+        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"grey\" stroke-dasharray=\"10,10\"/>",
+        x(min_threads),
+        y(1.0),
+        x(max_threads),
+        y(ideal_speedup)
+    )?;
+    for (algorithm_speedups, color) in speedups.iter().zip(HISTOGRAM_COLORS.iter().cycle()) {
+        let algorithm_speedups = match algorithm_speedups {
+            Some(speedups) => speedups,
+            None => continue,
+        };
+        write!(
+            file,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"3\"/>",
+            threads_numbers
+                .iter()
+                .zip(algorithm_speedups)
+                .map(|(&threads, &speedup)| format!("{},{}", x(threads as f64), y(speedup)))
+                .join(" "),
+            color
+        )?;
+    }
+    write!(file, "</svg>")?;
+    Ok(())
+}
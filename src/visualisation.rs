@@ -0,0 +1,39 @@
+//! Graphical layout of logs, for writing your own renderers.
+//!
+//! A `Scene` contains all rectangles (tasks and idle times) and segments (dependencies)
+//! computed for displaying a `RunLog`. All coordinates are expressed with x in nanoseconds
+//! and y in abstract rows (each task is one row high), so you only need to scale them.
+//!
+//! Example:
+//!
+//! ```
+//! use rayon_logs::prelude::*;
+//! use rayon_logs::ThreadPoolBuilder;
+//!
+//! let v: Vec<u32> = (0..100_000).collect();
+//! let pool = ThreadPoolBuilder::new()
+//!     .num_threads(2)
+//!     .build()
+//!     .expect("building pool failed");
+//! let (_, log) = pool.logging_install(|| v.par_iter().max());
+//! let scene = log.scene();
+//! for rectangle in &scene.rectangles {
+//!     let (start, end) = rectangle.animation;
+//!     assert!(start <= end);
+//!     assert!(rectangle.width >= 0.0);
+//! }
+//! // each task is displayed by exactly one rectangle
+//! let mut tasks: Vec<usize> = scene.rectangles.iter().filter_map(|r| r.task).collect();
+//! tasks.sort();
+//! assert_eq!(tasks, (0..log.tasks().len()).collect::<Vec<_>>());
+//!
+//! // several logs are stacked, nothing to display without logs
+//! let stacked = rayon_logs::visualisation::visualisation_many(&[log.clone(), log]);
+//! assert_eq!(stacked.texts.len(), 2);
+//! let empty = rayon_logs::visualisation::visualisation_many(&[]);
+//! assert!(empty.rectangles.is_empty() && empty.texts.is_empty());
+//! let mut svg = Vec::new();
+//! rayon_logs::RunLog::write_svg_many(&[], &mut svg).expect("writing svg failed");
+//! ```
+pub use crate::fork_join_graph::{visualisation, visualisation_many};
+pub use crate::svg::{Point, Rectangle, Scene};